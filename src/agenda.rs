@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate};
+use colored::{Color, Colorize};
+
+use crate::error::TedoError;
+use crate::storage;
+use crate::storage::{Project, Task, TedoState};
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Section {
+    Overdue,
+    Today,
+    ThisWeek,
+    Scheduled,
+}
+
+impl Section {
+    fn title(&self) -> &'static str {
+        match self {
+            Section::Overdue => "Overdue",
+            Section::Today => "Due today",
+            Section::ThisWeek => "Due this week",
+            Section::Scheduled => "Scheduled",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Section::Overdue => Color::Red,
+            Section::Today => Color::Yellow,
+            Section::ThisWeek => Color::Cyan,
+            Section::Scheduled => Color::Blue,
+        }
+    }
+}
+
+
+// A task with the date it is listed under
+pub type Entry<'a> = (&'a Project, &'a Task, NaiveDate);
+
+
+// Open tasks by section, each sorted by date. "This week" is the six days
+// after today. A task that is due lands in its due section only; the rest
+// show up under scheduled when planned for this week or earlier.
+pub fn agenda(tedo_state: &TedoState, today: NaiveDate) -> Vec<(Section, Vec<Entry<'_>>)> {
+    let week_end = today + Duration::days(6);
+    let mut sections: Vec<(Section, Vec<Entry>)> = [Section::Overdue, Section::Today, Section::ThisWeek, Section::Scheduled]
+        .into_iter()
+        .map(|section| (section, Vec::new()))
+        .collect();
+
+    let tasks = tedo_state.projects.iter()
+        .flat_map(|p| p.tasks.iter().map(move |t| (p, t)))
+        .filter(|(_, t)| !t.is_done() && !t.is_template());
+    for (project, task) in tasks {
+        let entry = match (task.due, task.scheduled) {
+            (Some(due), _) if due < today => (Section::Overdue, due),
+            (Some(due), _) if due == today => (Section::Today, due),
+            (Some(due), _) if due <= week_end => (Section::ThisWeek, due),
+            (_, Some(scheduled)) if scheduled <= week_end => (Section::Scheduled, scheduled),
+            _ => continue,
+        };
+        if let Some((_, tasks)) = sections.iter_mut().find(|(section, _)| *section == entry.0) {
+            tasks.push((project, task, entry.1));
+        }
+    }
+
+    for (_, tasks) in &mut sections {
+        tasks.sort_by_key(|(project, task, date)| (*date, project.id, task.id));
+    }
+    sections.retain(|(_, tasks)| !tasks.is_empty());
+    sections
+}
+
+
+pub fn show_agenda(base_dir: &Path, project: Option<&str>, today: NaiveDate) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    tedo_state.narrow_to_subtree(project)?;
+
+    let sections = agenda(&tedo_state, today);
+    if sections.is_empty() {
+        println!("Nothing due or scheduled this week.");
+        return Ok(());
+    }
+    for (index, (section, tasks)) in sections.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!("{}", format!("{} ({})", section.title(), tasks.len()).color(section.color()).bold());
+        for (project, task, date) in tasks {
+            let date = date.format("%a %Y-%m-%d").to_string();
+            println!("  {}  {}:{}  {}", date.color(section.color()), project.name, task.id, task.description);
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::projects::create_project;
+    use crate::tasks::{add_task, close_task, create_task};
+
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_agenda_sections() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        let today = date("2026-10-21");
+
+        create_project(base_dir, "ops", true).unwrap();
+        create_project(base_dir, "growth", false).unwrap();
+        for (description, due) in [("Late", "2026-10-20"), ("Now", "2026-10-21"), ("Soon", "2026-10-27"), ("Later", "2026-10-28"), ("Done", "2026-10-19")] {
+            add_task(base_dir, None, Task { due: Some(date(due)), ..Task::new(0, description) }).unwrap();
+        }
+        close_task(base_dir, None, 5, false).unwrap();
+        let planned = create_task(base_dir, Some("growth"), "Planned", None).unwrap();
+        let mut state = storage::load_state(base_dir).unwrap();
+        state.projects[1].task_mut(planned).unwrap().scheduled = Some(date("2026-10-22"));
+        state.projects[0].task_mut(4).unwrap().scheduled = Some(date("2026-10-22"));
+
+        let sections: Vec<(Section, Vec<&str>)> = agenda(&state, today).into_iter()
+            .map(|(section, tasks)| (section, tasks.iter().map(|(_, t, _)| t.description.as_str()).collect()))
+            .collect();
+        assert_eq!(sections, vec![
+            (Section::Overdue, vec!["Late"]),
+            (Section::Today, vec!["Now"]),
+            (Section::ThisWeek, vec!["Soon"]),
+            (Section::Scheduled, vec!["Later", "Planned"]),
+        ]);
+    }
+}
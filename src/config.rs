@@ -0,0 +1,175 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::TedoError;
+
+
+// Machine-local settings, kept apart from the state so they are never shared
+// along with projects, tasks and notes.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Config {
+    // Command used to edit notes, e.g. "code --wait". Falls back to $VISUAL,
+    // then $EDITOR.
+    pub editor: Option<String>,
+    // Where note content lives: inline in the state file, or as Markdown
+    // files under ~/.tedo/notes
+    #[serde(default)]
+    pub note_storage: NoteStorage,
+    // How many revisions to keep per note, 0 keeps all of them
+    pub note_revisions: Option<usize>,
+    // Commit every change to ~/.tedo to a git repository
+    #[serde(default)]
+    pub git: bool,
+    // Repository `tedo sync` pulls from and pushes to
+    pub git_remote: Option<String>,
+}
+
+
+static DEFAULT_NOTE_REVISIONS: usize = 50;
+
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteStorage {
+    #[default]
+    State,
+    Files,
+}
+
+
+static KEYS: [&str; 5] = ["editor", "note_storage", "note_revisions", "git", "git_remote"];
+
+
+impl Config {
+    pub fn revision_limit(&self) -> usize {
+        self.note_revisions.unwrap_or(DEFAULT_NOTE_REVISIONS)
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>, TedoError> {
+        match key {
+            "editor" => Ok(self.editor.clone()),
+            "note_storage" => Ok(Some(match self.note_storage {
+                NoteStorage::State => "state".to_string(),
+                NoteStorage::Files => "files".to_string(),
+            })),
+            "note_revisions" => Ok(Some(self.revision_limit().to_string())),
+            "git" => Ok(Some(self.git.to_string())),
+            "git_remote" => Ok(self.git_remote.clone()),
+            _ => Err(unknown_key(key)),
+        }
+    }
+
+    // An empty value resets the key to its default
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), TedoError> {
+        let value = if value.is_empty() { None } else { Some(value.to_string()) };
+        match key {
+            "editor" => self.editor = value,
+            "note_storage" => {
+                self.note_storage = match value.as_deref() {
+                    None | Some("state") => NoteStorage::State,
+                    Some("files") => NoteStorage::Files,
+                    Some(value) => return Err(TedoError::InvalidInput(format!("Unknown note storage {}, use state or files", value))),
+                }
+            }
+            "note_revisions" => {
+                self.note_revisions = value.map(|value| value.parse::<usize>()).transpose()
+                    .map_err(|_| TedoError::InvalidInput("note_revisions must be a number, 0 keeps all revisions".into()))?;
+            }
+            "git" => {
+                self.git = match value.as_deref() {
+                    None | Some("false") | Some("off") => false,
+                    Some("true") | Some("on") => true,
+                    Some(value) => return Err(TedoError::InvalidInput(format!("Invalid value {} for git, use true or false", value))),
+                }
+            }
+            "git_remote" => self.git_remote = value,
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+}
+
+
+fn unknown_key(key: &str) -> TedoError {
+    TedoError::InvalidInput(format!("Unknown config key {}, known keys are: {}", key, KEYS.join(", ")))
+}
+
+
+fn get_config_path(base_dir: &Path) -> PathBuf {
+    base_dir.join("config.toml")
+}
+
+
+pub fn load_config(base_dir: &Path) -> Result<Config, TedoError> {
+    match fs::read_to_string(get_config_path(base_dir)) {
+        Ok(data) => toml::from_str(&data)
+            .map_err(|error| TedoError::StorageCorrupt(format!("config.toml: {}", error))),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Config::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+
+pub fn save_config(base_dir: &Path, config: &Config) -> Result<(), TedoError> {
+    fs::write(get_config_path(base_dir), toml::to_string(config)?)?;
+    Ok(())
+}
+
+
+// `tedo config` lists all keys, `tedo config <key>` prints one and
+// `tedo config <key> <value>` changes it.
+pub fn configure(base_dir: &Path, key: Option<&str>, value: Option<&str>) -> Result<(), TedoError> {
+    let mut config = load_config(base_dir)?;
+    match (key, value) {
+        (None, _) => {
+            for key in KEYS {
+                println!("{} = {}", key, config.get(key)?.unwrap_or_default());
+            }
+        }
+        (Some(key), None) => println!("{}", config.get(key)?.unwrap_or_default()),
+        (Some(key), Some(value)) => {
+            config.set(key, value)?;
+            save_config(base_dir, &config)?;
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_set_and_load_config() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        assert!(load_config(base_dir).unwrap().editor.is_none());
+
+        configure(base_dir, Some("editor"), Some("code --wait")).unwrap();
+        assert_eq!(load_config(base_dir).unwrap().editor.unwrap(), "code --wait");
+
+        configure(base_dir, Some("editor"), Some("")).unwrap();
+        assert!(load_config(base_dir).unwrap().editor.is_none());
+
+        assert!(matches!(configure(base_dir, Some("colour"), Some("red")), Err(TedoError::InvalidInput(_))));
+        assert!(matches!(configure(base_dir, Some("note_storage"), Some("cloud")), Err(TedoError::InvalidInput(_))));
+
+        configure(base_dir, Some("note_storage"), Some("files")).unwrap();
+        assert_eq!(load_config(base_dir).unwrap().revision_limit(), DEFAULT_NOTE_REVISIONS);
+        configure(base_dir, Some("note_revisions"), Some("5")).unwrap();
+        assert!(configure(base_dir, Some("note_revisions"), Some("many")).is_err());
+        assert_eq!(load_config(base_dir).unwrap().revision_limit(), 5);
+        assert_eq!(load_config(base_dir).unwrap().note_storage, NoteStorage::Files);
+
+        configure(base_dir, Some("git"), Some("on")).unwrap();
+        assert!(load_config(base_dir).unwrap().git);
+        assert!(configure(base_dir, Some("git"), Some("maybe")).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+use crate::error::TedoError;
+
+
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+
+// Accepts `YYYY-MM-DD`, `today`, `yesterday`, `tomorrow` and weekday names.
+// A weekday means the most recent one, today included, so `--since monday`
+// covers the current week.
+pub fn parse_date(text: &str, today: NaiveDate) -> Result<NaiveDate, TedoError> {
+    let text = text.trim().to_lowercase();
+    match text.as_str() {
+        "today" => return Ok(today),
+        "yesterday" => return Ok(today - Duration::days(1)),
+        "tomorrow" => return Ok(today + Duration::days(1)),
+        _ => {}
+    }
+
+    if let Ok(weekday) = text.parse::<Weekday>() {
+        let days_back = (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+        return Ok(today - Duration::days(days_back as i64));
+    }
+
+    NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+        .map_err(|_| TedoError::InvalidInput(format!("Invalid date {}, use YYYY-MM-DD, today, yesterday or a weekday", text)))
+}
+
+
+// Like `parse_date`, but looking ahead: a weekday means the next one, today
+// included, so `--due friday` is this week's Friday.
+pub fn parse_due_date(text: &str, today: NaiveDate) -> Result<NaiveDate, TedoError> {
+    if let Ok(weekday) = text.trim().to_lowercase().parse::<Weekday>() {
+        let days_ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+        return Ok(today + Duration::days(days_ahead as i64));
+    }
+    parse_date(text, today)
+}
+
+
+// A date as for `parse_due_date` followed by an optional `HH:MM`, or just a
+// time for today. Without a time it means 9 in the morning.
+pub fn parse_datetime(text: &str, now: NaiveDateTime) -> Result<NaiveDateTime, TedoError> {
+    let text = text.trim();
+    let (date, time) = match text.rsplit_once(' ').map_or(("", text), |(date, time)| (date, time)) {
+        (date, time) if NaiveTime::parse_from_str(time, "%H:%M").is_ok() => (date.trim(), time),
+        _ => (text, "09:00"),
+    };
+    let time = NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| TedoError::InvalidInput(format!("Invalid time {}, use HH:MM", time)))?;
+    let date = if date.is_empty() { now.date() } else { parse_due_date(date, now.date())? };
+    Ok(date.and_time(time))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        // A Wednesday
+        let today = NaiveDate::from_ymd_opt(2026, 10, 21).unwrap();
+
+        assert_eq!(parse_date("today", today).unwrap(), today);
+        assert_eq!(parse_date("Yesterday", today).unwrap(), NaiveDate::from_ymd_opt(2026, 10, 20).unwrap());
+        assert_eq!(parse_date("tomorrow", today).unwrap(), NaiveDate::from_ymd_opt(2026, 10, 22).unwrap());
+        assert_eq!(parse_date("monday", today).unwrap(), NaiveDate::from_ymd_opt(2026, 10, 19).unwrap());
+        assert_eq!(parse_date("wed", today).unwrap(), today);
+        assert_eq!(parse_date("thursday", today).unwrap(), NaiveDate::from_ymd_opt(2026, 10, 15).unwrap());
+        assert_eq!(parse_date("2026-01-31", today).unwrap(), NaiveDate::from_ymd_opt(2026, 1, 31).unwrap());
+        assert!(parse_date("31-01-2026", today).is_err());
+    }
+
+    #[test]
+    fn test_parse_due_date() {
+        // A Wednesday
+        let today = NaiveDate::from_ymd_opt(2026, 10, 21).unwrap();
+
+        assert_eq!(parse_due_date("friday", today).unwrap(), NaiveDate::from_ymd_opt(2026, 10, 23).unwrap());
+        assert_eq!(parse_due_date("wed", today).unwrap(), today);
+        assert_eq!(parse_due_date("monday", today).unwrap(), NaiveDate::from_ymd_opt(2026, 10, 26).unwrap());
+        assert_eq!(parse_due_date("tomorrow", today).unwrap(), NaiveDate::from_ymd_opt(2026, 10, 22).unwrap());
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        let now = NaiveDate::from_ymd_opt(2026, 10, 21).unwrap().and_hms_opt(15, 30, 0).unwrap();
+        let at = |text: &str| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+
+        assert_eq!(parse_datetime("17:00", now).unwrap(), at("2026-10-21 17:00"));
+        assert_eq!(parse_datetime("tomorrow 8:15", now).unwrap(), at("2026-10-22 08:15"));
+        assert_eq!(parse_datetime("2026-11-02 14:00", now).unwrap(), at("2026-11-02 14:00"));
+        assert_eq!(parse_datetime("friday", now).unwrap(), at("2026-10-23 09:00"));
+        assert!(parse_datetime("tomorrow 25:00", now).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use std::path::Path;
+
+use crate::error::TedoError;
+use crate::storage;
+use crate::storage::{save_state, Task, TaskRef, TedoState};
+
+
+impl TedoState {
+    pub fn find_task(&self, task_ref: TaskRef) -> Option<&Task> {
+        self.projects.iter()
+            .find(|p| p.id == task_ref.project)
+            .and_then(|p| p.tasks.iter().find(|t| t.id == task_ref.task))
+    }
+
+    // Parses `12` (a task in the given project) or `<project>:12`, where the
+    // project is anything `project_index` accepts.
+    pub fn parse_task_ref(&self, reference: &str, project_index: usize) -> Result<TaskRef, TedoError> {
+        let (project_index, task) = match reference.rsplit_once(':') {
+            Some((project, task)) => (self.project_index(project)?, task),
+            None => (project_index, reference),
+        };
+        let task = task.parse::<u32>()
+            .map_err(|_| TedoError::InvalidInput(format!("Invalid task identifier {}", reference)))?;
+
+        let task_ref = TaskRef { project: self.projects[project_index].id, task };
+        self.find_task(task_ref)
+            .ok_or_else(|| TedoError::NotFound(format!("Task {} not found.", reference)))?;
+        Ok(task_ref)
+    }
+
+    // Prerequisites of a task that are still open. Prerequisites that no longer
+    // exist don't block anything.
+    pub fn open_prerequisites(&self, task_ref: TaskRef) -> Vec<TaskRef> {
+        match self.find_task(task_ref) {
+            Some(task) => task.depends_on.iter()
+                .filter(|prerequisite| self.find_task(**prerequisite).is_some_and(|t| !t.is_done()))
+                .copied()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn is_blocked(&self, task_ref: TaskRef) -> bool {
+        !self.open_prerequisites(task_ref).is_empty()
+    }
+
+    // Ids of the blocked tasks in a project
+    pub fn blocked_tasks(&self, project_index: usize) -> Vec<u32> {
+        let project = &self.projects[project_index];
+        project.tasks.iter()
+            .filter(|t| self.is_blocked(TaskRef { project: project.id, task: t.id }))
+            .map(|t| t.id)
+            .collect()
+    }
+
+    // Whether `from` depends on `to`, directly or through other tasks
+    fn depends_on(&self, from: TaskRef, to: TaskRef) -> bool {
+        let mut visited: Vec<TaskRef> = Vec::new();
+        let mut pending = vec![from];
+        while let Some(current) = pending.pop() {
+            if current == to {
+                return true;
+            }
+            if visited.contains(&current) {
+                continue;
+            }
+            visited.push(current);
+            if let Some(task) = self.find_task(current) {
+                pending.extend(task.depends_on.iter().copied());
+            }
+        }
+        false
+    }
+}
+
+
+pub fn add_dependency(base_dir: &Path, project: Option<&str>, task_id: u32, prerequisite: &str) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project_index = tedo_state.scoped_project_index(project)?;
+
+    let task_ref = tedo_state.parse_task_ref(&task_id.to_string(), project_index)?;
+    let prerequisite = tedo_state.parse_task_ref(prerequisite, project_index)?;
+
+    if task_ref == prerequisite || tedo_state.depends_on(prerequisite, task_ref) {
+        return Err(TedoError::InvalidInput(format!(
+            "Task {} cannot depend on {}:{}, that would create a dependency cycle",
+            task_id, prerequisite.project, prerequisite.task
+        )));
+    }
+
+    let task = tedo_state.projects[project_index].task_mut(task_id)?;
+    if !task.depends_on.contains(&prerequisite) {
+        task.depends_on.push(prerequisite);
+    }
+    save_state(base_dir, &tedo_state)
+}
+
+
+pub fn remove_dependency(base_dir: &Path, project: Option<&str>, task_id: u32, prerequisite: &str) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project_index = tedo_state.scoped_project_index(project)?;
+    let prerequisite = tedo_state.parse_task_ref(prerequisite, project_index)?;
+
+    let task = tedo_state.projects[project_index].task_mut(task_id)?;
+    let count = task.depends_on.len();
+    task.depends_on.retain(|t| *t != prerequisite);
+    if task.depends_on.len() == count {
+        return Err(TedoError::NotFound(format!(
+            "Task {} does not depend on {}:{}", task_id, prerequisite.project, prerequisite.task
+        )));
+    }
+    save_state(base_dir, &tedo_state)
+}
+
+
+// Open tasks that can be worked on right now: not blocked by a prerequisite
+// and without open subtasks of their own.
+pub fn next_tasks(tedo_state: &TedoState, project_index: usize) -> Vec<&Task> {
+    let project = &tedo_state.projects[project_index];
+    let blocked = tedo_state.blocked_tasks(project_index);
+    project.task_tree().into_iter()
+        .map(|(_, task)| task)
+        .filter(|t| !t.is_done() && !blocked.contains(&t.id))
+        .filter(|t| project.children(t.id).iter().all(|child| child.is_done()))
+        .collect()
+}
+
+
+pub fn list_next_tasks(base_dir: &Path, project: Option<&str>, all: bool) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+
+    let project_indices: Vec<usize> = if all {
+        (0..tedo_state.projects.len()).collect()
+    } else {
+        vec![tedo_state.scoped_project_index(project)?]
+    };
+
+    for project_index in project_indices {
+        for task in next_tasks(&tedo_state, project_index) {
+            if all {
+                println!("{}\t{}\t{}", tedo_state.projects[project_index].name, task.id, task.description);
+            } else {
+                println!("{} {}", task.id, task.description);
+            }
+        }
+    }
+    Ok(())
+}
+
+
+// Renders the dependency graph of the given projects as Graphviz DOT or
+// Mermaid. Prerequisites in other projects are included so that no edge
+// points at a missing node.
+pub fn graph(tedo_state: &TedoState, project_indices: &[usize], format: &str) -> Result<String, TedoError> {
+    let mut nodes: Vec<TaskRef> = Vec::new();
+    let mut edges: Vec<(TaskRef, TaskRef)> = Vec::new();
+
+    for &project_index in project_indices {
+        let project = &tedo_state.projects[project_index];
+        for task in &project.tasks {
+            let task_ref = TaskRef { project: project.id, task: task.id };
+            if !nodes.contains(&task_ref) {
+                nodes.push(task_ref);
+            }
+            for prerequisite in &task.depends_on {
+                if tedo_state.find_task(*prerequisite).is_none() {
+                    continue;
+                }
+                if !nodes.contains(prerequisite) {
+                    nodes.push(*prerequisite);
+                }
+                edges.push((*prerequisite, task_ref));
+            }
+        }
+    }
+
+    let node_id = |task_ref: &TaskRef| format!("p{}_t{}", task_ref.project, task_ref.task);
+    let label = |task_ref: &TaskRef| {
+        let task = tedo_state.find_task(*task_ref).unwrap();
+        format!("{}:{} {}", task_ref.project, task.id, task.description)
+    };
+
+    let mut output = String::new();
+    match format {
+        "dot" => {
+            output.push_str("digraph tedo {\n    rankdir=LR;\n");
+            for node in &nodes {
+                let task = tedo_state.find_task(*node).unwrap();
+                let style = if task.is_done() {
+                    ", style=filled, fillcolor=lightgray"
+                } else if tedo_state.is_blocked(*node) {
+                    ", color=red"
+                } else {
+                    ""
+                };
+                output.push_str(&format!("    {} [label=\"{}\"{}];\n", node_id(node), label(node).replace('"', "\\\""), style));
+            }
+            for (from, to) in &edges {
+                output.push_str(&format!("    {} -> {};\n", node_id(from), node_id(to)));
+            }
+            output.push_str("}\n");
+        }
+        "mermaid" => {
+            output.push_str("flowchart LR\n");
+            for node in &nodes {
+                output.push_str(&format!("    {}[\"{}\"]\n", node_id(node), label(node).replace('"', "#quot;")));
+            }
+            for (from, to) in &edges {
+                output.push_str(&format!("    {} --> {}\n", node_id(from), node_id(to)));
+            }
+        }
+        _ => return Err(TedoError::InvalidInput(format!("Unknown graph format {}, use dot or mermaid", format))),
+    }
+    Ok(output)
+}
+
+
+pub fn print_graph(base_dir: &Path, project: Option<&str>, all: bool, format: &str) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project_indices: Vec<usize> = if all {
+        (0..tedo_state.projects.len()).collect()
+    } else {
+        vec![tedo_state.scoped_project_index(project)?]
+    };
+    print!("{}", graph(&tedo_state, &project_indices, format)?);
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::projects::create_project;
+    use crate::tasks::{close_task, create_task};
+
+    use super::*;
+
+    fn setup(base_dir: &Path) {
+        create_project(base_dir, "first", true).unwrap();
+        create_project(base_dir, "second", false).unwrap();
+        create_task(base_dir, None, "design", None).unwrap();
+        create_task(base_dir, None, "build", None).unwrap();
+        create_task(base_dir, None, "ship", None).unwrap();
+        create_task(base_dir, Some("second"), "approve", None).unwrap();
+    }
+
+    #[test]
+    fn test_blocked_until_prerequisites_are_done() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+
+        add_dependency(base_dir, None, 2, "1").unwrap();
+        add_dependency(base_dir, None, 3, "second:1").unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.blocked_tasks(0), vec![2, 3]);
+        let next: Vec<u32> = next_tasks(&state, 0).iter().map(|t| t.id).collect();
+        assert_eq!(next, vec![1]);
+
+        close_task(base_dir, None, 1, false).unwrap();
+        close_task(base_dir, Some("second"), 1, false).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert!(state.blocked_tasks(0).is_empty());
+    }
+
+    #[test]
+    fn test_reject_dependency_cycles() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+
+        add_dependency(base_dir, None, 2, "1").unwrap();
+        add_dependency(base_dir, None, 3, "2").unwrap();
+        add_dependency(base_dir, Some("second"), 1, "first:3").unwrap();
+
+        assert!(add_dependency(base_dir, None, 1, "1").is_err());
+        assert!(add_dependency(base_dir, None, 1, "3").is_err());
+        assert!(add_dependency(base_dir, None, 1, "second:1").is_err());
+
+        let state = storage::load_state(base_dir).unwrap();
+        assert!(state.projects[0].tasks[0].depends_on.is_empty());
+    }
+
+    #[test]
+    fn test_deleting_a_prerequisite_removes_the_dependency() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+
+        add_dependency(base_dir, None, 3, "1").unwrap();
+        add_dependency(base_dir, None, 3, "2").unwrap();
+        add_dependency(base_dir, Some("second"), 1, "first:1").unwrap();
+        crate::tasks::delete_task(base_dir, None, 1).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].task(3).unwrap().depends_on, vec![TaskRef { project: 1, task: 2 }]);
+        assert!(state.projects[1].tasks[0].depends_on.is_empty());
+        assert!(state.blocked_tasks(1).is_empty());
+    }
+
+    #[test]
+    fn test_remove_dependency() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+
+        add_dependency(base_dir, None, 2, "1").unwrap();
+        remove_dependency(base_dir, None, 2, "1").unwrap();
+        assert!(matches!(remove_dependency(base_dir, None, 2, "1"), Err(TedoError::NotFound(_))));
+        assert!(matches!(add_dependency(base_dir, None, 2, "9"), Err(TedoError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_graph_output() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+
+        add_dependency(base_dir, None, 2, "1").unwrap();
+        add_dependency(base_dir, None, 3, "second:1").unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+
+        let dot = graph(&state, &[0], "dot").unwrap();
+        assert!(dot.starts_with("digraph tedo {"));
+        assert!(dot.contains("p1_t1 -> p1_t2;"));
+        assert!(dot.contains("p2_t1 -> p1_t3;"));
+        assert!(dot.contains("p2_t1 [label=\"2:1 approve\"];"));
+
+        let mermaid = graph(&state, &[0], "mermaid").unwrap();
+        assert!(mermaid.starts_with("flowchart LR"));
+        assert!(mermaid.contains("p1_t1 --> p1_t2"));
+
+        assert!(graph(&state, &[0], "svg").is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config;
+use crate::error::TedoError;
+
+
+// The editor command and its arguments, from the config, $VISUAL or $EDITOR
+pub fn resolve_editor(base_dir: &Path) -> Result<Vec<String>, TedoError> {
+    let configured = config::load_config(base_dir)?.editor;
+    let editor = configured
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    Ok(editor.split_whitespace().map(String::from).collect())
+}
+
+
+// Opens `text` in the editor as a Markdown file. Returns the edited text, or
+// None when the editor left it unchanged.
+pub fn edit_text(base_dir: &Path, text: &str) -> Result<Option<String>, TedoError> {
+    let editor = resolve_editor(base_dir)?;
+
+    let mut temp_file = tempfile::Builder::new().prefix("tedo-").suffix(".md").tempfile()?;
+    temp_file.write_all(text.as_bytes())?;
+    temp_file.flush()?;
+
+    let status = Command::new(&editor[0])
+        .args(&editor[1..])
+        .arg(temp_file.path())
+        .status()
+        .map_err(|error| TedoError::Aborted(format!("Failed to launch editor {}: {}", editor[0], error)))?;
+    if !status.success() {
+        return Err(TedoError::Aborted(format!("Editor exited with {}, changes were discarded", status)));
+    }
+
+    let edited = fs::read_to_string(temp_file.path())?;
+    if edited == text {
+        Ok(None)
+    } else {
+        Ok(Some(edited))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_edit_text_with_configured_editor() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        config::configure(base_dir, Some("editor"), Some("sed -i s/old/new/")).unwrap();
+        assert_eq!(edit_text(base_dir, "old text").unwrap(), Some("new text".to_string()));
+        assert_eq!(edit_text(base_dir, "unrelated").unwrap(), None);
+
+        config::configure(base_dir, Some("editor"), Some("false")).unwrap();
+        assert!(matches!(edit_text(base_dir, "old text"), Err(TedoError::Aborted(_))));
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+
+
+/// Everything that can go wrong while running a tedo command.
+///
+/// Each variant maps to its own process exit code so scripts can tell the
+/// failures apart:
+///
+/// | code | meaning                                         |
+/// |------|-------------------------------------------------|
+/// | 0    | success                                         |
+/// | 1    | invalid input or usage                          |
+/// | 2    | the requested object was not found              |
+/// | 3    | an identifier matched more than one object      |
+/// | 4    | no project selected for a project-bound command |
+/// | 5    | `tedo_state.toml` could not be parsed           |
+/// | 6    | I/O failure while reading or writing state      |
+/// | 7    | tedo has not been initialized with `tedo init`  |
+/// | 8    | an external program failed, nothing was changed |
+/// | 9    | a git command failed in the state directory     |
+#[derive(Debug)]
+pub enum TedoError {
+    InvalidInput(String),
+    NotFound(String),
+    Ambiguous { identifier: String, candidates: Vec<String> },
+    NoContext,
+    StorageCorrupt(String),
+    Io(std::io::Error),
+    NotInitialized,
+    Aborted(String),
+    Git(String),
+}
+
+impl TedoError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TedoError::InvalidInput(_) => 1,
+            TedoError::NotFound(_) => 2,
+            TedoError::Ambiguous { .. } => 3,
+            TedoError::NoContext => 4,
+            TedoError::StorageCorrupt(_) => 5,
+            TedoError::Io(_) => 6,
+            TedoError::NotInitialized => 7,
+            TedoError::Aborted(_) => 8,
+            TedoError::Git(_) => 9,
+        }
+    }
+}
+
+impl fmt::Display for TedoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TedoError::InvalidInput(message) => write!(f, "{}", message),
+            TedoError::NotFound(message) => write!(f, "{}", message),
+            TedoError::Ambiguous { identifier, candidates } => write!(
+                f,
+                "Identifier '{}' is ambiguous, it matches: {}",
+                identifier,
+                candidates.join(", ")
+            ),
+            TedoError::NoContext => write!(f, "No selected project. Please switch to a project first."),
+            TedoError::StorageCorrupt(message) => write!(f, "The tedo state is corrupt: {}", message),
+            TedoError::Io(error) => write!(f, "I/O error: {}", error),
+            TedoError::NotInitialized => write!(f, "You can initialize Tedo using `tedo init`"),
+            TedoError::Aborted(message) => write!(f, "{}", message),
+            TedoError::Git(message) => write!(f, "git failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for TedoError {}
+
+impl From<std::io::Error> for TedoError {
+    fn from(error: std::io::Error) -> Self {
+        TedoError::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for TedoError {
+    fn from(error: toml::de::Error) -> Self {
+        TedoError::StorageCorrupt(error.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for TedoError {
+    fn from(error: toml::ser::Error) -> Self {
+        TedoError::StorageCorrupt(error.to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let errors = [
+            TedoError::InvalidInput("x".into()),
+            TedoError::NotFound("x".into()),
+            TedoError::Ambiguous { identifier: "x".into(), candidates: vec![] },
+            TedoError::NoContext,
+            TedoError::StorageCorrupt("x".into()),
+            TedoError::Io(std::io::Error::other("x")),
+            TedoError::NotInitialized,
+            TedoError::Aborted("x".into()),
+            TedoError::Git("x".into()),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(|e| e.exit_code()).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&0));
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use prettytable::row;
+
+use crate::error::TedoError;
+use crate::storage;
+use crate::storage::{save_state, FocusSession, Interruption, TedoState, TimeEntry};
+use crate::time_tracking::format_duration;
+
+
+// `tedo focus <task>` runs pomodoros in the foreground: a countdown, then a
+// break, for as many sessions as asked. While it runs, a line typed into the
+// terminal is logged as an interruption and `q` stops the session early.
+// Every session is recorded on the task, and its time as a time entry.
+
+pub struct FocusOptions {
+    pub minutes: u32,
+    pub break_minutes: u32,
+    pub sessions: u32,
+}
+
+
+enum Outcome {
+    Completed,
+    Stopped,
+}
+
+
+fn now() -> NaiveDateTime {
+    chrono::Local::now().naive_local()
+}
+
+
+// Rings the terminal bell and shows a desktop notification where available
+pub fn notify(message: &str) {
+    println!("\x07{}", message);
+    let _ = std::process::Command::new("notify-send")
+        .args(["tedo", message])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+}
+
+
+fn stdin_lines() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+
+// Counts down in place. Lines typed meanwhile are interruptions, `q` stops.
+fn countdown(label: &str, length: Duration, input: &Receiver<String>, interruptions: &mut Vec<Interruption>) -> Outcome {
+    let end = Instant::now() + length;
+    loop {
+        let remaining = end.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            println!();
+            return Outcome::Completed;
+        }
+        let seconds = remaining.as_secs_f64().ceil() as u64;
+        print!("\r{} {:02}:{:02} left ", label, seconds / 60, seconds % 60);
+        let _ = std::io::stdout().flush();
+
+        let tick = remaining.min(Duration::from_secs(1));
+        match input.recv_timeout(tick) {
+            Ok(line) if matches!(line.trim(), "q" | "quit") => {
+                println!();
+                return Outcome::Stopped;
+            }
+            Ok(line) if !line.trim().is_empty() => {
+                interruptions.push(Interruption { at: now(), note: line.trim().to_string() });
+                println!("Logged interruption: {}", line.trim());
+            }
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => std::thread::sleep(tick),
+        }
+    }
+}
+
+
+pub fn focus(base_dir: &Path, project: Option<&str>, id: u32, options: &FocusOptions) -> Result<(), TedoError> {
+    if options.minutes == 0 || options.sessions == 0 {
+        return Err(TedoError::InvalidInput("Sessions need at least one minute".into()));
+    }
+    let description = {
+        let tedo_state = storage::load_state(base_dir)?;
+        if tedo_state.running_timer().is_some() {
+            return Err(TedoError::InvalidInput("A timer is running, stop it first with `tedo stop`".into()));
+        }
+        let task = tedo_state.projects[tedo_state.scoped_project_index(project)?].task(id)?;
+        if task.is_done() {
+            return Err(TedoError::InvalidInput(format!("Task {} is closed, reopen it to focus on it", id)));
+        }
+        task.description.clone()
+    };
+
+    println!("Focusing on task {} '{}'. Type a note and Enter to log an interruption, q to stop.", id, description);
+    let input = stdin_lines();
+    for number in 1..=options.sessions {
+        let start = now();
+        let mut interruptions = Vec::new();
+        let label = format!("Focus {}/{}", number, options.sessions);
+        let outcome = countdown(&label, Duration::from_secs(options.minutes as u64 * 60), &input, &mut interruptions);
+
+        let session = FocusSession {
+            start,
+            end: now(),
+            planned_minutes: options.minutes,
+            completed: matches!(outcome, Outcome::Completed),
+            interruptions,
+        };
+        record_session(base_dir, project, id, session)?;
+        if matches!(outcome, Outcome::Stopped) {
+            println!("Session stopped.");
+            return Ok(());
+        }
+
+        if number < options.sessions && options.break_minutes > 0 {
+            notify(&format!("Focus session {} done, take a {} minute break.", number, options.break_minutes));
+            if let Outcome::Stopped = countdown("Break", Duration::from_secs(options.break_minutes as u64 * 60), &input, &mut Vec::new()) {
+                return Ok(());
+            }
+            notify("Break over, back to work.");
+        } else {
+            notify(&format!("Focus session {} done.", number));
+        }
+    }
+    Ok(())
+}
+
+
+pub fn record_session(base_dir: &Path, project: Option<&str>, id: u32, session: FocusSession) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let task = tedo_state.scoped_project_mut(project)?.task_mut(id)?;
+    task.time_entries.push(TimeEntry { start: session.start, end: Some(session.end) });
+    task.focus_sessions.push(session);
+    save_state(base_dir, &tedo_state)
+}
+
+
+#[derive(Debug, Default, PartialEq)]
+pub struct DaySummary {
+    pub sessions: usize,
+    pub completed: usize,
+    pub time: chrono::Duration,
+    pub interruptions: usize,
+}
+
+
+// Focus sessions per day, for the days between `from` and `to`
+pub fn daily_summary(tedo_state: &TedoState, from: NaiveDate, to: NaiveDate) -> BTreeMap<NaiveDate, DaySummary> {
+    let mut days: BTreeMap<NaiveDate, DaySummary> = BTreeMap::new();
+    let sessions = tedo_state.projects.iter()
+        .flat_map(|p| &p.tasks)
+        .flat_map(|t| &t.focus_sessions)
+        .filter(|s| s.start.date() >= from && s.start.date() <= to);
+    for session in sessions {
+        let day = days.entry(session.start.date()).or_default();
+        day.sessions += 1;
+        day.completed += session.completed as usize;
+        day.time += session.end - session.start;
+        day.interruptions += session.interruptions.len();
+    }
+    days
+}
+
+
+pub fn focus_report(base_dir: &Path, project: Option<&str>, from: NaiveDate, to: NaiveDate) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    tedo_state.narrow_to_subtree(project)?;
+
+    let days = daily_summary(&tedo_state, from, to);
+    if days.is_empty() {
+        println!("No focus sessions between {} and {}.", from, to);
+        return Ok(());
+    }
+    let mut table = prettytable::Table::new();
+    table.add_row(row!["Day", "Sessions", "Completed", "Focus time", "Interruptions"]);
+    for (date, day) in days {
+        table.add_row(row![date.format("%a %Y-%m-%d"), r->day.sessions, r->day.completed, r->format_duration(day.time), r->day.interruptions]);
+    }
+    table.printstd();
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::projects::create_project;
+    use crate::tasks::create_task;
+
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn session(start: &str, end: &str, completed: bool, interruptions: usize) -> FocusSession {
+        FocusSession {
+            start: at(start),
+            end: at(end),
+            planned_minutes: 25,
+            completed,
+            interruptions: (0..interruptions).map(|_| Interruption { at: at(start), note: "call".into() }).collect(),
+        }
+    }
+
+    #[test]
+    fn test_sessions_are_recorded_with_their_time() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "growth", true).unwrap();
+        create_task(base_dir, None, "Write docs", None).unwrap();
+        record_session(base_dir, None, 1, session("2026-10-19 09:00", "2026-10-19 09:25", true, 1)).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        let task = &state.projects[0].tasks[0];
+        assert_eq!(task.focus_sessions.len(), 1);
+        assert_eq!(task.time_entries, vec![TimeEntry { start: at("2026-10-19 09:00"), end: Some(at("2026-10-19 09:25")) }]);
+    }
+
+    #[test]
+    fn test_focus_refuses_closed_tasks_and_running_timers() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        let options = FocusOptions { minutes: 25, break_minutes: 5, sessions: 1 };
+
+        create_project(base_dir, "growth", true).unwrap();
+        create_task(base_dir, None, "Write docs", None).unwrap();
+        create_task(base_dir, None, "Review", None).unwrap();
+        crate::tasks::close_task(base_dir, None, 2, false).unwrap();
+        assert!(matches!(focus(base_dir, None, 2, &options), Err(TedoError::InvalidInput(_))));
+        assert!(matches!(focus(base_dir, None, 3, &options), Err(TedoError::NotFound(_))));
+
+        crate::time_tracking::start_timer(base_dir, None, 1, at("2026-10-19 09:00")).unwrap();
+        assert!(matches!(focus(base_dir, None, 1, &options), Err(TedoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_daily_summary() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "growth", true).unwrap();
+        create_task(base_dir, None, "Write docs", None).unwrap();
+        create_task(base_dir, None, "Review", None).unwrap();
+        record_session(base_dir, None, 1, session("2026-10-19 09:00", "2026-10-19 09:25", true, 2)).unwrap();
+        record_session(base_dir, None, 2, session("2026-10-19 10:00", "2026-10-19 10:10", false, 0)).unwrap();
+        record_session(base_dir, None, 1, session("2026-10-20 09:00", "2026-10-20 09:25", true, 0)).unwrap();
+        record_session(base_dir, None, 1, session("2026-10-12 09:00", "2026-10-12 09:25", true, 0)).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        let days = daily_summary(&state, NaiveDate::from_ymd_opt(2026, 10, 19).unwrap(), NaiveDate::from_ymd_opt(2026, 10, 25).unwrap());
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[&NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()], DaySummary {
+            sessions: 2,
+            completed: 1,
+            time: chrono::Duration::minutes(35),
+            interruptions: 2,
+        });
+    }
+}
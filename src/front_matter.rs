@@ -0,0 +1,83 @@
+// Front-matter is a block of `key: value` lines between two `---` lines at the
+// start of the text. Lists are written as `[a, b]`.
+pub fn with_front_matter(fields: &[(&str, String)], body: &str) -> String {
+    let mut text = String::from("---\n");
+    for (key, value) in fields {
+        text.push_str(&format!("{}: {}\n", key, value));
+    }
+    text.push_str("---\n");
+    text.push_str(body);
+    text
+}
+
+
+pub fn split_front_matter(text: &str) -> (Vec<(String, String)>, String) {
+    let mut lines = text.split_inclusive('\n');
+    let mut consumed = match lines.next() {
+        Some(line) if line.trim_end() == "---" => line.len(),
+        _ => return (Vec::new(), text.to_string()),
+    };
+
+    let mut fields = Vec::new();
+    for line in lines {
+        consumed += line.len();
+        if line.trim_end() == "---" {
+            return (fields, text[consumed..].to_string());
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            fields.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    // No closing line, so this was not front-matter after all
+    (Vec::new(), text.to_string())
+}
+
+
+pub fn format_list(values: &[String]) -> String {
+    format!("[{}]", values.join(", "))
+}
+
+
+pub fn parse_list(value: &str) -> Vec<String> {
+    value.trim().trim_start_matches('[').trim_end_matches(']')
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+
+pub fn field<'a>(fields: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter_round_trip() {
+        let text = with_front_matter(&[("title", "Design: v2".into()), ("tags", format_list(&["a".into(), "b".into()]))], "# Body\n---\nmore");
+        assert_eq!(text, "---\ntitle: Design: v2\ntags: [a, b]\n---\n# Body\n---\nmore");
+
+        let (fields, body) = split_front_matter(&text);
+        assert_eq!(field(&fields, "title"), Some("Design: v2"));
+        assert_eq!(parse_list(field(&fields, "tags").unwrap()), vec!["a", "b"]);
+        assert_eq!(body, "# Body\n---\nmore");
+    }
+
+    #[test]
+    fn test_text_without_front_matter() {
+        let (fields, body) = split_front_matter("just a body\n");
+        assert!(fields.is_empty());
+        assert_eq!(body, "just a body\n");
+
+        let (fields, body) = split_front_matter("---\nnot closed");
+        assert!(fields.is_empty());
+        assert_eq!(body, "---\nnot closed");
+
+        assert!(parse_list("[]").is_empty());
+    }
+}
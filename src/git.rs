@@ -0,0 +1,354 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::TedoError;
+use crate::merge::{self, Side};
+use crate::storage::{get_state_path, TedoState, STATE_FILE};
+use crate::config;
+
+
+// With `tedo config git true` the state directory is a git repository and
+// every command that changes something is committed with a message describing
+// the change. `tedo sync` shares the store through a remote, merging the state
+// file by entity instead of line by line.
+
+static BRANCH: &str = "main";
+static REMOTE_BRANCH: &str = "refs/remotes/origin/main";
+
+
+fn git(base_dir: &Path, args: &[&str]) -> Result<String, TedoError> {
+    let output = Command::new("git").arg("-C").arg(base_dir).args(args).output()
+        .map_err(|error| TedoError::Git(format!("could not run git: {}", error)))?;
+    if !output.status.success() {
+        return Err(TedoError::Git(format!("`git {}`: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+
+fn ensure_repository(base_dir: &Path) -> Result<(), TedoError> {
+    if base_dir.join(".git").exists() {
+        return Ok(());
+    }
+    git(base_dir, &["init", "-q"])?;
+    git(base_dir, &["symbolic-ref", "HEAD", &format!("refs/heads/{}", BRANCH)])?;
+    if git(base_dir, &["config", "user.email"]).is_err() {
+        git(base_dir, &["config", "user.name", "tedo"])?;
+        git(base_dir, &["config", "user.email", "tedo@localhost"])?;
+    }
+    // The config is machine-local, everything else is shared
+    fs::write(base_dir.join(".gitignore"), "config.toml\n")?;
+    git(base_dir, &["add", "-A"])?;
+    git(base_dir, &["commit", "-q", "-m", "initialize tedo store"])?;
+    Ok(())
+}
+
+
+// The state file as of a commit, empty if it didn't exist yet
+fn state_at(base_dir: &Path, revision: &str) -> Result<TedoState, TedoError> {
+    let object = format!("{}:{}", revision, STATE_FILE);
+    if git(base_dir, &["cat-file", "-e", &object]).is_err() {
+        return Ok(TedoState::default());
+    }
+    Ok(toml::from_str(&git(base_dir, &["show", &object])?)?)
+}
+
+
+fn current_state(base_dir: &Path) -> Result<TedoState, TedoError> {
+    match fs::read_to_string(get_state_path(base_dir)) {
+        Ok(data) => Ok(toml::from_str(&data)?),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(TedoState::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+
+// Commits whatever changed in the state directory. Does nothing unless git
+// is turned on.
+pub fn commit_changes(base_dir: &Path) -> Result<(), TedoError> {
+    if !config::load_config(base_dir)?.git {
+        return Ok(());
+    }
+    ensure_repository(base_dir)?;
+    git(base_dir, &["add", "-A"])?;
+    if git(base_dir, &["diff", "--cached", "--quiet"]).is_ok() {
+        return Ok(());
+    }
+
+    let changes = describe_changes(&state_at(base_dir, "HEAD")?, &current_state(base_dir)?);
+    git(base_dir, &["commit", "-q", "-m", &commit_message(&changes)])?;
+    Ok(())
+}
+
+
+fn commit_message(changes: &[String]) -> String {
+    match changes {
+        [] => "update tedo store".to_string(),
+        [change] => change.clone(),
+        [first, rest @ ..] => format!(
+            "{} and {} more change{}\n\n{}",
+            first,
+            rest.len(),
+            if rest.len() == 1 { "" } else { "s" },
+            changes.join("\n")
+        ),
+    }
+}
+
+
+// One line per change between two states, e.g. "create task 'x' in growth"
+pub fn describe_changes(old: &TedoState, new: &TedoState) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    for project in &new.projects {
+        let old_project = old.projects.iter().find(|p| p.id == project.id);
+        match old_project {
+            None => changes.push(format!("create project '{}'", project.name)),
+            Some(old_project) if old_project.name != project.name => {
+                changes.push(format!("rename project '{}' to '{}'", old_project.name, project.name));
+            }
+            Some(_) => {}
+        }
+        let (old_tasks, old_notes) = old_project.map_or((&[][..], &[][..]), |p| (&p.tasks[..], &p.notes[..]));
+
+        for task in &project.tasks {
+            let change = match old_tasks.iter().find(|t| t.id == task.id) {
+                None => "create task",
+                Some(old_task) if old_task.status != task.status => if task.is_done() { "close task" } else { "reopen task" },
+                Some(old_task) if old_task.description != task.description => {
+                    changes.push(format!("rename task '{}' to '{}' in {}", old_task.description, task.description, project.name));
+                    continue;
+                }
+                Some(old_task) if old_task.focus_sessions != task.focus_sessions => "focus on task",
+                Some(old_task) if old_task.time_entries != task.time_entries => "track time on task",
+                Some(old_task) if !merge::same(old_task, task) => "update task",
+                Some(_) => continue,
+            };
+            changes.push(format!("{} '{}' in {}", change, task.description, project.name));
+        }
+        for task in old_tasks.iter().filter(|t| !project.tasks.iter().any(|task| task.id == t.id)) {
+            changes.push(format!("delete task '{}' in {}", task.description, project.name));
+        }
+
+        for note in &project.notes {
+            let change = match old_notes.iter().find(|n| n.id == note.id) {
+                None => "create note",
+                Some(old_note) if old_note.description != note.description => {
+                    changes.push(format!("rename note '{}' to '{}' in {}", old_note.description, note.description, project.name));
+                    continue;
+                }
+                // Notes stored as files only show the edit in their history
+                Some(old_note) if old_note.content != note.content || old_note.revisions.len() != note.revisions.len()
+                    || note.history.iter().skip(old_note.history.len()).any(|change| change.field == "content") => "edit note",
+                Some(old_note) if !merge::same(old_note, note) => "update note",
+                Some(_) => continue,
+            };
+            changes.push(format!("{} '{}' in {}", change, note.description, project.name));
+        }
+        for note in old_notes.iter().filter(|n| !project.notes.iter().any(|note| note.id == n.id)) {
+            changes.push(format!("delete note '{}' in {}", note.description, project.name));
+        }
+    }
+
+    for project in old.projects.iter().filter(|p| !new.projects.iter().any(|project| project.id == p.id)) {
+        changes.push(format!("delete project '{}'", project.name));
+    }
+    if old.current_project != new.current_project {
+        if let Some(name) = &new.current_project {
+            changes.push(format!("switch to project '{}'", name));
+        }
+    }
+    changes
+}
+
+
+// Commits local changes, merges what the remote has and pushes the result
+pub fn sync(base_dir: &Path) -> Result<(), TedoError> {
+    let config = config::load_config(base_dir)?;
+    if !config.git {
+        return Err(TedoError::InvalidInput("Turn on git first with `tedo config git true`".into()));
+    }
+    let remote = config.git_remote
+        .ok_or_else(|| TedoError::InvalidInput("No remote to sync with, set one with `tedo config git_remote <url>`".into()))?;
+
+    commit_changes(base_dir)?;
+    match git(base_dir, &["remote", "get-url", "origin"]) {
+        Ok(url) if url.trim() == remote => {}
+        Ok(_) => { git(base_dir, &["remote", "set-url", "origin", &remote])?; }
+        Err(_) => { git(base_dir, &["remote", "add", "origin", &remote])?; }
+    }
+    git(base_dir, &["fetch", "-q", "origin"])?;
+
+    if git(base_dir, &["rev-parse", "-q", "--verify", REMOTE_BRANCH]).is_ok() {
+        merge_remote(base_dir, &remote)?;
+    }
+    git(base_dir, &["push", "-q", "origin", &format!("HEAD:refs/heads/{}", BRANCH)])?;
+    println!("Synced with {}.", remote);
+    Ok(())
+}
+
+
+// Git merges the note files and templates, the state file is merged by entity.
+// Conflicting fields keep the local value.
+fn merge_remote(base_dir: &Path, remote: &str) -> Result<(), TedoError> {
+    if git(base_dir, &["merge-base", "--is-ancestor", REMOTE_BRANCH, "HEAD"]).is_ok() {
+        return Ok(());
+    }
+    if git(base_dir, &["merge-base", "--is-ancestor", "HEAD", REMOTE_BRANCH]).is_ok() {
+        git(base_dir, &["merge", "-q", "--ff-only", REMOTE_BRANCH])?;
+        return Ok(());
+    }
+
+    let base = match git(base_dir, &["merge-base", "HEAD", REMOTE_BRANCH]) {
+        Ok(revision) => state_at(base_dir, revision.trim())?,
+        Err(_) => TedoState::default(),
+    };
+    let ours = state_at(base_dir, "HEAD")?;
+    let theirs = state_at(base_dir, REMOTE_BRANCH)?;
+
+    let result = git(base_dir, &["merge", "-q", "--no-commit", "--no-ff", "--allow-unrelated-histories", REMOTE_BRANCH]);
+    if git(base_dir, &["rev-parse", "-q", "--verify", "MERGE_HEAD"]).is_err() {
+        return result.map(|_| ());
+    }
+
+    let merge = merge::merge_states(&base, &ours, &theirs, &mut |_| Side::Ours);
+    fs::write(get_state_path(base_dir), toml::to_string(&merge.state)?)?;
+    git(base_dir, &["add", STATE_FILE])?;
+
+    let unmerged = git(base_dir, &["diff", "--name-only", "--diff-filter=U"])?;
+    if !unmerged.trim().is_empty() {
+        git(base_dir, &["merge", "--abort"])?;
+        return Err(TedoError::Git(format!(
+            "could not merge {}, resolve the conflict with git in {}",
+            unmerged.lines().collect::<Vec<&str>>().join(", "),
+            base_dir.display()
+        )));
+    }
+
+    let conflicts: Vec<String> = merge.conflicts.iter().map(|(conflict, _)| conflict.to_string()).collect();
+    let mut message = format!("merge changes from {}", remote);
+    if !conflicts.is_empty() {
+        message.push_str(&format!("\n\nKept the local side of:\n{}", conflicts.join("\n")));
+    }
+    git(base_dir, &["commit", "-q", "-m", &message])?;
+    for conflict in conflicts {
+        println!("Conflict in {}. Kept the local value.", conflict);
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::notes::create_note;
+    use crate::projects::create_project;
+    use crate::storage::load_state;
+    use crate::tasks::{close_task, create_task};
+
+    use super::*;
+
+    fn subjects(base_dir: &Path) -> Vec<String> {
+        git(base_dir, &["log", "--format=%s"]).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_changes_are_committed_with_a_description() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "growth", true).unwrap();
+        config::configure(base_dir, Some("git"), Some("true")).unwrap();
+        commit_changes(base_dir).unwrap();
+
+        create_task(base_dir, None, "Write docs", None).unwrap();
+        commit_changes(base_dir).unwrap();
+        close_task(base_dir, None, 1, false).unwrap();
+        commit_changes(base_dir).unwrap();
+        commit_changes(base_dir).unwrap();
+        create_note(base_dir, None, "Plan", "").unwrap();
+        create_task(base_dir, None, "Review", None).unwrap();
+        commit_changes(base_dir).unwrap();
+
+        assert_eq!(subjects(base_dir), vec![
+            "create task 'Review' in growth and 1 more change",
+            "close task 'Write docs' in growth",
+            "create task 'Write docs' in growth",
+            "initialize tedo store",
+        ]);
+        assert!(!git(base_dir, &["ls-files"]).unwrap().contains("config.toml"));
+    }
+
+    #[test]
+    fn test_sync_requires_a_remote() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        assert!(matches!(sync(base_dir), Err(TedoError::InvalidInput(_))));
+        config::configure(base_dir, Some("git"), Some("true")).unwrap();
+        assert!(matches!(sync(base_dir), Err(TedoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_sync_merges_concurrent_changes_by_entity() {
+        let remote_dir = tempdir().unwrap();
+        let remote = remote_dir.path().to_str().unwrap();
+        git(remote_dir.path(), &["init", "-q", "--bare"]).unwrap();
+
+        let laptop_dir = tempdir().unwrap();
+        let desktop_dir = tempdir().unwrap();
+        let (laptop, desktop) = (laptop_dir.path(), desktop_dir.path());
+        for base_dir in [laptop, desktop] {
+            config::configure(base_dir, Some("git"), Some("true")).unwrap();
+            config::configure(base_dir, Some("git_remote"), Some(remote)).unwrap();
+        }
+
+        create_project(laptop, "growth", true).unwrap();
+        create_task(laptop, None, "Shared", None).unwrap();
+        sync(laptop).unwrap();
+        sync(desktop).unwrap();
+        assert_eq!(load_state(desktop).unwrap().projects[0].tasks[0].description, "Shared");
+
+        // Both sides create task 2 and change the shared task
+        crate::projects::switch_project(desktop, "growth").unwrap();
+        create_task(laptop, None, "From the laptop", None).unwrap();
+        close_task(laptop, None, 1, false).unwrap();
+        create_task(desktop, None, "From the desktop", None).unwrap();
+        crate::tags::tag_task(desktop, None, 1, &["team"], false).unwrap();
+
+        sync(laptop).unwrap();
+        sync(desktop).unwrap();
+        sync(laptop).unwrap();
+
+        for base_dir in [laptop, desktop] {
+            let state = load_state(base_dir).unwrap();
+            let tasks = &state.projects[0].tasks;
+            let descriptions: Vec<&str> = tasks.iter().map(|t| t.description.as_str()).collect();
+            assert_eq!(descriptions, vec!["Shared", "From the desktop", "From the laptop"]);
+            assert_eq!(tasks.iter().map(|t| t.id).collect::<Vec<u32>>(), vec![1, 2, 3]);
+            assert!(tasks[0].is_done());
+            assert_eq!(tasks[0].tags, vec!["team"]);
+        }
+        assert_eq!(subjects(desktop)[0], format!("merge changes from {}", remote));
+    }
+
+    #[test]
+    fn test_describe_changes() {
+        let mut old = TedoState::default();
+        old.projects.push(crate::storage::Project::new(1, "growth"));
+        let mut new = old.clone();
+        new.projects[0].name = "team".into();
+        new.projects[0].notes.push(crate::storage::Note::new(1, "Plan", ""));
+        new.current_project = Some("team".into());
+
+        assert_eq!(describe_changes(&old, &new), vec![
+            "rename project 'growth' to 'team'",
+            "create note 'Plan' in team",
+            "switch to project 'team'",
+        ]);
+        assert_eq!(describe_changes(&new, &old), vec!["rename project 'team' to 'growth'", "delete note 'Plan' in growth"]);
+    }
+}
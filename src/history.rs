@@ -0,0 +1,321 @@
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use colored::Colorize;
+use serde::Serialize;
+use toml::{Table, Value};
+
+use crate::error::TedoError;
+use crate::storage;
+use crate::storage::{Change, Note, Project, Task, TedoState};
+
+
+// Every save compares the state with the one on disk and stamps the projects,
+// tasks and notes that changed: a creation time for new ones, a modification
+// time and one history entry per changed field for the others.
+
+trait Tracked: Serialize {
+    // Fields that are bookkeeping, or entities of their own
+    const UNTRACKED: &'static [&'static str];
+
+    fn activity(&mut self) -> (&mut Option<NaiveDateTime>, &mut Option<NaiveDateTime>, &mut Vec<Change>);
+}
+
+impl Tracked for Project {
+    const UNTRACKED: &'static [&'static str] = &["created", "modified", "history", "tasks", "notes", "next_task_id", "next_note_id"];
+
+    fn activity(&mut self) -> (&mut Option<NaiveDateTime>, &mut Option<NaiveDateTime>, &mut Vec<Change>) {
+        (&mut self.created, &mut self.modified, &mut self.history)
+    }
+}
+
+impl Tracked for Task {
+    const UNTRACKED: &'static [&'static str] = &["created", "modified", "history", "reminded"];
+
+    fn activity(&mut self) -> (&mut Option<NaiveDateTime>, &mut Option<NaiveDateTime>, &mut Vec<Change>) {
+        (&mut self.created, &mut self.modified, &mut self.history)
+    }
+}
+
+impl Tracked for Note {
+    const UNTRACKED: &'static [&'static str] = &["created", "modified", "history", "revisions", "file", "previous_slugs"];
+
+    fn activity(&mut self) -> (&mut Option<NaiveDateTime>, &mut Option<NaiveDateTime>, &mut Vec<Change>) {
+        (&mut self.created, &mut self.modified, &mut self.history)
+    }
+}
+
+
+fn to_table<T: Serialize>(item: &T) -> Table {
+    match Value::try_from(item) {
+        Ok(Value::Table(table)) => table,
+        _ => Table::new(),
+    }
+}
+
+
+// The new value is kept for short scalar values only
+fn change(field: &str, value: Option<&Value>, at: NaiveDateTime) -> Change {
+    let value = match value {
+        _ if field == "content" => None,
+        Some(Value::String(text)) => Some(text.clone()),
+        Some(Value::Integer(number)) => Some(number.to_string()),
+        Some(Value::Boolean(flag)) => Some(flag.to_string()),
+        Some(Value::Datetime(datetime)) => Some(datetime.to_string()),
+        _ => None,
+    };
+    Change { at, field: field.to_string(), value }
+}
+
+
+fn stamp<T: Tracked>(old: Option<&T>, new: &mut T, now: NaiveDateTime) {
+    let new_fields = to_table(new);
+    let changes = match old.map(to_table) {
+        // Entities that come with a history, e.g. from a merge, have their
+        // changes recorded already
+        None if new_fields.contains_key("created") => return,
+        Some(old_fields) if old_fields.get("history") != new_fields.get("history") => return,
+        None => vec![change("created", None, now)],
+        Some(old_fields) => {
+            let mut keys: Vec<&String> = old_fields.keys().chain(new_fields.keys())
+                .filter(|key| !T::UNTRACKED.contains(&key.as_str()))
+                .collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter()
+                .filter(|key| old_fields.get(*key) != new_fields.get(*key))
+                .map(|key| change(key, new_fields.get(key), now))
+                .collect()
+        }
+    };
+    if changes.is_empty() {
+        return;
+    }
+
+    let (created, modified, history) = new.activity();
+    if old.is_none() {
+        *created = Some(now);
+    }
+    *modified = Some(now);
+    history.extend(changes);
+}
+
+
+pub fn record_changes(old: &TedoState, new: &mut TedoState, now: NaiveDateTime) {
+    for project in &mut new.projects {
+        let old_project = old.projects.iter().find(|p| p.id == project.id);
+        stamp(old_project, project, now);
+        for task in &mut project.tasks {
+            let old_task = old_project.and_then(|p| p.tasks.iter().find(|t| t.id == task.id));
+            stamp(old_task, task, now);
+        }
+        for note in &mut project.notes {
+            let old_note = old_project.and_then(|p| p.notes.iter().find(|n| n.id == note.id));
+            stamp(old_note, note, now);
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Project,
+    Task,
+    Note,
+}
+
+
+// A change along with the entity it happened to
+pub struct Event<'a> {
+    pub project: &'a Project,
+    pub kind: Kind,
+    pub id: u32,
+    pub description: &'a str,
+    pub change: &'a Change,
+}
+
+impl Event<'_> {
+    pub fn entity(&self) -> String {
+        match self.kind {
+            Kind::Project => format!("project '{}'", self.description),
+            Kind::Task => format!("task {} '{}'", self.id, self.description),
+            Kind::Note => format!("note {} '{}'", self.id, self.description),
+        }
+    }
+}
+
+
+pub fn describe_change(change: &Change) -> String {
+    match (change.field.as_str(), &change.value) {
+        ("created", _) => "created".to_string(),
+        (field, Some(value)) => format!("set {} to {}", field, value),
+        (field, None) => format!("changed {}", field),
+    }
+}
+
+
+// All changes in the given projects, oldest first
+pub fn events(projects: &[Project]) -> Vec<Event<'_>> {
+    let mut events = Vec::new();
+    for project in projects {
+        events.extend(project.history.iter().map(|change| Event { project, kind: Kind::Project, id: project.id, description: &project.name, change }));
+        for task in &project.tasks {
+            events.extend(task.history.iter().map(|change| Event { project, kind: Kind::Task, id: task.id, description: &task.description, change }));
+        }
+        for note in &project.notes {
+            events.extend(note.history.iter().map(|change| Event { project, kind: Kind::Note, id: note.id, description: &note.description, change }));
+        }
+    }
+    events.sort_by_key(|event| event.change.at);
+    events
+}
+
+
+fn print_timeline(events: &[Event], with_project: bool) {
+    let mut day = None;
+    for event in events {
+        let date = event.change.at.date();
+        if day != Some(date) {
+            if day.is_some() {
+                println!();
+            }
+            println!("{}", date.format("%a %Y-%m-%d").to_string().bold());
+            day = Some(date);
+        }
+        let project = if with_project { format!("{}  ", event.project.name) } else { String::new() };
+        println!("  {}  {}{}: {}", event.change.at.format("%H:%M").to_string().dimmed(), project, event.entity(), describe_change(event.change));
+    }
+}
+
+
+// What happened across projects since the given day
+pub fn show_log(base_dir: &Path, project: Option<&str>, since: NaiveDate) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    tedo_state.narrow_to_subtree(project)?;
+
+    let events: Vec<Event> = events(&tedo_state.projects).into_iter()
+        .filter(|event| event.change.at.date() >= since)
+        .collect();
+    if events.is_empty() {
+        println!("Nothing happened since {}.", since);
+        return Ok(());
+    }
+    print_timeline(&events, true);
+    Ok(())
+}
+
+
+pub enum Entity<'a> {
+    Project(Option<&'a str>),
+    Task(u32),
+    Note(u32),
+}
+
+
+// The history of one entity. For a project that includes its tasks and notes.
+pub fn show_history(base_dir: &Path, project: Option<&str>, entity: Entity) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project = match entity {
+        Entity::Project(Some(identifier)) => &tedo_state.projects[tedo_state.project_index(identifier)?],
+        _ => &tedo_state.projects[tedo_state.scoped_project_index(project)?],
+    };
+    let all_events = events(std::slice::from_ref(project));
+    let (title, created, modified, events): (String, _, _, Vec<Event>) = match entity {
+        Entity::Project(_) => (format!("Project {}", project.name), project.created, project.modified, all_events),
+        Entity::Task(id) => {
+            let task = project.task(id)?;
+            let events = all_events.into_iter().filter(|e| e.kind == Kind::Task && e.id == id).collect();
+            (format!("Task {}: {}", id, task.description), task.created, task.modified, events)
+        }
+        Entity::Note(id) => {
+            let note = project.note(id)?;
+            let events = all_events.into_iter().filter(|e| e.kind == Kind::Note && e.id == id).collect();
+            (format!("Note {}: {}", id, note.description), note.created, note.modified, events)
+        }
+    };
+
+    println!("{}", title.bold());
+    let timestamp = |at: Option<NaiveDateTime>| at.map(|at| at.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_else(|| "unknown".to_string());
+    println!("{:<12} {}", "Created:".dimmed(), timestamp(created));
+    println!("{:<12} {}", "Modified:".dimmed(), timestamp(modified));
+    if events.is_empty() {
+        println!();
+        println!("No changes recorded yet.");
+        return Ok(());
+    }
+    println!();
+    print_timeline(&events, false);
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::notes::create_note;
+    use crate::projects::create_project;
+    use crate::tasks::{close_task, create_task};
+
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn fields(history: &[Change]) -> Vec<(&str, Option<&str>)> {
+        history.iter().map(|c| (c.field.as_str(), c.value.as_deref())).collect()
+    }
+
+    #[test]
+    fn test_record_changes() {
+        let mut old = TedoState::default();
+        old.projects.push(Project { tasks: vec![Task::new(1, "Write docs"), Task::new(2, "Review")], ..Project::new(1, "growth") });
+
+        let mut new = old.clone();
+        new.projects[0].tasks[0].status = storage::TaskStatus::Done;
+        new.projects[0].tasks[0].tags.push("docs".into());
+        new.projects[0].tasks.push(Task::new(3, "Ship"));
+        record_changes(&old, &mut new, at("2026-10-19 09:00"));
+
+        let project = &new.projects[0];
+        assert_eq!(fields(&project.tasks[0].history), vec![("status", Some("done")), ("tags", None)]);
+        assert_eq!(project.tasks[0].created, None);
+        assert_eq!(project.tasks[0].modified, Some(at("2026-10-19 09:00")));
+        assert!(project.tasks[1].history.is_empty());
+        assert_eq!(fields(&project.tasks[2].history), vec![("created", None)]);
+        assert_eq!(project.tasks[2].created, Some(at("2026-10-19 09:00")));
+        // Changes to its tasks are not changes to the project
+        assert!(project.history.is_empty());
+
+        // Entities that arrive with a history are left alone
+        let old = new.clone();
+        new.projects[0].tasks[1].history.push(Change { at: at("2026-10-18 10:00"), field: "status".into(), value: Some("done".into()) });
+        new.projects[0].tasks[1].status = storage::TaskStatus::Done;
+        record_changes(&old, &mut new, at("2026-10-19 10:00"));
+        assert_eq!(new.projects[0].tasks[1].history.len(), 1);
+    }
+
+    #[test]
+    fn test_saving_records_history() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "growth", true).unwrap();
+        create_task(base_dir, None, "Write docs", None).unwrap();
+        create_note(base_dir, None, "Ideas", "").unwrap();
+        close_task(base_dir, None, 1, false).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        let project = &state.projects[0];
+        assert!(project.created.is_some());
+        assert_eq!(fields(&project.tasks[0].history), vec![("created", None), ("status", Some("done"))]);
+        assert_eq!(fields(&project.notes[0].history)[0], ("created", None));
+
+        let events: Vec<String> = events(&state.projects).iter()
+            .map(|e| format!("{}: {}", e.entity(), describe_change(e.change)))
+            .collect();
+        assert_eq!(events[0], "project 'growth': created");
+        assert!(events.contains(&"task 1 'Write docs': set status to done".to_string()));
+    }
+}
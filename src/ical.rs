@@ -0,0 +1,433 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::error::TedoError;
+use crate::storage::{save_state, Project, Task, TaskStatus, TedoState};
+use crate::{dates, storage, tags};
+
+
+// Tasks with a due date or a scheduled day are exported as VTODOs, or as
+// all-day VEVENTs for calendar apps that don't show todos. Every task has a
+// stable UID: the one it was imported with, or one made of its project and
+// task ids. Importing matches on that UID, so a task that comes back from a
+// calendar updates the existing one instead of adding a copy.
+
+pub fn task_uid(project: &Project, task: &Task) -> String {
+    task.uid.clone().unwrap_or_else(|| format!("{}-{}@tedo", project.id, task.id))
+}
+
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+
+// Content lines are folded at 75 bytes, continuation lines start with a space
+fn push_line(ics: &mut String, line: &str) {
+    let mut rest = line;
+    let mut limit = 75;
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        ics.push_str(&rest[..split]);
+        ics.push_str("\r\n ");
+        rest = &rest[split..];
+        limit = 74;
+    }
+    ics.push_str(rest);
+    ics.push_str("\r\n");
+}
+
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+
+// Timestamps are local times, written in UTC as the standard requires for
+// DTSTAMP and alarm triggers
+fn format_utc(at: NaiveDateTime) -> String {
+    let utc = Local.from_local_datetime(&at).earliest().map_or(at, |local| local.with_timezone(&Utc).naive_utc());
+    utc.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+
+fn parse_datetime(value: &str) -> Option<NaiveDateTime> {
+    match value.strip_suffix('Z') {
+        Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()
+            .map(|at| Utc.from_utc_datetime(&at).with_timezone(&Local).naive_local()),
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok(),
+    }
+}
+
+
+pub fn export_calendar(tedo_state: &TedoState, events: bool, now: NaiveDateTime) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//tedo//tedo//EN");
+    push_line(&mut ics, "X-WR-CALNAME:tedo");
+
+    let tasks = tedo_state.projects.iter()
+        .flat_map(|p| p.tasks.iter().map(move |t| (p, t)))
+        .filter(|(_, t)| !t.is_template() && (t.due.is_some() || t.scheduled.is_some()));
+    for (project, task) in tasks {
+        let component = if events { "VEVENT" } else { "VTODO" };
+        push_line(&mut ics, &format!("BEGIN:{}", component));
+        push_line(&mut ics, &format!("UID:{}", task_uid(project, task)));
+        push_line(&mut ics, &format!("DTSTAMP:{}", format_utc(now)));
+        push_line(&mut ics, &format!("SUMMARY:{}", escape(&task.description)));
+        push_line(&mut ics, &format!("DESCRIPTION:{}", escape(&format!("Task {} in {}", task.id, project.name))));
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|tag| escape(tag)).collect();
+            push_line(&mut ics, &format!("CATEGORIES:{}", tags.join(",")));
+        }
+
+        if events {
+            // An all-day event on the due date, or on the scheduled day
+            let day = task.due.or(task.scheduled).unwrap_or(now.date());
+            push_line(&mut ics, &format!("DTSTART;VALUE=DATE:{}", format_date(day)));
+            push_line(&mut ics, &format!("DTEND;VALUE=DATE:{}", format_date(day + chrono::Duration::days(1))));
+            push_line(&mut ics, if task.is_done() { "STATUS:CANCELLED" } else { "STATUS:CONFIRMED" });
+            push_line(&mut ics, "TRANSP:TRANSPARENT");
+        } else {
+            if let Some(scheduled) = task.scheduled {
+                push_line(&mut ics, &format!("DTSTART;VALUE=DATE:{}", format_date(scheduled)));
+            }
+            if let Some(due) = task.due {
+                push_line(&mut ics, &format!("DUE;VALUE=DATE:{}", format_date(due)));
+            }
+            push_line(&mut ics, if task.is_done() { "STATUS:COMPLETED" } else { "STATUS:NEEDS-ACTION" });
+        }
+
+        if let Some(at) = task.reminder.filter(|_| !task.is_done()) {
+            push_line(&mut ics, "BEGIN:VALARM");
+            push_line(&mut ics, "ACTION:DISPLAY");
+            push_line(&mut ics, &format!("DESCRIPTION:{}", escape(&task.description)));
+            push_line(&mut ics, &format!("TRIGGER;VALUE=DATE-TIME:{}", format_utc(at)));
+            push_line(&mut ics, "END:VALARM");
+        }
+        push_line(&mut ics, &format!("END:{}", component));
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+
+// Exports all projects unless one is given, along with its sub-projects.
+// Without `output` the calendar is printed, so it can be piped.
+pub fn export_ics(base_dir: &Path, project: Option<&str>, output: Option<&Path>, events: bool) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    tedo_state.narrow_to_subtree(project)?;
+
+    let ics = export_calendar(&tedo_state, events, chrono::Local::now().naive_local());
+    match output {
+        Some(path) => {
+            fs::write(path, ics)?;
+            println!("Exported to {}", path.display());
+        }
+        None => print!("{}", ics),
+    }
+    Ok(())
+}
+
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Todo {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub completed: bool,
+    pub due: Option<NaiveDate>,
+    pub start: Option<NaiveDate>,
+    pub categories: Vec<String>,
+    pub reminder: Option<NaiveDateTime>,
+}
+
+
+// Dates and date-times, with or without a time zone, are cut down to the day
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+
+// Splits `NAME;PARAM=x:value` into the name, its parameters and the value
+fn split_property(line: &str) -> Option<(String, String, &str)> {
+    let (head, value) = line.split_once(':')?;
+    let (name, params) = head.split_once(';').unwrap_or((head, ""));
+    Some((name.to_uppercase(), params.to_uppercase(), value))
+}
+
+
+pub fn parse_todos(text: &str) -> Vec<Todo> {
+    // Unfold continuation lines first
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut todos = Vec::new();
+    let mut todo: Option<Todo> = None;
+    let mut in_alarm = false;
+    for line in &lines {
+        let Some((name, params, value)) = split_property(line) else {
+            continue;
+        };
+        match (name.as_str(), value.trim().to_uppercase().as_str()) {
+            ("BEGIN", "VTODO") => todo = Some(Todo::default()),
+            ("END", "VTODO") => todos.extend(todo.take()),
+            ("BEGIN", "VALARM") => in_alarm = true,
+            ("END", "VALARM") => in_alarm = false,
+            _ => {}
+        }
+        let Some(todo) = todo.as_mut() else {
+            continue;
+        };
+
+        let value = value.trim();
+        if in_alarm {
+            if name == "TRIGGER" && params.contains("VALUE=DATE-TIME") {
+                todo.reminder = parse_datetime(value);
+            }
+            continue;
+        }
+        match name.as_str() {
+            "UID" => todo.uid = Some(value.to_string()),
+            "SUMMARY" => todo.summary = unescape(value),
+            "STATUS" => todo.completed = value.eq_ignore_ascii_case("COMPLETED"),
+            "COMPLETED" => todo.completed = true,
+            "DUE" => todo.due = parse_date(value),
+            "DTSTART" => todo.start = parse_date(value),
+            "CATEGORIES" => todo.categories.extend(
+                value.split(',').map(|tag| unescape(tag.trim())).filter(|tag| !tag.is_empty())
+            ),
+            _ => {}
+        }
+    }
+    todos
+}
+
+
+fn task_with_uid(project: &Project, uid: &str) -> Option<usize> {
+    project.tasks.iter().position(|t| !t.is_template() && task_uid(project, t) == uid)
+}
+
+
+// Adds the todos to the project, or updates the tasks that have the same
+// UID. Returns the number of (created, updated) tasks.
+pub fn import_todos(project: &mut Project, todos: Vec<Todo>, today: NaiveDate) -> (usize, usize) {
+    let (mut created, mut updated) = (0, 0);
+    let mut closed = Vec::new();
+    for todo in todos.into_iter().filter(|todo| !todo.summary.is_empty()) {
+        let existing = todo.uid.as_ref().and_then(|uid| task_with_uid(project, uid));
+        let index = match existing {
+            Some(index) => {
+                updated += 1;
+                index
+            }
+            None => {
+                let next_id = project.allocate_task_id();
+                let mut task = Task::new(next_id, &todo.summary);
+                // A UID tedo made up elsewhere could clash with the ones of
+                // this project
+                task.uid = todo.uid.clone().filter(|uid| !uid.ends_with("@tedo"));
+                project.tasks.push(task);
+                created += 1;
+                project.tasks.len() - 1
+            }
+        };
+
+        let task = &mut project.tasks[index];
+        task.description = todo.summary;
+        task.due = todo.due;
+        task.scheduled = todo.start;
+        if !todo.categories.is_empty() {
+            let categories: Vec<&str> = todo.categories.iter().map(String::as_str).collect();
+            tags::update_tags(&mut task.tags, &categories, false);
+        }
+        if task.reminder != todo.reminder {
+            task.reminder = todo.reminder;
+            task.reminded = false;
+        }
+        match (task.is_done(), todo.completed) {
+            (false, true) => {
+                task.status = TaskStatus::Done;
+                closed.push(task.id);
+            }
+            (true, false) => task.status = TaskStatus::Open,
+            _ => {}
+        }
+    }
+    project.generate_next_instances(&closed, today);
+    (created, updated)
+}
+
+
+pub fn import_ics(base_dir: &Path, project: Option<&str>, path: &Path) -> Result<(), TedoError> {
+    let text = fs::read_to_string(path)?;
+    let todos = parse_todos(&text);
+    if todos.is_empty() {
+        return Err(TedoError::InvalidInput(format!("No VTODO entries found in {}", path.display())));
+    }
+
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let index = tedo_state.scoped_project_index(project)?;
+    let today = dates::today();
+
+    // Todos that came from a task in another project update that task
+    let (mut todos, mut updated) = (todos, 0);
+    for other in (0..tedo_state.projects.len()).filter(|&other| other != index) {
+        let project = &mut tedo_state.projects[other];
+        let (own, rest): (Vec<Todo>, Vec<Todo>) = todos.into_iter()
+            .partition(|todo| todo.uid.as_ref().is_some_and(|uid| task_with_uid(project, uid).is_some()));
+        todos = rest;
+        if !own.is_empty() {
+            updated += import_todos(project, own, today).1;
+        }
+    }
+
+    let project = &mut tedo_state.projects[index];
+    let (created, updated_here) = import_todos(project, todos, today);
+    let (updated, name) = (updated + updated_here, project.name.clone());
+    save_state(base_dir, &tedo_state)?;
+    println!("Imported {} new and {} updated task(s) into {}.", created, updated, name);
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::projects::create_project;
+    use crate::tasks::{add_task, close_task, create_task};
+
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn now() -> NaiveDateTime {
+        date("2026-10-21").and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_export_todos_and_events() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "ops", true).unwrap();
+        add_task(base_dir, None, Task { due: Some(date("2026-10-23")), ..Task::new(0, "Renew certificate; then deploy") }).unwrap();
+        create_task(base_dir, None, "No date", None).unwrap();
+        add_task(base_dir, None, Task { scheduled: Some(date("2026-10-22")), ..Task::new(0, "Plan offsite") }).unwrap();
+        crate::tags::tag_task(base_dir, None, 1, &["security"], false).unwrap();
+        close_task(base_dir, None, 3, false).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        let ics = export_calendar(&state, false, now());
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VTODO").count(), 2);
+        assert!(ics.contains(&format!("UID:1-1@tedo\r\nDTSTAMP:{}\r\nSUMMARY:Renew certificate\\; then deploy\r\n", format_utc(now()))));
+        assert!(ics.contains("CATEGORIES:security\r\nDUE;VALUE=DATE:20261023\r\nSTATUS:NEEDS-ACTION\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20261022\r\nSTATUS:COMPLETED\r\n"));
+        assert!(!ics.contains("No date"));
+
+        let ics = export_calendar(&state, true, now());
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20261023\r\nDTEND;VALUE=DATE:20261024\r\n"));
+    }
+
+    #[test]
+    fn test_long_lines_are_folded_and_unfolded() {
+        let mut state = TedoState::default();
+        let mut project = Project::new(1, "ops");
+        let description = "Ünïcödé ".repeat(20).trim_end().to_string();
+        project.tasks.push(Task { due: Some(date("2026-10-23")), ..Task::new(1, &description) });
+        state.projects.push(project);
+
+        let ics = export_calendar(&state, false, now());
+        assert!(ics.lines().all(|line| line.len() <= 75));
+        assert_eq!(parse_todos(&ics)[0].summary, description);
+    }
+
+    #[test]
+    fn test_parse_todos() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:event\r\nSUMMARY:Not a todo\r\nEND:VEVENT\r\n\
+            BEGIN:VTODO\r\nUID:abc@example.com\r\nSUMMARY:Call\\, then write\r\n  the vendor\r\n\
+            DUE;TZID=Europe/Berlin:20261030T170000\r\nCATEGORIES:work,calls\r\nSTATUS:COMPLETED\r\n\
+            BEGIN:VALARM\r\nTRIGGER;VALUE=DATE-TIME:20261029T090000Z\r\nDESCRIPTION:ignored\r\nEND:VALARM\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+
+        assert_eq!(parse_todos(ics), vec![Todo {
+            uid: Some("abc@example.com".into()),
+            summary: "Call, then write the vendor".into(),
+            completed: true,
+            due: Some(date("2026-10-30")),
+            start: None,
+            categories: vec!["work".into(), "calls".into()],
+            reminder: Some(Utc.from_utc_datetime(&date("2026-10-29").and_hms_opt(9, 0, 0).unwrap()).with_timezone(&Local).naive_local()),
+        }]);
+    }
+
+    #[test]
+    fn test_round_trip_updates_the_original_task() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        let path = dir.path().join("tasks.ics");
+
+        create_project(base_dir, "ops", true).unwrap();
+        create_project(base_dir, "calendar", false).unwrap();
+        add_task(base_dir, None, Task { due: Some(date("2026-10-23")), ..Task::new(0, "Renew certificate") }).unwrap();
+
+        // Importing into the same project updates the task
+        export_ics(base_dir, None, Some(&path), false).unwrap();
+        let edited = fs::read_to_string(&path).unwrap().replace("20261023", "20261030");
+        fs::write(&path, edited).unwrap();
+        import_ics(base_dir, None, &path).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].tasks.len(), 1);
+        assert_eq!(state.projects[0].tasks[0].due, Some(date("2026-10-30")));
+
+        // Importing into another project still updates the original task
+        fs::write(&path, fs::read_to_string(&path).unwrap().replace("20261030", "20261106")).unwrap();
+        import_ics(base_dir, Some("calendar"), &path).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert!(state.projects[1].tasks.is_empty());
+        assert_eq!(state.projects[0].tasks[0].due, Some(date("2026-11-06")));
+
+        // UIDs made up by another tedo are not kept, as they could clash
+        let foreign = fs::read_to_string(&path).unwrap().replace("UID:1-1@tedo", "UID:7-9@tedo");
+        fs::write(&path, foreign).unwrap();
+        import_ics(base_dir, Some("calendar"), &path).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[1].tasks[0].uid, None);
+        let ics = export_calendar(&state, false, now());
+        assert_eq!(ics.matches("UID:1-1@tedo").count(), 1);
+        assert_eq!(ics.matches("UID:2-1@tedo").count(), 1);
+        assert!(!ics.contains("7-9@tedo"));
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use colored::Colorize;
+
+use crate::error::TedoError;
+use crate::storage::{save_state, Project};
+use crate::{config, markdown, storage};
+
+
+// Every project gets one journal note per day, titled "Journal <date>" and
+// tagged "journal". Entries are appended to it as timestamped list items.
+
+pub fn journal_title(date: NaiveDate) -> String {
+    format!("Journal {}", date.format("%Y-%m-%d"))
+}
+
+
+fn journal_note_id(project: &Project, date: NaiveDate) -> Option<u32> {
+    let title = journal_title(date);
+    project.notes.iter()
+        .find(|n| n.description == title && n.tags.iter().any(|t| t == "journal"))
+        .map(|n| n.id)
+}
+
+
+pub fn add_entry(base_dir: &Path, project: Option<&str>, text: &str, now: NaiveDateTime) -> Result<(), TedoError> {
+    let keep = config::load_config(base_dir)?.revision_limit();
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    let entry = format!("- {} {}\n", now.format("%H:%M"), text.trim_end());
+    match journal_note_id(project, now.date()) {
+        Some(note_id) => project.note_mut(note_id)?.append(&entry, now, keep),
+        None => {
+            project.add_note(&journal_title(now.date()), &entry, &["journal"], now, keep);
+        }
+    }
+    save_state(base_dir, &tedo_state)
+}
+
+
+pub fn show_journal(base_dir: &Path, project: Option<&str>, from: NaiveDate, to: NaiveDate, raw: bool) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project = &tedo_state.projects[tedo_state.scoped_project_index(project)?];
+
+    let mut found = false;
+    for date in from.iter_days().take_while(|date| *date <= to) {
+        let note = match journal_note_id(project, date) {
+            Some(note_id) => project.note(note_id)?,
+            None => continue,
+        };
+        if found {
+            println!();
+        }
+        found = true;
+        if raw {
+            println!("## {}", date.format("%A %Y-%m-%d"));
+            print!("{}", note.content);
+        } else {
+            println!("{}", date.format("%A %Y-%m-%d").to_string().bold().blue());
+            println!("{}", markdown::render(&note.content));
+        }
+    }
+
+    if !found {
+        println!("No journal entries between {} and {}.", from, to);
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::projects::create_project;
+
+    use super::*;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_entries_go_to_one_note_per_day() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        add_entry(base_dir, None, "Started on the parser", at("2026-10-19", "09:05")).unwrap();
+        add_entry(base_dir, None, "Parser done\n", at("2026-10-19", "17:30")).unwrap();
+        add_entry(base_dir, None, "Reviews", at("2026-10-20", "10:00")).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        let notes = &state.projects[0].notes;
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].description, "Journal 2026-10-19");
+        assert_eq!(notes[0].tags, vec!["journal"]);
+        assert_eq!(notes[0].content, "- 09:05 Started on the parser\n- 17:30 Parser done\n");
+        assert_eq!(notes[1].content, "- 10:00 Reviews\n");
+        assert_eq!(notes[0].revisions.len(), 2);
+    }
+}
@@ -0,0 +1,222 @@
+use std::path::Path;
+
+use crate::error::TedoError;
+use crate::storage;
+use crate::storage::{save_state, Note, Project};
+
+
+// A reference written inside note content: `[[note:3]]` or `#task:12`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reference {
+    Note(u32),
+    Task(u32),
+}
+
+
+pub fn parse_references(content: &str) -> Vec<Reference> {
+    let mut references = Vec::new();
+
+    for (start, pattern) in content.match_indices("[[note:") {
+        let rest = &content[start + pattern.len()..];
+        if let Some(end) = rest.find("]]") {
+            if let Ok(id) = rest[..end].trim().parse::<u32>() {
+                push_unique(&mut references, Reference::Note(id));
+            }
+        }
+    }
+
+    for (start, pattern) in content.match_indices("#task:") {
+        let digits: String = content[start + pattern.len()..].chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if let Ok(id) = digits.parse::<u32>() {
+            push_unique(&mut references, Reference::Task(id));
+        }
+    }
+
+    references
+}
+
+
+fn push_unique(references: &mut Vec<Reference>, reference: Reference) {
+    if !references.contains(&reference) {
+        references.push(reference);
+    }
+}
+
+
+// Where a note is referenced from
+pub struct Backlinks {
+    pub tasks: Vec<u32>,
+    pub notes: Vec<u32>,
+}
+
+
+impl Project {
+    pub fn note(&self, id: u32) -> Result<&Note, TedoError> {
+        self.notes.iter().find(|n| n.id == id)
+            .ok_or_else(|| TedoError::NotFound(format!("Note with id {} not found.", id)))
+    }
+
+    pub fn note_mut(&mut self, id: u32) -> Result<&mut Note, TedoError> {
+        self.notes.iter_mut().find(|n| n.id == id)
+            .ok_or_else(|| TedoError::NotFound(format!("Note with id {} not found.", id)))
+    }
+
+    // Notes attached to a task, either explicitly or because the note content
+    // mentions `#task:<id>`. May include ids of notes that no longer exist.
+    pub fn task_notes(&self, task_id: u32) -> Vec<u32> {
+        let mut notes: Vec<u32> = self.task(task_id).map(|t| t.notes.clone()).unwrap_or_default();
+        for note in &self.notes {
+            if parse_references(&note.content).contains(&Reference::Task(task_id)) && !notes.contains(&note.id) {
+                notes.push(note.id);
+            }
+        }
+        notes
+    }
+
+    pub fn note_backlinks(&self, note_id: u32) -> Backlinks {
+        let tasks = self.tasks.iter()
+            .filter(|t| self.task_notes(t.id).contains(&note_id))
+            .map(|t| t.id)
+            .collect();
+        let notes = self.notes.iter()
+            .filter(|n| n.id != note_id && parse_references(&n.content).contains(&Reference::Note(note_id)))
+            .map(|n| n.id)
+            .collect();
+        Backlinks { tasks, notes }
+    }
+
+    pub fn reference_exists(&self, reference: Reference) -> bool {
+        match reference {
+            Reference::Note(id) => self.note(id).is_ok(),
+            Reference::Task(id) => self.task(id).is_ok(),
+        }
+    }
+
+    // Human readable descriptions of every link whose target was deleted
+    pub fn broken_links(&self) -> Vec<String> {
+        let mut broken = Vec::new();
+        for task in &self.tasks {
+            for note_id in task.notes.iter().filter(|id| self.note(**id).is_err()) {
+                broken.push(format!("task {} is attached to missing note {}", task.id, note_id));
+            }
+        }
+        for note in &self.notes {
+            for reference in parse_references(&note.content) {
+                if self.reference_exists(reference) {
+                    continue;
+                }
+                match reference {
+                    Reference::Note(id) => broken.push(format!("note {} references missing note {}", note.id, id)),
+                    Reference::Task(id) => broken.push(format!("note {} references missing task {}", note.id, id)),
+                }
+            }
+        }
+        broken
+    }
+}
+
+
+pub fn attach_note(base_dir: &Path, project: Option<&str>, task_id: u32, note_id: u32) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    project.note(note_id)?;
+    let task = project.task_mut(task_id)?;
+    if !task.notes.contains(&note_id) {
+        task.notes.push(note_id);
+    }
+    save_state(base_dir, &tedo_state)
+}
+
+
+pub fn detach_note(base_dir: &Path, project: Option<&str>, task_id: u32, note_id: u32) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    let task = project.task_mut(task_id)?;
+    if !task.notes.contains(&note_id) {
+        return Err(TedoError::NotFound(format!("Note {} is not attached to task {}", note_id, task_id)));
+    }
+    task.notes.retain(|id| *id != note_id);
+    save_state(base_dir, &tedo_state)
+}
+
+
+pub fn list_broken_links(base_dir: &Path, project: Option<&str>) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project = &tedo_state.projects[tedo_state.scoped_project_index(project)?];
+
+    let broken = project.broken_links();
+    if broken.is_empty() {
+        println!("No broken links.");
+    }
+    for link in broken {
+        println!("{}", link);
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::notes::{create_note, delete_note};
+    use crate::projects::create_project;
+    use crate::tasks::create_task;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_references() {
+        let content = "See [[note:3]] and [[note: 4]], fixes #task:12 and #task:12.\n[[note:x]] #task:";
+        assert_eq!(parse_references(content), vec![Reference::Note(3), Reference::Note(4), Reference::Task(12)]);
+    }
+
+    #[test]
+    fn test_task_notes_and_backlinks() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_task(base_dir, None, "test_task", None).unwrap();
+        create_note(base_dir, None, "attached", "").unwrap();
+        create_note(base_dir, None, "mentions", "Work on #task:1, see [[note:1]]").unwrap();
+        attach_note(base_dir, None, 1, 1).unwrap();
+        attach_note(base_dir, None, 1, 1).unwrap();
+
+        let project = storage::load_state(base_dir).unwrap().projects.remove(0);
+        assert_eq!(project.tasks[0].notes, vec![1]);
+        assert_eq!(project.task_notes(1), vec![1, 2]);
+
+        let backlinks = project.note_backlinks(1);
+        assert_eq!(backlinks.tasks, vec![1]);
+        assert_eq!(backlinks.notes, vec![2]);
+
+        detach_note(base_dir, None, 1, 1).unwrap();
+        assert!(detach_note(base_dir, None, 1, 1).is_err());
+        assert!(matches!(attach_note(base_dir, None, 1, 9), Err(TedoError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_broken_links_after_delete() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_task(base_dir, None, "test_task", None).unwrap();
+        create_note(base_dir, None, "attached", "").unwrap();
+        create_note(base_dir, None, "mentions", "[[note:1]] #task:2").unwrap();
+        attach_note(base_dir, None, 1, 1).unwrap();
+        delete_note(base_dir, None, 1).unwrap();
+
+        let project = storage::load_state(base_dir).unwrap().projects.remove(0);
+        assert_eq!(project.broken_links(), vec![
+            "task 1 is attached to missing note 1".to_string(),
+            "note 2 references missing note 1".to_string(),
+            "note 2 references missing task 2".to_string(),
+        ]);
+    }
+}
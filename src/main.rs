@@ -1,13 +1,40 @@
 use std::path::Path;
-use crate::projects::current_project;
+use crate::error::TedoError;
 
-use crate::storage::Project;
+use crate::storage::{Project, ProjectStatus, Task};
 
+use colored::Colorize;
+
+mod error;
 mod storage;
 mod projects;
 mod tasks;
 mod notes;
 mod tedo;
+mod dependencies;
+mod links;
+mod show;
+mod markdown;
+mod tags;
+mod config;
+mod editor;
+mod front_matter;
+mod note_files;
+mod templates;
+mod dates;
+mod journal;
+mod revisions;
+mod merge;
+mod git;
+mod time_tracking;
+mod focus;
+mod recurrence;
+mod agenda;
+mod reminders;
+mod ical;
+mod history;
+mod summary;
+mod stats;
 
 
 static PROJECT_SHORTHANDS: [&str; 4] = ["project", "pr", "proj", "pro"];
@@ -15,202 +42,583 @@ fn main() {
     let base_dir = dirs::home_dir().unwrap().join(".tedo");
     let args: Vec<String> = std::env::args().collect();
 
-    let known_subcommands = vec!["project", "init", "create", "list", "edit", "switch", "table"];
-    let processed_args;
-    let mut clap_args = args.clone();
+    let processed_args = preprocess_arguments(&args);
+    let matches = process_matches(&processed_args);
+    let result = handle_arguments(&base_dir, &matches, &args, None)
+        .and_then(|_| if base_dir.exists() { git::commit_changes(&base_dir) } else { Ok(()) });
+    if let Err(error) = result {
+        eprintln!("{} {}", "error:".red().bold(), error);
+        std::process::exit(error.exit_code());
+    }
+}
+
+
+fn preprocess_arguments(args: &[String]) -> Vec<String> {
+    let mut clap_args = args.to_vec();
 
 
-    if args.len() > 3 && PROJECT_SHORTHANDS.contains(&args[1].as_str()) {
+    if args.len() > 1 && (args[1] == "p" || PROJECT_SHORTHANDS.contains(&args[1].as_str())) {
+        clap_args[1] = "project".to_string();
         clap_args.truncate(3); // Only take the first 3 arguments for clap
     }
 
-    if clap_args.len() > 1 && !known_subcommands.contains(&clap_args[1].as_str()) {
+    if clap_args.len() > 1 && !known_subcommands().contains(&clap_args[1]) && !clap_args[1].starts_with('-') {
         // Convert shortcuts to potential subcommands
-        let new_args = arguments_from_shortcut(&args);
+        let new_args = arguments_from_shortcut(&clap_args);
 
         // Merge the new args with the old ones
         let mut merged_args = vec![clap_args[0].clone()];
         merged_args.extend(new_args);
         merged_args.extend(clap_args[2..].to_vec());
 
-        processed_args = merged_args;
+        merged_args
     } else {
-        processed_args = clap_args.clone();
+        clap_args
     }
+}
 
-    let matches = process_matches(&processed_args);
-    handle_arguments(&base_dir, &matches, &args);
+
+// The -p/--project flag may be given at any level of the subcommand chain.
+fn project_flag<'a>(matches: &'a clap::ArgMatches) -> Option<&'a str> {
+    if let Some(project) = matches.value_of("project") {
+        return Some(project);
+    }
+    match matches.subcommand() {
+        (_, Some(sub_matches)) => project_flag(sub_matches),
+        _ => None,
+    }
 }
 
 
-fn initialize_tedo() {
+fn initialize_tedo() -> Result<(), TedoError> {
     use std::fs;
     use std::io;
 
     println!("Are you sure you want to initialize tedo on your machine? A folder named .tedo will be created in your home directory (y/n)");
 
     let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
+    io::stdin().read_line(&mut input)?;
 
     if input.trim() == "y" {
         let path = dirs::home_dir().unwrap().join(".tedo");
-        fs::create_dir_all(&path).expect("Failed to create .tedo directory");
+        fs::create_dir_all(&path)?;
         println!(".tedo directory has been created successfully!");
     } else {
         println!("Initialization aborted.");
     }
+    Ok(())
 }
 
-fn handle_arguments(base_dir: &Path, matches: &clap::ArgMatches, args: &[String]) {
+fn handle_arguments(base_dir: &Path, matches: &clap::ArgMatches, args: &[String], scope: Option<&str>) -> Result<(), TedoError> {
 
     // Handling the init subcommand
     if matches.subcommand_matches("init").is_some() {
-        initialize_tedo();
-        return;
+        return initialize_tedo();
     }
 
 
-    if Path::new(&base_dir).exists() {
-
-        // Create
+    if !Path::new(&base_dir).exists() {
+        return Err(TedoError::NotInitialized);
+    }
 
-        if let Some(matches) = matches.subcommand_matches("create") {
-            if let Some(note_matches) = matches.subcommand_matches("note") {
-                let note_description: Vec<&str> = note_matches
-                    .values_of("note_description")
-                    .unwrap()
-                    .collect();
-                let note_description = note_description.join(" ");
-                notes::create_note(&base_dir, &note_description, "");
+    // An explicit --project flag wins over a `tedo project <id>` prefix
+    let scope = project_flag(matches).or(scope);
+
+    // Create
+
+    if let Some(matches) = matches.subcommand_matches("create") {
+        if let Some(note_matches) = matches.subcommand_matches("note") {
+            let note_description: Vec<&str> = note_matches
+                .values_of("note_description")
+                .unwrap()
+                .collect();
+            let note_description = note_description.join(" ");
+            let content = if let Some(template) = note_matches.value_of("template") {
+                let project = projects::scoped_project(base_dir, scope)?;
+                let variables = templates::note_variables(&note_description, &project.name);
+                templates::render(&templates::load_template(base_dir, template)?, &variables)
+            } else if let Some(content) = note_matches.value_of("content") {
+                content.to_string()
+            } else if let Some(path) = note_matches.value_of("file") {
+                std::fs::read_to_string(path)?
+            } else {
+                read_piped_stdin()?.unwrap_or_default()
+            };
+            let tags: Vec<&str> = note_matches.values_of("tag").map(|tags| tags.collect()).unwrap_or_default();
+            if note_matches.is_present("edit") || note_matches.is_present("template") {
+                notes::create_note_in_editor(base_dir, scope, &note_description, &content, &tags)?;
+            } else {
+                let note_id = notes::create_note(base_dir, scope, &note_description, &content)?;
+                if !tags.is_empty() {
+                    tags::tag_note(base_dir, scope, note_id, &tags, false)?;
+                }
             }
-            if let Some(project_matches) = matches.subcommand_matches("project") {
-                let project_name = project_matches.value_of("project_name").unwrap();
-                let switch = project_matches.is_present("switch");
-                projects::create_project(&base_dir, project_name, switch);
+        }
+        if let Some(project_matches) = matches.subcommand_matches("project") {
+            let mut project_name = project_matches.value_of("project_name").unwrap().to_string();
+            if let Some(parent) = project_matches.value_of("parent") {
+                project_name = format!("{}/{}", Project::find(base_dir, parent)?.name, project_name);
             }
+            let switch = project_matches.is_present("switch");
+            projects::create_project(base_dir, &project_name, switch)?;
+        }
 
-            if let Some(task_matches) = matches.subcommand_matches("task") {
-                let task_description: Vec<&str> = task_matches
-                    .values_of("task_description")
-                    .unwrap()
-                    .collect();
-                let task_description = task_description.join(" ");
-                tasks::create_task(&base_dir, &task_description);
+        if let Some(task_matches) = matches.subcommand_matches("task") {
+            let task_description: Vec<&str> = task_matches
+                .values_of("task_description")
+                .unwrap()
+                .collect();
+            let task_description = task_description.join(" ");
+            let parent = task_matches.value_of("parent").map(|id| parse_id(id, "task")).transpose()?;
+            let due = task_matches.value_of("due").map(|due| dates::parse_due_date(due, dates::today())).transpose()?;
+            let tags: Vec<&str> = task_matches.values_of("tag").map(|tags| tags.collect()).unwrap_or_default();
+            match task_matches.value_of("every") {
+                Some(rule) => {
+                    let rule = storage::Recurrence::parse(rule)?;
+                    recurrence::create_recurring_task(base_dir, scope, &task_description, rule, due.unwrap_or_else(dates::today), &tags)?;
+                }
+                None => {
+                    let mut task = Task::new(0, &task_description);
+                    task.parent = parent;
+                    task.due = due;
+                    task.scheduled = task_matches.value_of("scheduled").map(|scheduled| dates::parse_due_date(scheduled, dates::today())).transpose()?;
+                    task.reminder = task_matches.value_of("remind").map(|remind| dates::parse_datetime(remind, chrono::Local::now().naive_local())).transpose()?;
+                    tags::update_tags(&mut task.tags, &tags, false);
+                    tasks::add_task(base_dir, scope, task)?;
+                }
             }
+        }
 
 
-        // List
+    // List
 
-        } else if let Some(matches) = matches.subcommand_matches("list") {
-            if let Some(_project_matches) = matches.subcommand_matches("projects") {
-                projects::list_projects(&base_dir, "list");
-            } else if let Some(task_matches) = matches.subcommand_matches("tasks") {
-
-
-                if let Some(_) = task_matches.subcommand_matches("all") {
-                    println!("All tasks");
-                    tasks::list_tasks(&base_dir, "list");
-                } else {
-                    let project = current_project(&base_dir);
-                    if let Some(project) = project {
-                        project.list_tasks("list");
-                    } else {
-                        println!("No selected project. Please switch to a project before listing tasks.");
-                    }
-                }
-            } else if let Some(_note_matches) = matches.subcommand_matches("notes") {
-                notes::list_notes(&base_dir, "list");
+    } else if let Some(matches) = matches.subcommand_matches("list") {
+        if let Some(project_matches) = matches.subcommand_matches("projects") {
+            projects::list_projects(base_dir, "list", project_matches.is_present("archived"))?;
+        } else if let Some(task_matches) = matches.subcommand_matches("tasks") {
+
+
+            if task_matches.subcommand_matches("all").is_some() {
+                println!("All tasks");
+                tasks::list_tasks(base_dir, scope, "list")?;
             } else {
-                tedo::list(&base_dir);
+                tasks::list_project_tasks(base_dir, scope, "list", task_matches.is_present("hide_blocked"))?;
             }
+        } else if let Some(_note_matches) = matches.subcommand_matches("notes") {
+            notes::list_notes(base_dir, scope, "list")?;
+        } else if matches.subcommand_matches("recurring").is_some() {
+            recurrence::list_recurring_tasks(base_dir, scope)?;
+        } else {
+            tedo::list(base_dir, matches.is_present("archived"))?;
+        }
 
 
-        //  Table
-
-        } else if let Some(matches) = matches.subcommand_matches("table") {
-            if let Some(_project_matches) = matches.subcommand_matches("projects") {
-                projects::list_projects(&base_dir, "table");
-            } else if let Some(task_matches) = matches.subcommand_matches("tasks") {
-                if let Some(project_matches) = task_matches.subcommand_matches("project") {
-                    let project_identifier = project_matches
-                        .value_of("project_identifier");
+    //  Table
 
-                    if let Some(project_identifier) = project_identifier {
-                        let project = Project::find(base_dir, project_identifier);
-                        if let Some(project) = project {
-                            project.list_tasks("table")
-                        }
-                    }
+    } else if let Some(matches) = matches.subcommand_matches("table") {
+        if let Some(project_matches) = matches.subcommand_matches("projects") {
+            projects::list_projects(base_dir, "table", project_matches.is_present("archived"))?;
+        } else if let Some(task_matches) = matches.subcommand_matches("tasks") {
+            if let Some(project_matches) = task_matches.subcommand_matches("project") {
+                let project_identifier = project_matches
+                    .value_of("project_identifier");
 
-                }
-                // tasks::list_tasks(&base_dir, "table");
-            } else if let Some(_note_matches) = matches.subcommand_matches("notes") {
-                notes::list_notes(&base_dir, "table");
+                tasks::list_project_tasks(base_dir, project_identifier.or(scope), "table", task_matches.is_present("hide_blocked"))?;
+            } else if task_matches.subcommand_matches("all").is_some() {
+                tasks::list_tasks(base_dir, scope, "table")?;
+            } else {
+                tasks::list_project_tasks(base_dir, scope, "table", task_matches.is_present("hide_blocked"))?;
             }
+        } else if let Some(_note_matches) = matches.subcommand_matches("notes") {
+            notes::list_notes(base_dir, scope, "table")?;
+        }
 
 
-        // Switch
+    // Archive
 
+    } else if let Some(matches) = matches.subcommand_matches("archive") {
+        projects::set_project_status(base_dir, matches.value_of("project_identifier").or(scope), ProjectStatus::Archived)?;
+    } else if let Some(matches) = matches.subcommand_matches("unarchive") {
+        projects::set_project_status(base_dir, matches.value_of("project_identifier").or(scope), ProjectStatus::Active)?;
 
-        } else if let Some(matches) = matches.subcommand_matches("switch") {
-            let project_name = matches.value_of("project_name").unwrap();
-            projects::switch_project(&base_dir, project_name);
 
+    // Switch
 
 
-        // Edit
+    } else if let Some(matches) = matches.subcommand_matches("switch") {
+        let project_name = matches.value_of("project_name").unwrap();
+        projects::switch_project(base_dir, project_name)?;
 
-        } else if let Some(matches) = matches.subcommand_matches("edit") {
-            if let Some(note_matches) = matches.subcommand_matches("note") {
-                let note_identifier = note_matches.value_of("note_identifier").unwrap();
-                let note_id = note_identifier.parse::<u32>().expect("Failed to parse note identifier");
-                notes::edit_note(&base_dir, note_id);
-            }
-            // Project
-        } else if let Some(matches) = matches.subcommand_matches("project") {
-            let project_identifier = matches.value_of("project_identifier").unwrap();
 
 
-            let project = Project::find(base_dir, project_identifier);
-            if let Some(project) = project {
-                println!("{} {}", project.id, project.name);
-            }
+    // Edit
 
-            println!("Project: {:?}", args);
-            if args.len() > 3 {
-                let additional_args: Vec<String> = args[2..].to_vec();
-                println!("{:?}", additional_args);
-                // Now additional_args contains your ["here", "are", "more", "arguments"]
-                // Do something with additional_args...
-                handle_arguments(&base_dir, &process_matches(&additional_args), &additional_args);
-            }
+    } else if let Some(matches) = matches.subcommand_matches("edit") {
+        if let Some(note_matches) = matches.subcommand_matches("note") {
+            let note_id = notes::resolve_note_id(base_dir, scope, note_matches.value_of("note_identifier").unwrap())?;
+            notes::edit_note(base_dir, scope, note_id)?;
+        }
+        if let Some(task_matches) = matches.subcommand_matches("task") {
+            let task_id = parse_id(task_matches.value_of("task_identifier").unwrap(), "task")?;
+            tasks::edit_task(base_dir, scope, task_id)?;
+        }
+        if let Some(project_matches) = matches.subcommand_matches("project") {
+            projects::edit_project(base_dir, project_matches.value_of("project_identifier").or(scope))?;
+        }
+
+
+    // Note
+
+    } else if let Some(matches) = matches.subcommand_matches("note") {
+        if let Some(append_matches) = matches.subcommand_matches("append") {
+            let note_id = notes::resolve_note_id(base_dir, scope, append_matches.value_of("note_identifier").unwrap())?;
+            let text = match append_matches.values_of("text") {
+                Some(text) => text.collect::<Vec<&str>>().join(" "),
+                None => read_piped_stdin()?.ok_or_else(|| TedoError::InvalidInput("Nothing to append, pass text or pipe it in".into()))?,
+            };
+            notes::append_note(base_dir, scope, note_id, &text)?;
+        } else if let Some(history_matches) = matches.subcommand_matches("history") {
+            let note_id = notes::resolve_note_id(base_dir, scope, history_matches.value_of("note_identifier").unwrap())?;
+            revisions::show_history(base_dir, scope, note_id)?;
+        } else if let Some(diff_matches) = matches.subcommand_matches("diff") {
+            let note_id = notes::resolve_note_id(base_dir, scope, diff_matches.value_of("note_identifier").unwrap())?;
+            let mut numbers = diff_matches.values_of("revisions").into_iter().flatten().map(|rev| parse_id(rev, "revision"));
+            let from = numbers.next().transpose()?;
+            let to = numbers.next().transpose()?;
+            revisions::show_diff(base_dir, scope, note_id, from, to)?;
+        } else if let Some(revert_matches) = matches.subcommand_matches("revert") {
+            let note_id = notes::resolve_note_id(base_dir, scope, revert_matches.value_of("note_identifier").unwrap())?;
+            let revision = parse_id(revert_matches.value_of("revision").unwrap(), "revision")?;
+            revisions::revert_note(base_dir, scope, note_id, revision)?;
+        }
+
+
+    // Journal
+
+    } else if let Some(matches) = matches.subcommand_matches("journal") {
+        if let Some(show_matches) = matches.subcommand_matches("show") {
+            let today = dates::today();
+            let from = if show_matches.is_present("week") {
+                dates::parse_date("monday", today)?
+            } else {
+                show_matches.value_of("since").map(|since| dates::parse_date(since, today)).transpose()?.unwrap_or(today)
+            };
+            let to = show_matches.value_of("until").map(|until| dates::parse_date(until, today)).transpose()?.unwrap_or(today);
+            journal::show_journal(base_dir, scope, from, to, show_matches.is_present("raw"))?;
+        } else {
+            let text = match matches.values_of("text") {
+                Some(text) => text.collect::<Vec<&str>>().join(" "),
+                None => read_piped_stdin()?.ok_or_else(|| TedoError::InvalidInput("Nothing to log, pass text or pipe it in".into()))?,
+            };
+            journal::add_entry(base_dir, scope, &text, chrono::Local::now().naive_local())?;
+        }
+
+
+    // Time tracking
+
+    } else if let Some(matches) = matches.subcommand_matches("start") {
+        let task_id = parse_id(matches.value_of("task_identifier").unwrap(), "task")?;
+        time_tracking::start_timer(base_dir, scope, task_id, chrono::Local::now().naive_local())?;
+    } else if matches.subcommand_matches("stop").is_some() {
+        time_tracking::stop_timer(base_dir, chrono::Local::now().naive_local())?;
+    } else if let Some(matches) = matches.subcommand_matches("time") {
+        let today = dates::today();
+        if let Some(log_matches) = matches.subcommand_matches("log") {
+            let task_id = parse_id(log_matches.value_of("task_identifier").unwrap(), "task")?;
+            let duration = time_tracking::parse_duration(log_matches.value_of("duration").unwrap())?;
+            let date = log_matches.value_of("date").map(|date| dates::parse_date(date, today)).transpose()?.unwrap_or(today);
+            time_tracking::log_time(base_dir, scope, task_id, duration, date, chrono::Local::now().naive_local())?;
+        } else if let Some(report_matches) = matches.subcommand_matches("report") {
+            let grouping = match report_matches.value_of("by") {
+                Some("tag") => time_tracking::Grouping::Tag,
+                Some("day") => time_tracking::Grouping::Day,
+                _ => time_tracking::Grouping::Project,
+            };
+            let since = report_matches.value_of("since").map(|since| dates::parse_date(since, today)).transpose()?;
+            time_tracking::time_report(base_dir, scope, grouping, since, report_matches.is_present("csv"), chrono::Local::now().naive_local())?;
+        }
+
+
+    } else if let Some(matches) = matches.subcommand_matches("focus") {
+        let today = dates::today();
+        if let Some(report_matches) = matches.subcommand_matches("report") {
+            let from = report_matches.value_of("since").map(|since| dates::parse_date(since, today)).transpose()?
+                .unwrap_or(today - chrono::Duration::days(6));
+            focus::focus_report(base_dir, scope, from, today)?;
+        } else {
+            let task_id = parse_id(matches.value_of("task_identifier")
+                .ok_or_else(|| TedoError::InvalidInput("Which task do you want to focus on?".into()))?, "task")?;
+            let number = |name: &str| matches.value_of(name).unwrap().parse::<u32>()
+                .map_err(|_| TedoError::InvalidInput(format!("--{} must be a number", name)));
+            let options = focus::FocusOptions {
+                minutes: number("minutes")?,
+                break_minutes: number("break")?,
+                sessions: number("sessions")?,
+            };
+            focus::focus(base_dir, scope, task_id, &options)?;
+        }
+
+
+    // Agenda
+
+    } else if matches.subcommand_matches("agenda").is_some() {
+        agenda::show_agenda(base_dir, scope, dates::today())?;
+    } else if let Some(matches) = matches.subcommand_matches("remind") {
+        if let Some(task_identifier) = matches.value_of("task_identifier") {
+            let task_id = parse_id(task_identifier, "task")?;
+            let at = match matches.value_of("when") {
+                Some("none") => None,
+                Some(when) => Some(dates::parse_datetime(when, chrono::Local::now().naive_local())?),
+                None => return Err(TedoError::InvalidInput("When should the reminder go off? Pass a time or none".into())),
+            };
+            reminders::set_reminder(base_dir, scope, task_id, at)?;
+        } else if matches.is_present("watch") {
+            let interval = matches.value_of("interval").unwrap().parse::<u64>()
+                .map_err(|_| TedoError::InvalidInput("--interval must be a number of seconds".into()))?;
+            reminders::watch_reminders(base_dir, std::time::Duration::from_secs(interval.max(1)), matches.is_present("notify"))?;
+        } else {
+            reminders::check_reminders(base_dir, chrono::Local::now().naive_local(), matches.is_present("notify"))?;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("history") {
+        let entity = if let Some(task_matches) = matches.subcommand_matches("task") {
+            history::Entity::Task(parse_id(task_matches.value_of("task_identifier").unwrap(), "task")?)
+        } else if let Some(note_matches) = matches.subcommand_matches("note") {
+            history::Entity::Note(notes::resolve_note_id(base_dir, scope, note_matches.value_of("note_identifier").unwrap())?)
+        } else {
+            history::Entity::Project(matches.subcommand_matches("project").and_then(|m| m.value_of("project_identifier")))
+        };
+        history::show_history(base_dir, scope, entity)?;
+    } else if let Some(matches) = matches.subcommand_matches("log") {
+        let today = dates::today();
+        let since = matches.value_of("since").map(|since| dates::parse_date(since, today)).transpose()?.unwrap_or(today);
+        history::show_log(base_dir, scope, since)?;
+    } else if let Some(matches) = matches.subcommand_matches("summary") {
+        let today = dates::today();
+        let since = matches.value_of("since").map(|since| dates::parse_date(since, today)).transpose()?.unwrap_or(today);
+        let output = matches.value_of("output").map(Path::new);
+        summary::summary(base_dir, scope, since, output, chrono::Local::now().naive_local())?;
+    } else if let Some(matches) = matches.subcommand_matches("stats") {
+        let weeks = matches.value_of("weeks").unwrap().parse::<usize>()
+            .map_err(|_| TedoError::InvalidInput("--weeks must be a number".into()))?;
+        stats::show_stats(base_dir, matches.value_of("project_identifier").or(scope), weeks, dates::today())?;
+    } else if let Some(matches) = matches.subcommand_matches("ical") {
+        if let Some(export_matches) = matches.subcommand_matches("export") {
+            let output = export_matches.value_of("output").map(Path::new);
+            ical::export_ics(base_dir, scope, output, export_matches.is_present("events"))?;
+        } else if let Some(import_matches) = matches.subcommand_matches("import") {
+            ical::import_ics(base_dir, scope, Path::new(import_matches.value_of("file").unwrap()))?;
+        }
+
+
+    // Template
+
+    } else if let Some(matches) = matches.subcommand_matches("template") {
+        if matches.subcommand_matches("list").is_some() {
+            templates::list_templates(base_dir)?;
+        }
+        if let Some(edit_matches) = matches.subcommand_matches("edit") {
+            templates::edit_template(base_dir, edit_matches.value_of("template_name").unwrap())?;
+        }
+
+
+    // Config
+
+    } else if let Some(matches) = matches.subcommand_matches("config") {
+        config::configure(base_dir, matches.value_of("key"), matches.value_of("value"))?;
+    } else if matches.subcommand_matches("sync").is_some() {
+        git::sync(base_dir)?;
+    } else if let Some(matches) = matches.subcommand_matches("merge") {
+        let resolution = match matches.value_of("resolve") {
+            Some("ours") => merge::Resolution::Ours,
+            Some("theirs") => merge::Resolution::Theirs,
+            _ => merge::Resolution::Ask,
+        };
+        merge::merge_file(
+            base_dir,
+            Path::new(matches.value_of("file").unwrap()),
+            matches.value_of("base").map(Path::new),
+            resolution,
+            &mut std::io::stdin().lock(),
+        )?;
+
+
+    // Close / reopen / move
+
+    } else if let Some(matches) = matches.subcommand_matches("close") {
+        if let Some(task_matches) = matches.subcommand_matches("task") {
+            let task_id = parse_id(task_matches.value_of("task_identifier").unwrap(), "task")?;
+            tasks::close_task(base_dir, scope, task_id, task_matches.is_present("force"))?;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("reopen") {
+        if let Some(task_matches) = matches.subcommand_matches("task") {
+            let task_id = parse_id(task_matches.value_of("task_identifier").unwrap(), "task")?;
+            tasks::reopen_task(base_dir, scope, task_id)?;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("move") {
+        if let Some(task_matches) = matches.subcommand_matches("task") {
+            let task_id = parse_id(task_matches.value_of("task_identifier").unwrap(), "task")?;
+            let parent = task_matches.value_of("parent").map(|id| parse_id(id, "task")).transpose()?;
+            tasks::move_task(base_dir, scope, task_id, parent)?;
+        }
 
 
+    // Dependencies
 
+    } else if let Some(matches) = matches.subcommand_matches("depend") {
+        let task_id = parse_id(matches.value_of("task_identifier").unwrap(), "task")?;
+        let prerequisite = matches.value_of("prerequisite").unwrap();
+        if matches.is_present("remove") {
+            dependencies::remove_dependency(base_dir, scope, task_id, prerequisite)?;
         } else {
-            println!("Invalid command. Use `tedo --help` to see the list of available commands.");
+            dependencies::add_dependency(base_dir, scope, task_id, prerequisite)?;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("next") {
+        dependencies::list_next_tasks(base_dir, scope, matches.is_present("all"))?;
+    } else if let Some(matches) = matches.subcommand_matches("graph") {
+        let format = matches.value_of("format").unwrap();
+        dependencies::print_graph(base_dir, scope, matches.is_present("all"), format)?;
+
+
+    // Links
+
+    } else if let Some(matches) = matches.subcommand_matches("attach") {
+        let task_id = parse_id(matches.value_of("task_identifier").unwrap(), "task")?;
+        let note_id = notes::resolve_note_id(base_dir, scope, matches.value_of("note_identifier").unwrap())?;
+        if matches.is_present("remove") {
+            links::detach_note(base_dir, scope, task_id, note_id)?;
+        } else {
+            links::attach_note(base_dir, scope, task_id, note_id)?;
+        }
+    } else if matches.subcommand_matches("links").is_some() {
+        links::list_broken_links(base_dir, scope)?;
+
+
+    // Show
+
+    } else if let Some(matches) = matches.subcommand_matches("show") {
+        if let Some(task_matches) = matches.subcommand_matches("task") {
+            let task_id = parse_id(task_matches.value_of("task_identifier").unwrap(), "task")?;
+            show::show_task(base_dir, scope, task_id)?;
+        }
+        if let Some(note_matches) = matches.subcommand_matches("note") {
+            let note_id = notes::resolve_note_id(base_dir, scope, note_matches.value_of("note_identifier").unwrap())?;
+            show::show_note(base_dir, scope, note_id, note_matches.is_present("raw"))?;
+        }
+        if let Some(project_matches) = matches.subcommand_matches("project") {
+            show::show_project(base_dir, project_matches.value_of("project_identifier").or(scope))?;
+        }
+
+
+    // Tag
+
+    } else if let Some(matches) = matches.subcommand_matches("tag") {
+        let remove = matches.is_present("remove");
+        if let Some(task_matches) = matches.subcommand_matches("task") {
+            let task_id = parse_id(task_matches.value_of("task_identifier").unwrap(), "task")?;
+            let tags: Vec<&str> = task_matches.values_of("tags").unwrap().collect();
+            tags::tag_task(base_dir, scope, task_id, &tags, remove || task_matches.is_present("remove"))?;
+        }
+        if let Some(note_matches) = matches.subcommand_matches("note") {
+            let note_id = notes::resolve_note_id(base_dir, scope, note_matches.value_of("note_identifier").unwrap())?;
+            let tags: Vec<&str> = note_matches.values_of("tags").unwrap().collect();
+            tags::tag_note(base_dir, scope, note_id, &tags, remove || note_matches.is_present("remove"))?;
         }
 
 
+    // Delete
 
+    } else if let Some(matches) = matches.subcommand_matches("delete") {
+        if let Some(task_matches) = matches.subcommand_matches("task") {
+            let task_id = parse_id(task_matches.value_of("task_identifier").unwrap(), "task")?;
+            tasks::delete_task(base_dir, scope, task_id)?;
+        }
+        if let Some(note_matches) = matches.subcommand_matches("note") {
+            let note_id = notes::resolve_note_id(base_dir, scope, note_matches.value_of("note_identifier").unwrap())?;
+            notes::delete_note(base_dir, scope, note_id)?;
+        }
+
+
+    // Project
+
+    } else if let Some(matches) = matches.subcommand_matches("project") {
+        let project_identifier = matches.value_of("project_identifier").unwrap();
+        let project = Project::find(base_dir, project_identifier)?;
+
+        if args.len() > 3 {
+            // Run the remaining arguments as their own command, scoped to this
+            // project. The saved current project is left untouched.
+            let mut scoped_args = vec![args[0].clone()];
+            scoped_args.extend(args[3..].to_vec());
+            let processed_args = preprocess_arguments(&scoped_args);
+            handle_arguments(base_dir, &process_matches(&processed_args), &scoped_args, Some(&project.name))?;
+        } else {
+            println!("({}) {}", project.id, project.name);
+        }
 
 
 
     } else {
-        println!("You can initialize Tedo using `tedo init`");
+        return Err(TedoError::InvalidInput("Invalid command. Use `tedo --help` to see the list of available commands.".into()));
     }
+
+    Ok(())
+}
+
+
+// Reads stdin when something is piped in, e.g. `git log | tedo cn "release log"`
+fn read_piped_stdin() -> Result<Option<String>, TedoError> {
+    use std::io::{IsTerminal, Read};
+
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok(None);
+    }
+    let mut content = String::new();
+    stdin.read_to_string(&mut content)?;
+    Ok(Some(content))
+}
+
+
+fn parse_id(identifier: &str, kind: &str) -> Result<u32, TedoError> {
+    identifier.parse::<u32>()
+        .map_err(|_| TedoError::InvalidInput(format!("Invalid {} identifier {}", kind, identifier)))
 }
 
 
 fn arguments_from_shortcut(args: &[String]) -> Vec<String> {
-    return args[1].chars().map(|c| c.to_string()).collect();
+    args[1].chars().map(|c| c.to_string()).collect()
+}
+
+
+fn process_matches(args: &[String]) -> clap::ArgMatches<'_> {
+    app().get_matches_from(args)
 }
 
 
-fn process_matches(args: &[String]) -> clap::ArgMatches {
-    let matches = clap::App::new("Tedo")
+// The names and aliases of the top-level subcommands. Only other arguments are
+// split up as shortcuts.
+fn known_subcommands() -> Vec<String> {
+    app().p.subcommands.iter()
+        .flat_map(|subcommand| {
+            let meta = &subcommand.p.meta;
+            std::iter::once(meta.name.clone()).chain(meta.aliases.iter().flatten().map(|(alias, _)| alias.to_string()))
+        })
+        .collect()
+}
+
+
+fn app() -> clap::App<'static, 'static> {
+    clap::App::new("Tedo")
         .version("1.0")
         .about("Productivity Manager CLI")
+        .arg(
+            clap::Arg::with_name("project")
+                .short("p")
+                .long("project")
+                .takes_value(true)
+                .global(true)
+                .help("Run the command in this project (id, name or shorthand) instead of the current one"),
+        )
         .subcommand(
             clap::SubCommand::with_name("init")
                 .about("Initialize Tedo in the current directory"),
@@ -223,6 +631,12 @@ fn process_matches(args: &[String]) -> clap::ArgMatches {
             clap::SubCommand::with_name("list")
                 .aliases(&["ls", "l"])
                 .about("List objects like projects, tasks, etc.")
+                .arg(
+                    clap::Arg::with_name("archived")
+                        .short("a")
+                        .long("archived")
+                        .help("Include archived projects"),
+                )
                 .subcommand(
                     clap::SubCommand::with_name("notes")
                         .aliases(&["n", "nt", "note"])
@@ -231,12 +645,24 @@ fn process_matches(args: &[String]) -> clap::ArgMatches {
                 .subcommand(
                     clap::SubCommand::with_name("projects")
                         .aliases(&PROJECT_SHORTHANDS)
-                        .about("List all projects"),
+                        .about("List all projects")
+                        .arg(
+                            clap::Arg::with_name("archived")
+                                .short("a")
+                                .long("archived")
+                                .help("Include archived projects"),
+                        ),
                 )
                 .subcommand(
                     clap::SubCommand::with_name("tasks")
                         .aliases(&["t", "ts", "task"])
                         .about("List all tasks")
+                        .arg(
+                            clap::Arg::with_name("hide_blocked")
+                                .short("b")
+                                .long("hide-blocked")
+                                .help("Leave out tasks that wait on an open prerequisite"),
+                        )
                         .subcommand(
                             clap::SubCommand::with_name("all")
                                 .aliases(&["a", "al"])
@@ -244,6 +670,11 @@ fn process_matches(args: &[String]) -> clap::ArgMatches {
                         ),
 
                 )
+                .subcommand(
+                    clap::SubCommand::with_name("recurring")
+                        .aliases(&["r", "rec"])
+                        .about("List recurring task templates and their next due date"),
+                )
 
         )
 
@@ -263,123 +694,949 @@ fn process_matches(args: &[String]) -> clap::ArgMatches {
                                 .required(true),
                         ),
                 )
-        )
-
-        // Table
-
-        .subcommand(
-            clap::SubCommand::with_name("table")
-                .aliases(&["t", "tb", "tbl", "ta", "tab"])
-                .about("List objects like projects, tasks, etc.")
-                .subcommand(
-                    clap::SubCommand::with_name("projects")
-                        .aliases(&PROJECT_SHORTHANDS)
-                        .about("List all projects"),
-                )
                 .subcommand(
-                    clap::SubCommand::with_name("tasks")
-                        .aliases(&["t", "ts", "task"])
-                        .about("List all tasks")
-                        .subcommand(
-                            clap::SubCommand::with_name("project")
-                                .aliases(&PROJECT_SHORTHANDS)
-                                .help("Filter by project")
-
-                                .arg(
-                                    clap::Arg::with_name("project_identifier")
-                                        .help("Filter by project"),
-                                ),
+                    clap::SubCommand::with_name("task")
+                        .aliases(&["t", "ts"])
+                        .about("Edit a task")
+                        .arg(
+                            clap::Arg::with_name("task_identifier")
+                                .help("ID of the task")
+                                .required(true),
                         ),
                 )
                 .subcommand(
-                    clap::SubCommand::with_name("notes")
-                        .aliases(&["n", "nt", "note"])
-                        .about("List all notes")
+                    clap::SubCommand::with_name("project")
+                        .aliases(&["p", "pr"])
+                        .about("Edit a project")
                         .arg(
-                            clap::Arg::with_name("project")
-                                .short("p")
-                                .takes_value(true)
-                                .long("project")
-                                .help("Filter by project"),
+                            clap::Arg::with_name("project_identifier")
+                                .help("Identifier (id, name or shorthand) of the project, defaults to the current one"),
                         ),
-                ),
-        )
-
-        // Switch
-
-        .subcommand(
-            clap::SubCommand::with_name("switch")
-                .aliases(&["s", "sw"])
-                .about("Switch context to a different project")
-                .arg(
-                    clap::Arg::with_name("project_name")
-                        .help("Name of the project")
-                        .required(true),
-                ),
+                )
         )
 
-        // Create
+        // Note
 
         .subcommand(
-            clap::SubCommand::with_name("create")
-                .aliases(&["c", "cr"])
-                .about("Create new objects like projects, tasks, etc.")
+            clap::SubCommand::with_name("note")
+                .aliases(&["nt"])
+                .about("Work with the content of notes")
                 .subcommand(
-                    clap::SubCommand::with_name("note")
-                        .aliases(&["n", "nt"])
-                        .about("Create a new note")
+                    clap::SubCommand::with_name("append")
+                        .aliases(&["a", "ap"])
+                        .about("Append text to a note, from the arguments or stdin")
                         .arg(
-                            clap::Arg::with_name("note_description")
-                                .help("Description of the note")
-                                .required(true)
+                            clap::Arg::with_name("note_identifier")
+                                .help("ID or slug of the note")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("text")
+                                .help("Text to append")
                                 .multiple(true),
                         ),
                 )
                 .subcommand(
-                    clap::SubCommand::with_name("task")
-                        .about("Create a new task")
-                        .aliases(&["t", "ts"])
+                    clap::SubCommand::with_name("history")
+                        .aliases(&["h", "hist"])
+                        .about("List the revisions of a note")
                         .arg(
-                            clap::Arg::with_name("task_description")
-                                .help("Description of the task")
-                                .required(true)
+                            clap::Arg::with_name("note_identifier")
+                                .help("ID or slug of the note")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("diff")
+                        .aliases(&["d"])
+                        .about("Show the changes between revisions of a note, the last change by default")
+                        .arg(
+                            clap::Arg::with_name("note_identifier")
+                                .help("ID or slug of the note")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("revisions")
+                                .help("Revision to compare from and optionally to, the current content otherwise")
+                                .max_values(2)
                                 .multiple(true),
                         ),
                 )
-
                 .subcommand(
-                    clap::SubCommand::with_name("project")
-                        .aliases(&["p", "pr"])
-                        .about("Create a new project")
+                    clap::SubCommand::with_name("revert")
+                        .aliases(&["r"])
+                        .about("Restore the content of a note from an earlier revision")
                         .arg(
-                            clap::Arg::with_name("project_name")
-                                .help("Name of the project")
+                            clap::Arg::with_name("note_identifier")
+                                .help("ID or slug of the note")
                                 .required(true),
                         )
                         .arg(
-                            clap::Arg::with_name("switch")
-                                .short("s")
-                                .long("switch")
-                                .help("Switch to the new project"),
+                            clap::Arg::with_name("revision")
+                                .help("Number of the revision, see `tedo note history`")
+                                .required(true),
                         ),
                 )
         )
 
-        // Project
+        // Journal
 
         .subcommand(
-            clap::SubCommand::with_name("project")
-                .aliases(&["p", "pr"])
-                .about("Manage projects")
+            clap::SubCommand::with_name("journal")
+                .aliases(&["j", "jn"])
+                .about("Add a timestamped entry to today's journal note")
                 .arg(
-                    clap::Arg::with_name("project_identifier")
-                        .help("Identifier (id, name or shorthand) of the project")
-                        .required(true)
-
+                    clap::Arg::with_name("text")
+                        .help("Text of the entry, read from stdin when omitted")
+                        .multiple(true),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("show")
+                        .aliases(&["s", "sh"])
+                        .about("Show the journal for a range of days, today by default")
+                        .arg(
+                            clap::Arg::with_name("week")
+                                .short("w")
+                                .long("week")
+                                .conflicts_with("since")
+                                .help("Show this week, starting on Monday"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("since")
+                                .long("since")
+                                .takes_value(true)
+                                .help("First day to show: YYYY-MM-DD, yesterday, monday, ..."),
+                        )
+                        .arg(
+                            clap::Arg::with_name("until")
+                                .long("until")
+                                .takes_value(true)
+                                .help("Last day to show, today by default"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("raw")
+                                .long("raw")
+                                .help("Print plain Markdown"),
+                        ),
                 )
         )
 
-        .get_matches_from(args);
+        // Time tracking
 
-    return matches;
-}
\ No newline at end of file
+        .subcommand(
+            clap::SubCommand::with_name("start")
+                .about("Start a timer on a task, only one timer runs at a time")
+                .arg(
+                    clap::Arg::with_name("task_identifier")
+                        .help("ID of the task")
+                        .required(true),
+                )
+        )
+        .subcommand(
+            clap::SubCommand::with_name("stop")
+                .about("Stop the running timer")
+        )
+        .subcommand(
+            clap::SubCommand::with_name("time")
+                .about("Log time by hand and report on tracked time")
+                .subcommand(
+                    clap::SubCommand::with_name("log")
+                        .about("Log time spent on a task")
+                        .arg(
+                            clap::Arg::with_name("task_identifier")
+                                .help("ID of the task")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("duration")
+                                .help("Time spent, e.g. 1h30m, 45m or 1.5h")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("date")
+                                .long("date")
+                                .takes_value(true)
+                                .help("Day the time was spent: YYYY-MM-DD, yesterday, monday, ... Today by default"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("report")
+                        .about("Report tracked time across projects, or in one with --project")
+                        .arg(
+                            clap::Arg::with_name("by")
+                                .long("by")
+                                .takes_value(true)
+                                .possible_values(&["project", "tag", "day"])
+                                .default_value("project")
+                                .help("How to group the time"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("since")
+                                .long("since")
+                                .takes_value(true)
+                                .help("First day to include: YYYY-MM-DD, yesterday, monday, ..."),
+                        )
+                        .arg(
+                            clap::Arg::with_name("csv")
+                                .long("csv")
+                                .help("Print CSV instead of a table"),
+                        ),
+                )
+        )
+
+        .subcommand(
+            clap::SubCommand::with_name("focus")
+                .about("Run focus sessions with breaks on a task")
+                .arg(
+                    clap::Arg::with_name("task_identifier")
+                        .help("ID of the task"),
+                )
+                .arg(
+                    clap::Arg::with_name("minutes")
+                        .long("minutes")
+                        .short("m")
+                        .takes_value(true)
+                        .default_value("25")
+                        .help("Length of a focus session"),
+                )
+                .arg(
+                    clap::Arg::with_name("break")
+                        .long("break")
+                        .takes_value(true)
+                        .default_value("5")
+                        .help("Length of the break between sessions"),
+                )
+                .arg(
+                    clap::Arg::with_name("sessions")
+                        .long("sessions")
+                        .short("n")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Number of focus sessions to run"),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("report")
+                        .about("Show focus sessions per day, for the last 7 days by default")
+                        .arg(
+                            clap::Arg::with_name("since")
+                                .long("since")
+                                .takes_value(true)
+                                .help("First day to include: YYYY-MM-DD, yesterday, monday, ..."),
+                        ),
+                )
+        )
+
+        // Agenda
+
+        .subcommand(
+            clap::SubCommand::with_name("agenda")
+                .aliases(&["ag"])
+                .about("Show overdue tasks, tasks due today and this week, and scheduled tasks across projects"),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("remind")
+                .about("Set a reminder on a task, or check for reminders that are due")
+                .arg(
+                    clap::Arg::with_name("task_identifier")
+                        .help("ID of the task to set a reminder on"),
+                )
+                .arg(
+                    clap::Arg::with_name("when")
+                        .help("When to remind: HH:MM, tomorrow 9:00, YYYY-MM-DD HH:MM, ... or none to remove it"),
+                )
+                .arg(
+                    clap::Arg::with_name("notify")
+                        .long("notify")
+                        .help("Also send reminders as desktop notifications"),
+                )
+                .arg(
+                    clap::Arg::with_name("watch")
+                        .long("watch")
+                        .help("Keep running and check for reminders periodically"),
+                )
+                .arg(
+                    clap::Arg::with_name("interval")
+                        .long("interval")
+                        .takes_value(true)
+                        .default_value("60")
+                        .help("Seconds between checks with --watch"),
+                ),
+        )
+
+        // History
+
+        .subcommand(
+            clap::SubCommand::with_name("history")
+                .about("Show when a task, note or project was created and what changed since")
+                .subcommand(
+                    clap::SubCommand::with_name("task")
+                        .aliases(&["t", "ts"])
+                        .about("History of a task")
+                        .arg(
+                            clap::Arg::with_name("task_identifier")
+                                .help("ID of the task")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("note")
+                        .aliases(&["n", "nt"])
+                        .about("History of a note")
+                        .arg(
+                            clap::Arg::with_name("note_identifier")
+                                .help("ID or slug of the note")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("project")
+                        .aliases(&PROJECT_SHORTHANDS)
+                        .about("History of a project and its tasks and notes, the current project by default")
+                        .arg(
+                            clap::Arg::with_name("project_identifier")
+                                .help("ID or name of the project"),
+                        ),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("log")
+                .about("Show a timeline of changes across projects, today's by default")
+                .arg(
+                    clap::Arg::with_name("since")
+                        .long("since")
+                        .takes_value(true)
+                        .help("First day to include: YYYY-MM-DD, yesterday, monday, ..."),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("summary")
+                .about("Write a Markdown report of completed, created and ongoing tasks, notes and tracked time per project")
+                .arg(
+                    clap::Arg::with_name("since")
+                        .long("since")
+                        .takes_value(true)
+                        .help("First day to include, today by default: YYYY-MM-DD, yesterday, monday, ..."),
+                )
+                .arg(
+                    clap::Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("File to write the report to instead of printing it"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("stats")
+                .aliases(&["st"])
+                .about("Show open and done tasks, throughput, cycle time and a burndown per project")
+                .arg(
+                    clap::Arg::with_name("project_identifier")
+                        .help("ID, name or shorthand of a project to show in detail"),
+                )
+                .arg(
+                    clap::Arg::with_name("weeks")
+                        .short("w")
+                        .long("weeks")
+                        .takes_value(true)
+                        .default_value("8")
+                        .help("Number of weeks to chart, up to 520"),
+                ),
+        )
+
+        // Calendar
+
+        .subcommand(
+            clap::SubCommand::with_name("ical")
+                .aliases(&["ics"])
+                .about("Export tasks with dates to an iCalendar file, or import todos from one")
+                .subcommand(
+                    clap::SubCommand::with_name("export")
+                        .about("Export tasks with a due date or scheduled day, from all projects unless --project is given")
+                        .arg(
+                            clap::Arg::with_name("output")
+                                .short("o")
+                                .long("output")
+                                .takes_value(true)
+                                .help("File to write, prints the calendar when left out"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("events")
+                                .long("events")
+                                .help("Export all-day events instead of todos, for calendars that don't show todos"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("import")
+                        .about("Import the todos of an .ics file into the current project, updating tasks with the same UID")
+                        .arg(
+                            clap::Arg::with_name("file")
+                                .help("The .ics file")
+                                .required(true),
+                        ),
+                ),
+        )
+
+        // Template
+
+        .subcommand(
+            clap::SubCommand::with_name("template")
+                .aliases(&["tpl"])
+                .about("Manage note templates")
+                .subcommand(
+                    clap::SubCommand::with_name("list")
+                        .aliases(&["l", "ls"])
+                        .about("List the available templates"),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("edit")
+                        .aliases(&["e"])
+                        .about("Edit a template, creating it if needed")
+                        .arg(
+                            clap::Arg::with_name("template_name")
+                                .help("Name of the template, e.g. meeting")
+                                .required(true),
+                        ),
+                )
+        )
+
+        // Config
+
+        .subcommand(
+            clap::SubCommand::with_name("config")
+                .about("Show or change settings such as the editor")
+                .arg(
+                    clap::Arg::with_name("key")
+                        .help("Setting to show or change"),
+                )
+                .arg(
+                    clap::Arg::with_name("value")
+                        .help("New value, an empty value resets the setting"),
+                )
+        )
+        .subcommand(
+            clap::SubCommand::with_name("sync")
+                .about("Commit local changes, merge the changes of the git remote and push"),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("merge")
+                .about("Merge another copy of the state file into this one")
+                .arg(
+                    clap::Arg::with_name("file")
+                        .help("The other tedo_state.toml")
+                        .required(true),
+                )
+                .arg(
+                    clap::Arg::with_name("base")
+                        .long("base")
+                        .takes_value(true)
+                        .help("The state both copies started from, so that deletions and one-sided changes are recognized"),
+                )
+                .arg(
+                    clap::Arg::with_name("resolve")
+                        .long("resolve")
+                        .takes_value(true)
+                        .possible_values(&["ask", "ours", "theirs"])
+                        .default_value("ask")
+                        .help("How to resolve conflicts"),
+                )
+        )
+
+        // Table
+
+        .subcommand(
+            clap::SubCommand::with_name("table")
+                .aliases(&["t", "tb", "tbl", "ta", "tab"])
+                .about("List objects like projects, tasks, etc.")
+                .subcommand(
+                    clap::SubCommand::with_name("projects")
+                        .aliases(&PROJECT_SHORTHANDS)
+                        .about("List all projects")
+                        .arg(
+                            clap::Arg::with_name("archived")
+                                .short("a")
+                                .long("archived")
+                                .help("Include archived projects"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("tasks")
+                        .aliases(&["t", "ts", "task"])
+                        .about("List all tasks")
+                        .arg(
+                            clap::Arg::with_name("hide_blocked")
+                                .short("b")
+                                .long("hide-blocked")
+                                .help("Leave out tasks that wait on an open prerequisite"),
+                        )
+                        .subcommand(
+                            clap::SubCommand::with_name("all")
+                                .aliases(&["a", "al"])
+                                .about("List the tasks of all projects"),
+                        )
+                        .subcommand(
+                            clap::SubCommand::with_name("project")
+                                .aliases(&PROJECT_SHORTHANDS)
+                                .help("Filter by project")
+
+                                .arg(
+                                    clap::Arg::with_name("project_identifier")
+                                        .help("Filter by project"),
+                                ),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("notes")
+                        .aliases(&["n", "nt", "note"])
+                        .about("List all notes"),
+                ),
+        )
+
+        // Switch
+
+        // Archive
+
+        .subcommand(
+            clap::SubCommand::with_name("archive")
+                .about("Archive a project, hiding it from listings")
+                .arg(
+                    clap::Arg::with_name("project_identifier")
+                        .help("ID, name or shorthand of the project, the current one by default"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("unarchive")
+                .about("Make an archived project active again")
+                .arg(
+                    clap::Arg::with_name("project_identifier")
+                        .help("ID, name or shorthand of the project, the current one by default"),
+                ),
+        )
+
+        .subcommand(
+            clap::SubCommand::with_name("switch")
+                .aliases(&["s", "sw"])
+                .about("Switch context to a different project")
+                .arg(
+                    clap::Arg::with_name("project_name")
+                        .help("Name of the project")
+                        .required(true),
+                ),
+        )
+
+        // Create
+
+        .subcommand(
+            clap::SubCommand::with_name("create")
+                .aliases(&["c", "cr"])
+                .about("Create new objects like projects, tasks, etc.")
+                .subcommand(
+                    clap::SubCommand::with_name("note")
+                        .aliases(&["n", "nt"])
+                        .about("Create a new note")
+                        .arg(
+                            clap::Arg::with_name("note_description")
+                                .help("Description of the note")
+                                .required(true)
+                                .multiple(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("tag")
+                                .long("tag")
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1)
+                                .help("Tag the new note, can be repeated"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("content")
+                                .long("content")
+                                .takes_value(true)
+                                .help("Body of the note"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("file")
+                                .long("file")
+                                .takes_value(true)
+                                .conflicts_with("content")
+                                .help("Read the body of the note from a file"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("template")
+                                .long("template")
+                                .takes_value(true)
+                                .conflicts_with_all(&["content", "file"])
+                                .help("Start from a template in ~/.tedo/templates and open the editor"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("edit")
+                                .short("e")
+                                .long("edit")
+                                .help("Open the new note in the editor"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("task")
+                        .about("Create a new task")
+                        .aliases(&["t", "ts"])
+                        .arg(
+                            clap::Arg::with_name("task_description")
+                                .help("Description of the task")
+                                .required(true)
+                                .multiple(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("parent")
+                                .long("parent")
+                                .takes_value(true)
+                                .help("ID of the parent task"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("tag")
+                                .long("tag")
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1)
+                                .help("Tag the new task, can be repeated"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("due")
+                                .long("due")
+                                .takes_value(true)
+                                .help("Due date: YYYY-MM-DD, today, tomorrow or a weekday. Where a recurring task starts."),
+                        )
+                        .arg(
+                            clap::Arg::with_name("scheduled")
+                                .long("scheduled")
+                                .takes_value(true)
+                                .conflicts_with("every")
+                                .help("Day to work on the task"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("remind")
+                                .long("remind")
+                                .takes_value(true)
+                                .conflicts_with("every")
+                                .help("When to remind: HH:MM, tomorrow 9:00, YYYY-MM-DD HH:MM, ..."),
+                        )
+                        .arg(
+                            clap::Arg::with_name("every")
+                                .long("every")
+                                .takes_value(true)
+                                .conflicts_with("parent")
+                                .help("Make the task recurring: daily, weekdays, \"2 weeks\", \"month on the 1st\" or an RRULE"),
+                        ),
+                )
+
+                .subcommand(
+                    clap::SubCommand::with_name("project")
+                        .aliases(&["p", "pr"])
+                        .about("Create a new project")
+                        .arg(
+                            clap::Arg::with_name("project_name")
+                                .help("Name of the project, nested names like work/platform/auth create sub-projects")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("parent")
+                                .long("parent")
+                                .takes_value(true)
+                                .help("Create the project below this one"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("switch")
+                                .short("s")
+                                .long("switch")
+                                .help("Switch to the new project"),
+                        ),
+                )
+        )
+
+        // Close / reopen / move
+
+        .subcommand(
+            clap::SubCommand::with_name("close")
+                .aliases(&["x", "done"])
+                .about("Mark tasks as done")
+                .subcommand(
+                    clap::SubCommand::with_name("task")
+                        .aliases(&["t", "ts"])
+                        .about("Close a task")
+                        .arg(
+                            clap::Arg::with_name("task_identifier")
+                                .help("ID of the task")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("force")
+                                .short("f")
+                                .long("force")
+                                .help("Also close any open subtasks"),
+                        ),
+                )
+        )
+        .subcommand(
+            clap::SubCommand::with_name("reopen")
+                .aliases(&["o", "open"])
+                .about("Reopen tasks that were marked as done")
+                .subcommand(
+                    clap::SubCommand::with_name("task")
+                        .aliases(&["t", "ts"])
+                        .about("Reopen a task")
+                        .arg(
+                            clap::Arg::with_name("task_identifier")
+                                .help("ID of the task")
+                                .required(true),
+                        ),
+                )
+        )
+        .subcommand(
+            clap::SubCommand::with_name("move")
+                .aliases(&["m", "mv"])
+                .about("Move tasks within the task hierarchy")
+                .subcommand(
+                    clap::SubCommand::with_name("task")
+                        .aliases(&["t", "ts"])
+                        .about("Move a task under another task, or to the top level")
+                        .arg(
+                            clap::Arg::with_name("task_identifier")
+                                .help("ID of the task")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("parent")
+                                .long("parent")
+                                .takes_value(true)
+                                .help("ID of the new parent task; omit to move to the top level"),
+                        ),
+                )
+        )
+
+        // Dependencies
+
+        .subcommand(
+            clap::SubCommand::with_name("depend")
+                .aliases(&["dep"])
+                .about("Make a task depend on another task")
+                .arg(
+                    clap::Arg::with_name("task_identifier")
+                        .help("ID of the dependent task")
+                        .required(true),
+                )
+                .arg(
+                    clap::Arg::with_name("prerequisite")
+                        .help("The task it depends on: an ID, or <project>:<id> for a task in another project")
+                        .required(true),
+                )
+                .arg(
+                    clap::Arg::with_name("remove")
+                        .short("r")
+                        .long("remove")
+                        .help("Remove the dependency instead"),
+                )
+        )
+        .subcommand(
+            clap::SubCommand::with_name("next")
+                .aliases(&["nx"])
+                .about("List the open tasks that are not blocked")
+                .arg(
+                    clap::Arg::with_name("all")
+                        .short("a")
+                        .long("all")
+                        .help("Include all projects"),
+                )
+        )
+        .subcommand(
+            clap::SubCommand::with_name("graph")
+                .about("Print the task dependency graph")
+                .arg(
+                    clap::Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["dot", "mermaid"])
+                        .default_value("dot")
+                        .help("Output format"),
+                )
+                .arg(
+                    clap::Arg::with_name("all")
+                        .short("a")
+                        .long("all")
+                        .help("Include all projects"),
+                )
+        )
+
+        // Links
+
+        .subcommand(
+            clap::SubCommand::with_name("attach")
+                .aliases(&["at"])
+                .about("Attach a note to a task")
+                .arg(
+                    clap::Arg::with_name("task_identifier")
+                        .help("ID of the task")
+                        .required(true),
+                )
+                .arg(
+                    clap::Arg::with_name("note_identifier")
+                        .help("ID or slug of the note")
+                        .required(true),
+                )
+                .arg(
+                    clap::Arg::with_name("remove")
+                        .short("r")
+                        .long("remove")
+                        .help("Detach the note instead"),
+                )
+        )
+        .subcommand(
+            clap::SubCommand::with_name("links")
+                .about("List links between tasks and notes whose target was deleted")
+        )
+
+        // Show
+
+        .subcommand(
+            clap::SubCommand::with_name("show")
+                .aliases(&["sh"])
+                .about("Show the details of a single object")
+                .subcommand(
+                    clap::SubCommand::with_name("task")
+                        .aliases(&["t", "ts"])
+                        .about("Show a task with its status, links and notes")
+                        .arg(
+                            clap::Arg::with_name("task_identifier")
+                                .help("ID of the task")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("note")
+                        .aliases(&["n", "nt"])
+                        .about("Show a note, its references and backlinks")
+                        .arg(
+                            clap::Arg::with_name("note_identifier")
+                                .help("ID or slug of the note")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("raw")
+                                .long("raw")
+                                .help("Print only the plain note body"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("project")
+                        .aliases(&["p", "pr"])
+                        .about("Show a project with its tasks and notes")
+                        .arg(
+                            clap::Arg::with_name("project_identifier")
+                                .help("Identifier (id, name or shorthand) of the project, defaults to the current one"),
+                        ),
+                )
+        )
+
+        // Tag
+
+        .subcommand(
+            clap::SubCommand::with_name("tag")
+                .aliases(&["tg"])
+                .about("Add or remove tags")
+                .arg(
+                    clap::Arg::with_name("remove")
+                        .short("r")
+                        .long("remove")
+                        .global(true)
+                        .help("Remove the tags instead"),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("task")
+                        .aliases(&["t", "ts"])
+                        .about("Tag a task")
+                        .arg(
+                            clap::Arg::with_name("task_identifier")
+                                .help("ID of the task")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("tags")
+                                .help("Tags to add")
+                                .required(true)
+                                .multiple(true),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("note")
+                        .aliases(&["n", "nt"])
+                        .about("Tag a note")
+                        .arg(
+                            clap::Arg::with_name("note_identifier")
+                                .help("ID or slug of the note")
+                                .required(true),
+                        )
+                        .arg(
+                            clap::Arg::with_name("tags")
+                                .help("Tags to add")
+                                .required(true)
+                                .multiple(true),
+                        ),
+                )
+        )
+
+        // Delete
+
+        .subcommand(
+            clap::SubCommand::with_name("delete")
+                .aliases(&["d", "del", "rm"])
+                .about("Delete objects like tasks and notes")
+                .subcommand(
+                    clap::SubCommand::with_name("task")
+                        .aliases(&["t", "ts"])
+                        .about("Delete a task")
+                        .arg(
+                            clap::Arg::with_name("task_identifier")
+                                .help("ID of the task")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("note")
+                        .aliases(&["n", "nt"])
+                        .about("Delete a note")
+                        .arg(
+                            clap::Arg::with_name("note_identifier")
+                                .help("ID or slug of the note")
+                                .required(true),
+                        ),
+                )
+        )
+
+        // Project
+
+        .subcommand(
+            clap::SubCommand::with_name("project")
+                .aliases(&["p", "pr"])
+                .about("Manage projects")
+                .arg(
+                    clap::Arg::with_name("project_identifier")
+                        .help("Identifier (id, name or shorthand) of the project")
+                        .required(true)
+
+                )
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subcommands_are_not_split_into_shortcuts() {
+        for name in known_subcommands() {
+            let args = preprocess_arguments(&["tedo".into(), name.clone(), "--help".into()]);
+            assert_eq!(args.len(), 3, "tedo {}", name);
+            // Recognized by clap, which shows the help of the subcommand
+            let error = app().get_matches_from_safe(&args).err().unwrap();
+            assert_eq!(error.kind, clap::ErrorKind::HelpDisplayed, "tedo {}", name);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use colored::Colorize;
+
+
+// Renders Markdown for the terminal. Supports the parts we actually use in
+// notes: headings, lists, block quotes, fenced code blocks, rules and the
+// inline styles bold, italic, code and links.
+pub fn render(content: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(format!("    {}", line.yellow()));
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            let heading = render_inline(trimmed[level..].trim());
+            lines.push(match level {
+                1 => heading.bold().underline().blue().to_string(),
+                2 => heading.bold().blue().to_string(),
+                _ => heading.bold().to_string(),
+            });
+            continue;
+        }
+
+        if trimmed == "---" || trimmed == "***" {
+            lines.push("─".repeat(40).dimmed().to_string());
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        if let Some(item) = trimmed.strip_prefix("- ").or(trimmed.strip_prefix("* ")).or(trimmed.strip_prefix("+ ")) {
+            lines.push(format!("{}{} {}", indent, "•".cyan(), render_inline(item)));
+            continue;
+        }
+
+        let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 && trimmed[digits..].starts_with(". ") {
+            lines.push(format!("{}{} {}", indent, trimmed[..digits + 1].cyan(), render_inline(&trimmed[digits + 2..])));
+            continue;
+        }
+
+        if let Some(quote) = trimmed.strip_prefix('>') {
+            lines.push(format!("{} {}", "│".dimmed(), render_inline(quote.trim_start()).italic()));
+            continue;
+        }
+
+        lines.push(render_inline(line));
+    }
+
+    lines.join("\n")
+}
+
+
+fn render_inline(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if let Some(inner) = rest.strip_prefix('`') {
+            if let Some(end) = inner.find('`') {
+                output.push_str(&inner[..end].yellow().to_string());
+                rest = &inner[end + 1..];
+                continue;
+            }
+        }
+        if let Some(inner) = rest.strip_prefix("**") {
+            if let Some(end) = inner.find("**") {
+                output.push_str(&inner[..end].bold().to_string());
+                rest = &inner[end + 2..];
+                continue;
+            }
+        }
+        if let Some(inner) = rest.strip_prefix('*').or(rest.strip_prefix('_')) {
+            let marker = &rest[..1];
+            if let Some(end) = inner.find(marker) {
+                if end > 0 {
+                    output.push_str(&inner[..end].italic().to_string());
+                    rest = &inner[end + 1..];
+                    continue;
+                }
+            }
+        }
+        if let Some(inner) = rest.strip_prefix('[') {
+            if let Some(close) = inner.find("](") {
+                if let Some(end) = inner[close + 2..].find(')') {
+                    let label = &inner[..close];
+                    let url = &inner[close + 2..close + 2 + end];
+                    output.push_str(&format!("{} {}", label.blue().underline(), format!("({})", url).dimmed()));
+                    rest = &inner[close + 3 + end..];
+                    continue;
+                }
+            }
+        }
+
+        let next = rest.chars().next().unwrap();
+        output.push(next);
+        rest = &rest[next.len_utf8()..];
+    }
+
+    output
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_without_colors() {
+        colored::control::set_override(false);
+
+        let content = "# Title\n\nSome **bold** and *italic* with `code`.\n- item [docs](https://example.com)\n  * nested\n2. second\n> quoted\n```\nlet x = 1;\n```\n---";
+        let expected = "Title\n\nSome bold and italic with code.\n• item docs (https://example.com)\n  • nested\n2. second\n│ quoted\n    let x = 1;\n────────────────────────────────────────";
+        assert_eq!(render(content), expected);
+    }
+
+    #[test]
+    fn test_render_keeps_unmatched_markers() {
+        colored::control::set_override(false);
+
+        assert_eq!(render("2 * 3 = 6, a_b and [x]"), "2 * 3 = 6, a_b and [x]");
+        assert_eq!(render("#hashtag and #task:3"), "#hashtag and #task:3");
+    }
+}
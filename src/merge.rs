@@ -0,0 +1,1208 @@
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use similar::{Algorithm, DiffTag};
+use toml::{Table, Value};
+
+use crate::error::TedoError;
+use crate::{config, storage};
+use crate::storage::{save_state, Note, Project, Task, TaskRef, TedoState};
+
+
+// Three-way merge of two copies of the state that diverged from a common base.
+// Projects, tasks and notes are matched by id and merged field by field: a
+// field changed on one side only takes that change, a field changed the same
+// way on both sides is kept, and a field changed differently on both sides is
+// a conflict that the caller resolves. Tags, dependencies, attached notes,
+// time entries, focus sessions and change history are merged as sets, and
+// note content line by line. Entities both sides created with the same id
+// are told apart by their name, and the other side's entity gets a new id.
+// Parent and dependency cycles and a second running timer, which neither side
+// had on its own, are conflicts too.
+
+
+// Fields merged as sets instead of as a whole
+static SET_FIELDS: [&str; 7] = ["tags", "depends_on", "notes", "previous_slugs", "time_entries", "focus_sessions", "history"];
+
+// Text fields where changes to different lines combine
+static TEXT_FIELDS: [&str; 1] = ["content"];
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    // e.g. "task 3 'Write docs' in growth"
+    pub entity: String,
+    pub field: String,
+    pub ours: String,
+    pub theirs: String,
+}
+
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {} here, {} there", self.entity, self.field, self.ours, self.theirs)
+    }
+}
+
+
+// The merged state with the conflicts that came up and how each was resolved
+pub struct Merge {
+    pub state: TedoState,
+    pub conflicts: Vec<(Conflict, Side)>,
+}
+
+
+struct Merger<'a> {
+    resolve: &'a mut dyn FnMut(&Conflict) -> Side,
+    conflicts: Vec<(Conflict, Side)>,
+}
+
+
+impl Merger<'_> {
+    fn resolve(&mut self, conflict: Conflict) -> Side {
+        let side = (self.resolve)(&conflict);
+        self.conflicts.push((conflict, side));
+        side
+    }
+}
+
+
+pub fn merge_states(base: &TedoState, ours: &TedoState, theirs: &TedoState, resolve: &mut dyn FnMut(&Conflict) -> Side) -> Merge {
+    let mut theirs = theirs.clone();
+    renumber_new_entities(base, ours, &mut theirs);
+
+    let mut merger = Merger { resolve, conflicts: Vec::new() };
+    let projects = merge_lists(&mut merger, &base.projects, &ours.projects, &theirs.projects, |p| p.id, project_label, merge_project);
+
+    // The current project is a local choice, follow it through renames
+    let current_project = ours.current_project.as_ref()
+        .and_then(|name| ours.projects.iter().find(|p| &p.name == name))
+        .and_then(|current| projects.iter().find(|p| p.id == current.id))
+        .map(|p| p.name.clone());
+
+    let mut state = TedoState { current_project, projects };
+    break_parent_cycles(&mut merger, ours, &theirs, &mut state);
+    break_dependency_cycles(&mut merger, ours, &theirs, &mut state);
+    stop_extra_timers(&mut merger, ours, &theirs, &mut state);
+    Merge { state, conflicts: merger.conflicts }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    Ask,
+}
+
+
+// Notes kept as files are read from the notes directory next to the file
+fn read_state_file(path: &Path, revision_limit: usize) -> Result<TedoState, TedoError> {
+    let data = fs::read_to_string(path)
+        .map_err(|error| TedoError::NotFound(format!("Could not read {}: {}", path.display(), error)))?;
+    let mut tedo_state = toml::from_str(&data).map_err(|error| TedoError::StorageCorrupt(format!("{}: {}", path.display(), error)))?;
+    storage::read_notes(path.parent().unwrap_or(Path::new(".")), &mut tedo_state, false, revision_limit)?;
+    Ok(tedo_state)
+}
+
+
+// Merges another copy of the state into ours. Without the base both copies
+// started from, entities only one copy has are kept and every field the
+// copies disagree on is a conflict.
+pub fn merge_file(base_dir: &Path, other: &Path, base: Option<&Path>, resolution: Resolution, input: &mut dyn BufRead) -> Result<(), TedoError> {
+    let ours = storage::load_state(base_dir)?;
+    let revision_limit = config::load_config(base_dir)?.revision_limit();
+    let theirs = read_state_file(other, revision_limit)?;
+    let base = base.map(|base| read_state_file(base, revision_limit)).transpose()?.unwrap_or_default();
+
+    // Ask about all conflicts before changing anything
+    let mut answers = match resolution {
+        Resolution::Ask => {
+            let conflicts: Vec<Conflict> = merge_states(&base, &ours, &theirs, &mut |_| Side::Ours).conflicts
+                .into_iter().map(|(conflict, _)| conflict).collect();
+            ask(&conflicts, input)?
+        }
+        _ => Vec::new(),
+    }.into_iter();
+    let merge = merge_states(&base, &ours, &theirs, &mut |_| match resolution {
+        Resolution::Ours => Side::Ours,
+        Resolution::Theirs => Side::Theirs,
+        Resolution::Ask => answers.next().unwrap_or(Side::Ours),
+    });
+    save_state(base_dir, &merge.state)?;
+
+    if merge.conflicts.is_empty() {
+        println!("Merged {} without conflicts.", other.display());
+    } else {
+        let count = merge.conflicts.len();
+        println!("Merged {} with {} conflict{}:", other.display(), count, if count == 1 { "" } else { "s" });
+        for (conflict, side) in &merge.conflicts {
+            println!("  {}, kept {}", conflict, if *side == Side::Ours { "ours" } else { "theirs" });
+        }
+    }
+    Ok(())
+}
+
+
+fn ask(conflicts: &[Conflict], input: &mut dyn BufRead) -> Result<Vec<Side>, TedoError> {
+    let mut sides = Vec::new();
+    for (index, conflict) in conflicts.iter().enumerate() {
+        println!("Conflict {}/{} in {} ({})", index + 1, conflicts.len(), conflict.entity, conflict.field);
+        println!("  ours:   {}", conflict.ours);
+        println!("  theirs: {}", conflict.theirs);
+        loop {
+            print!("Keep [o]urs or [t]heirs? ");
+            std::io::stdout().flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                return Err(TedoError::Aborted("Merge aborted, nothing was changed".into()));
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "o" | "ours" => sides.push(Side::Ours),
+                "t" | "theirs" => sides.push(Side::Theirs),
+                _ => {
+                    println!("Please answer o or t.");
+                    continue;
+                }
+            }
+            break;
+        }
+    }
+    Ok(sides)
+}
+
+
+fn project_label(project: &Project) -> String {
+    format!("project '{}'", project.name)
+}
+
+
+fn merge_project(merger: &mut Merger, base: Option<&Project>, ours: &Project, theirs: &Project) -> Project {
+    let empty = Project::new(ours.id, "");
+    let base_lists = base.unwrap_or(&empty);
+    let name = ours.name.clone();
+
+    let mut project: Project = merge_fields(merger, base, ours, theirs, &project_label(ours), &["tasks", "notes"]);
+    project.tasks = merge_lists(
+        merger, &base_lists.tasks, &ours.tasks, &theirs.tasks, |t| t.id,
+        |t| format!("task {} '{}' in {}", t.id, t.description, name),
+        |merger, base, ours, theirs| merge_fields(merger, base, ours, theirs, &format!("task {} '{}' in {}", ours.id, ours.description, name), &[]),
+    );
+    project.notes = merge_lists(
+        merger, &base_lists.notes, &ours.notes, &theirs.notes, |n| n.id,
+        |n| format!("note {} '{}' in {}", n.id, n.description, name),
+        |merger, base, ours, theirs| merge_note(merger, base, ours, theirs, &name),
+    );
+    project
+}
+
+
+// The revision history follows the content it belongs to. Content combined
+// from both sides becomes a new revision.
+fn merge_note(merger: &mut Merger, base: Option<&Note>, ours: &Note, theirs: &Note, project_name: &str) -> Note {
+    let label = format!("note {} '{}' in {}", ours.id, ours.description, project_name);
+    let mut note: Note = merge_fields(merger, base, ours, theirs, &label, &["revisions"]);
+    note.revisions = if note.content == theirs.content && note.content != ours.content {
+        theirs.revisions.clone()
+    } else {
+        ours.revisions.clone()
+    };
+    if !note.revisions.is_empty() {
+        note.record_revision(chrono::Local::now().naive_local(), 0);
+    }
+    note
+}
+
+
+// Merges two lists of entities by id, keeping our order and appending what
+// only the other side has. Deleting an entity on one side while changing it
+// on the other is a conflict.
+fn merge_lists<T: Clone + Serialize>(
+    merger: &mut Merger,
+    base: &[T],
+    ours: &[T],
+    theirs: &[T],
+    id: impl Fn(&T) -> u32,
+    label: impl Fn(&T) -> String,
+    mut merge: impl FnMut(&mut Merger, Option<&T>, &T, &T) -> T,
+) -> Vec<T> {
+    let find = |list: &'_ [T], wanted: u32| list.iter().find(|item| id(item) == wanted).cloned();
+    let mut merged = Vec::new();
+
+    for our_item in ours {
+        let base_item = find(base, id(our_item));
+        match (find(theirs, id(our_item)), base_item) {
+            (Some(their_item), base_item) => merged.push(merge(merger, base_item.as_ref(), our_item, &their_item)),
+            (None, None) => merged.push(our_item.clone()),
+            (None, Some(base_item)) => {
+                if !same(&base_item, our_item) && merger.resolve(deletion_conflict(label(our_item), Side::Theirs)) == Side::Ours {
+                    merged.push(our_item.clone());
+                }
+            }
+        }
+    }
+
+    for their_item in theirs {
+        if find(ours, id(their_item)).is_some() {
+            continue;
+        }
+        match find(base, id(their_item)) {
+            None => merged.push(their_item.clone()),
+            Some(base_item) => {
+                if !same(&base_item, their_item) && merger.resolve(deletion_conflict(label(their_item), Side::Ours)) == Side::Theirs {
+                    merged.push(their_item.clone());
+                }
+            }
+        }
+    }
+    merged
+}
+
+
+fn deletion_conflict(entity: String, deleted_by: Side) -> Conflict {
+    let (ours, theirs) = match deleted_by {
+        Side::Ours => ("deleted", "changed"),
+        Side::Theirs => ("changed", "deleted"),
+    };
+    Conflict { entity, field: "existence".into(), ours: ours.into(), theirs: theirs.into() }
+}
+
+
+fn to_table<T: Serialize>(item: &T) -> Table {
+    match Value::try_from(item) {
+        Ok(Value::Table(table)) => table,
+        _ => Table::new(),
+    }
+}
+
+
+pub fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    to_table(a) == to_table(b)
+}
+
+
+// Merges the fields of one entity, leaving out `skip`. A missing field is an
+// unset optional value.
+fn merge_fields<T: Serialize + DeserializeOwned + Clone>(merger: &mut Merger, base: Option<&T>, ours: &T, theirs: &T, entity: &str, skip: &[&str]) -> T {
+    let base = base.map(to_table).unwrap_or_default();
+    let our_fields = to_table(ours);
+    let their_fields = to_table(theirs);
+
+    let mut keys: Vec<&String> = our_fields.keys().chain(their_fields.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let mut merged = our_fields.clone();
+    for key in keys {
+        if skip.contains(&key.as_str()) {
+            continue;
+        }
+        let (base_value, our_value, their_value) = (base.get(key), our_fields.get(key), their_fields.get(key));
+        let value = if our_value == their_value || their_value == base_value {
+            our_value.cloned()
+        } else if our_value == base_value {
+            their_value.cloned()
+        } else if key == "created" || key == "modified" {
+            // Created at the earliest, modified at the latest of both times
+            let mut times: Vec<&Value> = our_value.into_iter().chain(their_value).collect();
+            times.sort_by_key(|time| time.to_string());
+            if key == "created" { times.first() } else { times.last() }.copied().cloned()
+        } else if key == "next_task_id" || key == "next_note_id" {
+            // Ids handed out on either side stay taken
+            our_value.into_iter().chain(their_value).max_by_key(|id| id.as_integer()).cloned()
+        } else if SET_FIELDS.contains(&key.as_str()) {
+            Some(merge_sets(base_value, our_value, their_value))
+        } else if let Some(text) = merge_text_values(key, base_value, our_value, their_value) {
+            Some(text)
+        } else {
+            let conflict = Conflict {
+                entity: entity.to_string(),
+                field: key.clone(),
+                ours: describe_value(our_value),
+                theirs: describe_value(their_value),
+            };
+            match merger.resolve(conflict) {
+                Side::Ours => our_value.cloned(),
+                Side::Theirs => their_value.cloned(),
+            }
+        };
+        match value {
+            Some(value) => merged.insert(key.clone(), value),
+            None => merged.remove(key),
+        };
+    }
+    Value::Table(merged).try_into().unwrap_or_else(|_| ours.clone())
+}
+
+
+// Keeps what both sides have and what either side added
+fn merge_sets(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> Value {
+    let items = |value: Option<&Value>| value.and_then(Value::as_array).cloned().unwrap_or_default();
+    let (base, ours, theirs) = (items(base), items(ours), items(theirs));
+
+    let mut merged: Vec<Value> = ours.iter()
+        .filter(|item| theirs.contains(item) || !base.contains(item))
+        .cloned()
+        .collect();
+    for item in theirs {
+        if !base.contains(&item) && !merged.contains(&item) {
+            merged.push(item);
+        }
+    }
+    Value::Array(merged)
+}
+
+
+fn merge_text_values(key: &str, base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> Option<Value> {
+    if !TEXT_FIELDS.contains(&key) {
+        return None;
+    }
+    match (base?, ours?, theirs?) {
+        (Value::String(base), Value::String(ours), Value::String(theirs)) => merge_text(base, ours, theirs).map(Value::String),
+        _ => None,
+    }
+}
+
+
+// The changes each side made to a range of base lines, with the lines that
+// replace them
+fn changed_ranges<'a>(base: &[&str], other: &[&'a str]) -> Vec<(usize, usize, Vec<&'a str>)> {
+    similar::capture_diff_slices(Algorithm::Myers, base, other).iter()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        .map(|op| (op.old_range().start, op.old_range().end, other[op.new_range()].to_vec()))
+        .collect()
+}
+
+
+// Three-way merge of text by line. Returns None when both sides changed the
+// same or adjacent lines differently.
+pub fn merge_text(base: &str, ours: &str, theirs: &str) -> Option<String> {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let our_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let their_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+    let mut changes = changed_ranges(&base_lines, &our_lines);
+    changes.extend(changed_ranges(&base_lines, &their_lines));
+    changes.sort();
+    changes.dedup();
+    if changes.windows(2).any(|pair| pair[1].0 <= pair[0].1) {
+        return None;
+    }
+
+    let mut merged = String::new();
+    let mut position = 0;
+    for (start, end, lines) in changes {
+        merged.extend(base_lines[position..start].iter().copied());
+        merged.extend(lines);
+        position = end;
+    }
+    merged.extend(base_lines[position..].iter().copied());
+    Some(merged)
+}
+
+
+fn describe_value(value: Option<&Value>) -> String {
+    match value {
+        None => "unset".to_string(),
+        Some(Value::String(text)) => {
+            let line = text.lines().next().unwrap_or_default();
+            if line.chars().count() > 40 || text.lines().count() > 1 {
+                format!("'{}...'", line.chars().take(40).collect::<String>())
+            } else {
+                format!("'{}'", line)
+            }
+        }
+        Some(value) => value.to_string(),
+    }
+}
+
+
+fn side_task(side: &TedoState, task_ref: TaskRef) -> Option<&Task> {
+    side.projects.iter().find(|p| p.id == task_ref.project)?.tasks.iter().find(|t| t.id == task_ref.task)
+}
+
+
+fn task_ref_label(state: &TedoState, task_ref: TaskRef) -> String {
+    match state.projects.iter().find(|p| p.id == task_ref.project) {
+        Some(project) => format!("{}:{}", project.name, task_ref.task),
+        None => format!("{}:{}", task_ref.project, task_ref.task),
+    }
+}
+
+
+// The ids of tasks whose parents lead back to the first of them
+fn parent_cycle(project: &Project) -> Option<Vec<u32>> {
+    for task in &project.tasks {
+        let mut chain = vec![task.id];
+        let mut parent = task.parent;
+        while let Some(id) = parent {
+            if let Some(start) = chain.iter().position(|&other| other == id) {
+                return Some(chain[start..].to_vec());
+            }
+            chain.push(id);
+            parent = project.tasks.iter().find(|t| t.id == id).and_then(|t| t.parent);
+        }
+    }
+    None
+}
+
+
+// The tasks of a cycle take the parents the winning side gave them. That only
+// closes another cycle when different sides won, so after as many rounds as
+// there are tasks a cycle is broken by moving a task to the top level.
+fn break_parent_cycles(merger: &mut Merger, ours: &TedoState, theirs: &TedoState, state: &mut TedoState) {
+    for project_index in 0..state.projects.len() {
+        let mut rounds = 0;
+        while let Some(cycle) = parent_cycle(&state.projects[project_index]) {
+            let project = &state.projects[project_index];
+            let parent = |side: &TedoState, id: u32| side_task(side, TaskRef { project: project.id, task: id }).and_then(|t| t.parent);
+            let describe = |side: &TedoState| cycle.iter()
+                .map(|&id| match parent(side, id) {
+                    Some(parent) => format!("{} under {}", id, parent),
+                    None => format!("{} at the top", id),
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            rounds += 1;
+            let parents: Vec<Option<u32>> = if rounds > project.tasks.len() {
+                cycle.iter().enumerate().map(|(index, &id)| if index == 0 { None } else { parent(state, id) }).collect()
+            } else {
+                let ids: Vec<String> = cycle.iter().map(u32::to_string).collect();
+                let conflict = Conflict {
+                    entity: format!("tasks {} in {}", ids.join(", "), project.name),
+                    field: "parent".into(),
+                    ours: describe(ours),
+                    theirs: describe(theirs),
+                };
+                let side = if merger.resolve(conflict) == Side::Ours { ours } else { theirs };
+                cycle.iter().map(|&id| parent(side, id)).collect()
+            };
+            let project = &mut state.projects[project_index];
+            for (id, parent) in cycle.iter().zip(parents) {
+                if let Some(task) = project.tasks.iter_mut().find(|t| t.id == *id) {
+                    task.parent = parent;
+                }
+            }
+        }
+    }
+}
+
+
+// Tasks that depend on each other in a circle, each on the next one
+fn dependency_cycle(state: &TedoState) -> Option<Vec<TaskRef>> {
+    fn visit(state: &TedoState, current: TaskRef, path: &mut Vec<TaskRef>, done: &mut Vec<TaskRef>) -> Option<Vec<TaskRef>> {
+        if let Some(start) = path.iter().position(|&other| other == current) {
+            return Some(path[start..].to_vec());
+        }
+        if done.contains(&current) {
+            return None;
+        }
+        path.push(current);
+        for next in side_task(state, current).map(|t| t.depends_on.clone()).unwrap_or_default() {
+            if let Some(cycle) = visit(state, next, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.push(current);
+        None
+    }
+
+    let (mut path, mut done) = (Vec::new(), Vec::new());
+    state.projects.iter()
+        .flat_map(|p| p.tasks.iter().map(move |t| TaskRef { project: p.id, task: t.id }))
+        .find_map(|task_ref| visit(state, task_ref, &mut path, &mut done))
+}
+
+
+// The dependencies of a cycle that the winning side doesn't have are dropped
+fn break_dependency_cycles(merger: &mut Merger, ours: &TedoState, theirs: &TedoState, state: &mut TedoState) {
+    while let Some(cycle) = dependency_cycle(state) {
+        let links: Vec<(TaskRef, TaskRef)> = cycle.iter().copied().zip(cycle.iter().copied().cycle().skip(1)).collect();
+        let has_link = |side: &TedoState, (from, to): (TaskRef, TaskRef)| side_task(side, from).is_some_and(|t| t.depends_on.contains(&to));
+        let describe = |side: &TedoState| {
+            let kept: Vec<String> = links.iter()
+                .filter(|link| has_link(side, **link))
+                .map(|(from, to)| format!("{} depends on {}", task_ref_label(state, *from), task_ref_label(state, *to)))
+                .collect();
+            if kept.is_empty() { "none".to_string() } else { kept.join(", ") }
+        };
+        let labels: Vec<String> = cycle.iter().map(|task_ref| task_ref_label(state, *task_ref)).collect();
+        let conflict = Conflict {
+            entity: format!("tasks {}", labels.join(", ")),
+            field: "depends_on".into(),
+            ours: describe(ours),
+            theirs: describe(theirs),
+        };
+        let side = if merger.resolve(conflict) == Side::Ours { ours } else { theirs };
+
+        // A side that had the whole cycle, from a hand-edited file, loses the last link
+        let mut dropped: Vec<(TaskRef, TaskRef)> = links.iter().copied().filter(|link| !has_link(side, *link)).collect();
+        if dropped.is_empty() {
+            dropped.extend(links.last().copied());
+        }
+        for (from, to) in dropped {
+            let project = state.projects.iter_mut().find(|p| p.id == from.project).unwrap();
+            let task = project.tasks.iter_mut().find(|t| t.id == from.task).unwrap();
+            task.depends_on.retain(|dependency| *dependency != to);
+        }
+    }
+}
+
+
+// Only one timer runs at a time. The timers the winning side didn't have
+// running are stopped at the time of the merge.
+fn stop_extra_timers(merger: &mut Merger, ours: &TedoState, theirs: &TedoState, state: &mut TedoState) {
+    let running = |side: &TedoState| side.projects.iter()
+        .flat_map(|p| p.tasks.iter().map(move |t| (p, t)))
+        .flat_map(|(p, t)| t.time_entries.iter().filter(|e| e.end.is_none()).map(move |e| (TaskRef { project: p.id, task: t.id }, e.start)))
+        .collect::<Vec<(TaskRef, chrono::NaiveDateTime)>>();
+    let timers = running(state);
+    if timers.len() < 2 {
+        return;
+    }
+
+    let describe = |side: &TedoState| {
+        let labels: Vec<String> = running(side).into_iter().map(|(task_ref, _)| task_ref_label(state, task_ref)).collect();
+        if labels.is_empty() { "none".to_string() } else { format!("on {}", labels.join(", ")) }
+    };
+    let conflict = Conflict { entity: "timer".into(), field: "running".into(), ours: describe(ours), theirs: describe(theirs) };
+    let side = if merger.resolve(conflict) == Side::Ours { ours } else { theirs };
+    let mut kept = running(side);
+    kept.truncate(1);
+
+    let now = chrono::Local::now().naive_local();
+    for project in state.projects.iter_mut() {
+        let project_id = project.id;
+        for task in project.tasks.iter_mut() {
+            let task_ref = TaskRef { project: project_id, task: task.id };
+            for entry in task.time_entries.iter_mut().filter(|e| e.end.is_none() && !kept.contains(&(task_ref, e.start))) {
+                entry.end = Some(now.max(entry.start));
+            }
+        }
+    }
+}
+
+
+// Entities created on both sides under the same id are the same entity when
+// their names match. Otherwise the other side's entity moves to a new id and
+// the references to it move along.
+fn renumber_new_entities(base: &TedoState, ours: &TedoState, theirs: &mut TedoState) {
+    let mut next_project_id = base.projects.iter().chain(&ours.projects).chain(&theirs.projects)
+        .map(|p| p.id).max().unwrap_or(0) + 1;
+
+    for index in 0..theirs.projects.len() {
+        let id = theirs.projects[index].id;
+        let clashes = !base.projects.iter().any(|p| p.id == id)
+            && ours.projects.iter().any(|p| p.id == id && p.name != theirs.projects[index].name);
+        if clashes {
+            renumber_project(theirs, id, next_project_id);
+            next_project_id += 1;
+        }
+    }
+
+    for index in 0..theirs.projects.len() {
+        let project_id = theirs.projects[index].id;
+        let base_project = base.projects.iter().find(|p| p.id == project_id);
+        let our_project = match ours.projects.iter().find(|p| p.id == project_id) {
+            Some(project) => project,
+            None => continue,
+        };
+
+        // Ids of entities deleted on either side stay taken, and an id our
+        // side handed out since the base clashes even if it is gone again
+        let (base_tasks, our_tasks) = (base_project.map_or(1, Project::task_id_bound), our_project.task_id_bound());
+        let mut next_task_id = our_tasks.max(theirs.projects[index].task_id_bound()).max(base_tasks);
+        for task_index in 0..theirs.projects[index].tasks.len() {
+            let task = &theirs.projects[index].tasks[task_index];
+            let id = task.id;
+            let clashes = !base_project.is_some_and(|p| p.tasks.iter().any(|t| t.id == id))
+                && match our_project.tasks.iter().find(|t| t.id == task.id) {
+                    Some(our_task) => our_task.description != task.description,
+                    None => (base_tasks..our_tasks).contains(&task.id),
+                };
+            if clashes {
+                renumber_task(theirs, index, id, next_task_id);
+                next_task_id += 1;
+            }
+        }
+
+        let (base_notes, our_notes) = (base_project.map_or(1, Project::note_id_bound), our_project.note_id_bound());
+        let mut next_note_id = our_notes.max(theirs.projects[index].note_id_bound()).max(base_notes);
+        for note_index in 0..theirs.projects[index].notes.len() {
+            let note = &theirs.projects[index].notes[note_index];
+            let id = note.id;
+            let clashes = !base_project.is_some_and(|p| p.notes.iter().any(|n| n.id == id))
+                && match our_project.notes.iter().find(|n| n.id == note.id) {
+                    Some(our_note) => our_note.description != note.description,
+                    None => (base_notes..our_notes).contains(&note.id),
+                };
+            if clashes {
+                renumber_note(&mut theirs.projects[index], id, next_note_id);
+                next_note_id += 1;
+            }
+        }
+    }
+}
+
+
+fn renumber_project(state: &mut TedoState, old: u32, new: u32) {
+    for project in state.projects.iter_mut() {
+        if project.id == old {
+            project.id = new;
+        }
+        for dependency in project.tasks.iter_mut().flat_map(|t| t.depends_on.iter_mut()) {
+            if dependency.project == old {
+                dependency.project = new;
+            }
+        }
+    }
+}
+
+
+fn renumber_task(state: &mut TedoState, project_index: usize, old: u32, new: u32) {
+    let project_id = state.projects[project_index].id;
+    for project in state.projects.iter_mut() {
+        for dependency in project.tasks.iter_mut().flat_map(|t| t.depends_on.iter_mut()) {
+            if dependency.project == project_id && dependency.task == old {
+                dependency.task = new;
+            }
+        }
+    }
+
+    let project = &mut state.projects[project_index];
+    for task in project.tasks.iter_mut() {
+        if task.id == old {
+            task.id = new;
+        }
+        if task.parent == Some(old) {
+            task.parent = Some(new);
+        }
+        if task.template == Some(old) {
+            task.template = Some(new);
+        }
+    }
+    for note in project.notes.iter_mut() {
+        note.content = replace_reference(&note.content, "#task:", "", old, new);
+    }
+}
+
+
+fn renumber_note(project: &mut Project, old: u32, new: u32) {
+    for note in project.notes.iter_mut() {
+        if note.id == old {
+            note.id = new;
+        }
+        note.content = replace_reference(&note.content, "[[note:", "]]", old, new);
+    }
+    for task in project.tasks.iter_mut() {
+        task.notes.iter_mut().filter(|id| **id == old).for_each(|id| *id = new);
+    }
+}
+
+
+// Rewrites `<prefix><old><suffix>` references to point at the new id, taking
+// care that `#task:1` doesn't match `#task:12`
+fn replace_reference(content: &str, prefix: &str, suffix: &str, old: u32, new: u32) -> String {
+    let mut output = String::new();
+    let mut rest = content;
+    while let Some(start) = rest.find(prefix) {
+        output.push_str(&rest[..start + prefix.len()]);
+        rest = &rest[start + prefix.len()..];
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if rest[..digits].parse::<u32>() == Ok(old) && rest[digits..].starts_with(suffix) {
+            output.push_str(&new.to_string());
+            rest = &rest[digits..];
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::{Recurrence, TaskStatus, TimeEntry};
+
+    use super::*;
+
+    fn task(id: u32, description: &str) -> Task {
+        Task::new(id, description)
+    }
+
+    fn state(tasks: Vec<Task>, notes: Vec<Note>) -> TedoState {
+        TedoState {
+            current_project: Some("growth".into()),
+            projects: vec![Project { tasks, notes, ..Project::new(1, "growth") }],
+        }
+    }
+
+    fn ours_wins(_: &Conflict) -> Side {
+        Side::Ours
+    }
+
+    #[test]
+    fn test_changes_to_different_fields_combine() {
+        let base = state(vec![task(1, "Write docs")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks[0].status = TaskStatus::Done;
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[0].description = "Write the docs".into();
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let merged = &merge.state.projects[0].tasks[0];
+        assert_eq!(merged.status, TaskStatus::Done);
+        assert_eq!(merged.description, "Write the docs");
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_conflicting_field_is_resolved_by_the_caller() {
+        let base = state(vec![task(1, "Write docs")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks[0].description = "Docs".into();
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[0].description = "Documentation".into();
+
+        let merge = merge_states(&base, &ours, &theirs, &mut |_| Side::Theirs);
+        assert_eq!(merge.state.projects[0].tasks[0].description, "Documentation");
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.conflicts[0].0.field, "description");
+        assert_eq!(merge.conflicts[0].0.to_string(), "task 1 'Docs' in growth (description): 'Docs' here, 'Documentation' there");
+    }
+
+    #[test]
+    fn test_tasks_created_on_both_sides_get_distinct_ids() {
+        let base = state(vec![task(1, "Shared")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks.push(task(2, "Ours"));
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks.push(task(2, "Theirs"));
+        let mut subtask = task(3, "Theirs, part two");
+        subtask.parent = Some(2);
+        subtask.depends_on.push(TaskRef { project: 1, task: 2 });
+        theirs.projects[0].tasks.push(subtask);
+        theirs.projects[0].notes.push(Note::new(1, "Plan", "See #task:2 and #task:23"));
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let project = &merge.state.projects[0];
+        let ids: Vec<(u32, &str)> = project.tasks.iter().map(|t| (t.id, t.description.as_str())).collect();
+        assert_eq!(ids, vec![(1, "Shared"), (2, "Ours"), (4, "Theirs"), (3, "Theirs, part two")]);
+        assert_eq!(project.tasks[3].parent, Some(4));
+        assert_eq!(project.tasks[3].depends_on, vec![TaskRef { project: 1, task: 4 }]);
+        assert_eq!(project.notes[0].content, "See #task:4 and #task:23");
+    }
+
+    #[test]
+    fn test_renumbered_templates_keep_their_instances() {
+        let base = state(vec![task(1, "Shared")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks.push(task(2, "Ours"));
+        let mut theirs = base.clone();
+        let mut template = task(2, "Rotate keys");
+        template.recurrence = Some(Recurrence::parse("weekly").unwrap());
+        let mut instance = task(3, "Rotate keys");
+        instance.template = Some(2);
+        theirs.projects[0].tasks.extend([template, instance]);
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let project = &merge.state.projects[0];
+        let template = project.tasks.iter().find(|t| t.is_template()).unwrap();
+        assert_ne!(template.id, 2);
+        assert_eq!(project.tasks.iter().find(|t| t.template.is_some()).unwrap().template, Some(template.id));
+    }
+
+    #[test]
+    fn test_ids_deleted_on_our_side_are_not_reused() {
+        let base = state(vec![task(1, "Shared")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].allocate_task_id();
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks.push(task(2, "Theirs"));
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let project = &merge.state.projects[0];
+        let ids: Vec<u32> = project.tasks.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(project.clone().allocate_task_id(), 4);
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_deleting_a_changed_task_is_a_conflict() {
+        let base = state(vec![task(1, "Keep"), task(2, "Drop")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks.retain(|t| t.id != 2);
+        ours.projects[0].tasks[0].tags.push("a".into());
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[1].status = TaskStatus::Done;
+        theirs.projects[0].tasks[0].tags.push("b".into());
+
+        let merge = merge_states(&base, &ours, &theirs, &mut |_| Side::Theirs);
+        let project = &merge.state.projects[0];
+        assert_eq!(project.tasks.len(), 2);
+        assert_eq!(project.tasks[0].tags, vec!["a", "b"]);
+        assert_eq!(merge.conflicts[0].0.field, "existence");
+    }
+
+    #[test]
+    fn test_replace_reference() {
+        assert_eq!(replace_reference("[[note:1]] [[note:12]] [[note:1]", "[[note:", "]]", 1, 5), "[[note:5]] [[note:12]] [[note:1]");
+    }
+
+    #[test]
+    fn test_merging_identical_copies_changes_nothing() {
+        let base = state(vec![task(1, "One")], vec![Note::new(1, "Plan", "text")]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks.push(task(2, "Two"));
+
+        let merge = merge_states(&base, &ours, &ours, &mut ours_wins);
+        assert!(same(&merge.state, &ours));
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_one_sided_changes_are_taken() {
+        let base = state(vec![task(1, "One"), task(2, "Two")], vec![]);
+        let ours = base.clone();
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[0].description = "Uno".into();
+        theirs.projects[0].tasks[1].parent = Some(1);
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        assert!(same(&merge.state, &theirs));
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_the_same_change_on_both_sides_is_no_conflict() {
+        let base = state(vec![task(1, "One")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks[0].status = TaskStatus::Done;
+        let theirs = ours.clone();
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        assert!(merge.state.projects[0].tasks[0].is_done());
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_setting_and_unsetting_an_optional_field_conflicts() {
+        let mut base = state(vec![task(1, "Parent"), task(2, "Other parent"), task(3, "Child")], vec![]);
+        base.projects[0].tasks[2].parent = Some(1);
+        let mut ours = base.clone();
+        ours.projects[0].tasks[2].parent = None;
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[2].parent = Some(2);
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        assert_eq!(merge.state.projects[0].tasks[2].parent, None);
+        assert_eq!(merge.conflicts[0].0.ours, "unset");
+        assert_eq!(merge.conflicts[0].0.theirs, "2");
+
+        let merge = merge_states(&base, &ours, &theirs, &mut |_| Side::Theirs);
+        assert_eq!(merge.state.projects[0].tasks[2].parent, Some(2));
+    }
+
+    #[test]
+    fn test_tags_and_dependencies_merge_as_sets() {
+        let mut base = state(vec![task(1, "One"), task(2, "Two"), task(3, "Three")], vec![]);
+        base.projects[0].tasks[0].tags = vec!["keep".into(), "drop".into()];
+        base.projects[0].tasks[0].depends_on = vec![TaskRef { project: 1, task: 2 }];
+        let mut ours = base.clone();
+        ours.projects[0].tasks[0].tags = vec!["keep".into(), "ours".into()];
+        ours.projects[0].tasks[0].depends_on.push(TaskRef { project: 1, task: 3 });
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[0].tags = vec!["keep".into(), "drop".into(), "theirs".into()];
+        theirs.projects[0].tasks[0].depends_on.clear();
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let merged = &merge.state.projects[0].tasks[0];
+        assert_eq!(merged.tags, vec!["keep", "ours", "theirs"]);
+        assert_eq!(merged.depends_on, vec![TaskRef { project: 1, task: 3 }]);
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_parent_and_dependency_cycles_are_conflicts() {
+        let base = state(vec![task(1, "One"), task(2, "Two")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks[0].parent = Some(2);
+        ours.projects[0].tasks[0].depends_on = vec![TaskRef { project: 1, task: 2 }];
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[1].parent = Some(1);
+        theirs.projects[0].tasks[1].depends_on = vec![TaskRef { project: 1, task: 1 }];
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let fields: Vec<&str> = merge.conflicts.iter().map(|(conflict, _)| conflict.field.as_str()).collect();
+        assert_eq!(fields, vec!["parent", "depends_on"]);
+        assert_eq!(merge.conflicts[0].0.to_string(), "tasks 1, 2 in growth (parent): 1 under 2, 2 at the top here, 1 at the top, 2 under 1 there");
+        assert_eq!(merge.conflicts[1].0.ours, "growth:1 depends on growth:2");
+        let tasks = &merge.state.projects[0].tasks;
+        assert_eq!((tasks[0].parent, tasks[1].parent), (Some(2), None));
+        assert_eq!((tasks[0].depends_on.len(), tasks[1].depends_on.len()), (1, 0));
+
+        let merge = merge_states(&base, &ours, &theirs, &mut |_| Side::Theirs);
+        let tasks = &merge.state.projects[0].tasks;
+        assert_eq!((tasks[0].parent, tasks[1].parent), (None, Some(1)));
+        assert_eq!((tasks[0].depends_on.len(), tasks[1].depends_on.len()), (0, 1));
+    }
+
+    #[test]
+    fn test_a_second_running_timer_is_a_conflict() {
+        let start = chrono::NaiveDate::from_ymd_opt(2026, 10, 19).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        let base = state(vec![task(1, "One"), task(2, "Two")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks[0].time_entries.push(TimeEntry { start, end: None });
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[1].time_entries.push(TimeEntry { start, end: None });
+
+        let merge = merge_states(&base, &ours, &theirs, &mut |_| Side::Theirs);
+        assert_eq!(merge.conflicts[0].0.to_string(), "timer (running): on growth:1 here, on growth:2 there");
+        let tasks = &merge.state.projects[0].tasks;
+        assert!(!tasks[0].is_tracking());
+        assert!(tasks[1].is_tracking());
+    }
+
+    #[test]
+    fn test_deleting_an_unchanged_entity_wins() {
+        let base = state(vec![task(1, "One"), task(2, "Two")], vec![Note::new(1, "Plan", "")]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks.retain(|t| t.id != 1);
+        let mut theirs = base.clone();
+        theirs.projects[0].notes.clear();
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let project = &merge.state.projects[0];
+        assert_eq!(project.tasks.iter().map(|t| t.id).collect::<Vec<u32>>(), vec![2]);
+        assert!(project.notes.is_empty());
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_deleting_on_both_sides_removes() {
+        let base = state(vec![task(1, "One")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks.clear();
+        let theirs = ours.clone();
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        assert!(merge.state.projects[0].tasks.is_empty());
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_deleting_our_changed_task_keeps_it_unless_theirs_wins() {
+        let base = state(vec![task(1, "One")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks[0].status = TaskStatus::Done;
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks.clear();
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        assert!(merge.state.projects[0].tasks[0].is_done());
+        assert_eq!(merge.conflicts[0].0.to_string(), "task 1 'One' in growth (existence): changed here, deleted there");
+
+        let merge = merge_states(&base, &ours, &theirs, &mut |_| Side::Theirs);
+        assert!(merge.state.projects[0].tasks.is_empty());
+    }
+
+    #[test]
+    fn test_tasks_created_on_both_sides_with_the_same_title_are_one_task() {
+        let base = state(vec![], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks.push(task(1, "Release"));
+        ours.projects[0].tasks[0].tags.push("ours".into());
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks.push(task(1, "Release"));
+        theirs.projects[0].tasks[0].tags.push("theirs".into());
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let tasks = &merge.state.projects[0].tasks;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].tags, vec!["ours", "theirs"]);
+    }
+
+    #[test]
+    fn test_notes_created_on_both_sides_get_distinct_ids() {
+        let base = state(vec![task(1, "Task")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].notes.push(Note::new(1, "Ours", ""));
+        let mut theirs = base.clone();
+        theirs.projects[0].notes.push(Note::new(1, "Theirs", ""));
+        theirs.projects[0].notes.push(Note::new(2, "Index", "[[note:1]] and [[note:10]]"));
+        theirs.projects[0].tasks[0].notes = vec![1, 2];
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let project = &merge.state.projects[0];
+        let ids: Vec<(u32, &str)> = project.notes.iter().map(|n| (n.id, n.description.as_str())).collect();
+        assert_eq!(ids, vec![(1, "Ours"), (3, "Theirs"), (2, "Index")]);
+        assert_eq!(project.notes[2].content, "[[note:3]] and [[note:10]]");
+        assert_eq!(project.tasks[0].notes, vec![3, 2]);
+    }
+
+    #[test]
+    fn test_projects_created_on_both_sides_get_distinct_ids() {
+        let base = TedoState::default();
+        let mut ours = base.clone();
+        ours.projects.push(Project { tasks: vec![task(1, "Ours")], ..Project::new(1, "growth") });
+        let mut theirs = base.clone();
+        theirs.projects.push(Project { tasks: vec![task(1, "Theirs")], ..Project::new(1, "infra") });
+        let mut dependent = task(2, "Depends on theirs");
+        dependent.depends_on.push(TaskRef { project: 1, task: 1 });
+        theirs.projects[0].tasks.push(dependent);
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let projects = &merge.state.projects;
+        assert_eq!(projects.iter().map(|p| (p.id, p.name.as_str())).collect::<Vec<_>>(), vec![(1, "growth"), (2, "infra")]);
+        assert_eq!(projects[1].tasks[1].depends_on, vec![TaskRef { project: 2, task: 1 }]);
+    }
+
+    #[test]
+    fn test_current_project_follows_a_rename_on_the_other_side() {
+        let base = state(vec![], vec![]);
+        let ours = base.clone();
+        let mut theirs = base.clone();
+        theirs.projects[0].name = "growth-team".into();
+        theirs.current_project = Some("growth-team".into());
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        assert_eq!(merge.state.current_project.as_deref(), Some("growth-team"));
+    }
+
+    #[test]
+    fn test_note_edits_to_different_lines_combine() {
+        let base = state(vec![], vec![Note::new(1, "Plan", "one\ntwo\nthree\nfour\n")]);
+        let mut ours = base.clone();
+        ours.projects[0].notes[0].set_content("ONE\ntwo\nthree\nfour\n".into(), chrono::Local::now().naive_local(), 0);
+        let mut theirs = base.clone();
+        theirs.projects[0].notes[0].content = "one\ntwo\nthree\nFOUR\nfive\n".into();
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let note = &merge.state.projects[0].notes[0];
+        assert_eq!(note.content, "ONE\ntwo\nthree\nFOUR\nfive\n");
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(note.revisions.len(), 3);
+        assert_eq!(note.revisions[2].content, note.content);
+    }
+
+    #[test]
+    fn test_note_edits_to_the_same_line_conflict() {
+        let base = state(vec![], vec![Note::new(1, "Plan", "one\ntwo\n")]);
+        let mut ours = base.clone();
+        ours.projects[0].notes[0].content = "one\n2\n".into();
+        let mut theirs = base.clone();
+        theirs.projects[0].notes[0].content = "one\nzwei\n".into();
+
+        let merge = merge_states(&base, &ours, &theirs, &mut |_| Side::Theirs);
+        assert_eq!(merge.state.projects[0].notes[0].content, "one\nzwei\n");
+        assert_eq!(merge.conflicts[0].0.field, "content");
+        assert_eq!(merge.conflicts[0].0.ours, "'one...'");
+    }
+
+    #[test]
+    fn test_merge_text() {
+        assert_eq!(merge_text("a\nb\nc\n", "a\nb\nc\n", "a\nB\nc\n").as_deref(), Some("a\nB\nc\n"));
+        assert_eq!(merge_text("a\nb\nc\n", "x\na\nb\nc\n", "a\nb\nc\ny\n").as_deref(), Some("x\na\nb\nc\ny\n"));
+        assert_eq!(merge_text("a\nb\n", "a\nb\nc\n", "a\nb\nc\n").as_deref(), Some("a\nb\nc\n"));
+        assert_eq!(merge_text("a\nb\n", "a\nb\nc\n", "a\nb\nd\n"), None);
+        assert_eq!(merge_text("a\nb\nc\n", "A\nb\nc\n", "a\nB\nc\n"), None);
+    }
+
+    #[test]
+    fn test_without_a_base_differences_conflict_and_additions_are_kept() {
+        let base = TedoState::default();
+        let mut ours = state(vec![task(1, "One")], vec![]);
+        ours.projects[0].tasks[0].tags.push("ours".into());
+        let mut theirs = ours.clone();
+        theirs.projects[0].tasks[0].tags = vec!["theirs".into()];
+        theirs.projects[0].tasks[0].status = TaskStatus::Done;
+        theirs.projects[0].tasks.push(task(2, "Two"));
+
+        let merge = merge_states(&base, &ours, &theirs, &mut ours_wins);
+        let tasks = &merge.state.projects[0].tasks;
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].tags, vec!["ours", "theirs"]);
+        assert_eq!(tasks[0].status, TaskStatus::Open);
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.conflicts[0].0.field, "status");
+    }
+
+    #[test]
+    fn test_merge_file_with_a_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_dir = dir.path();
+        let base = state(vec![task(1, "One")], vec![]);
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[0].description = "Theirs".into();
+        theirs.projects[0].tasks.push(task(2, "Two"));
+        let mut ours = base.clone();
+        ours.projects[0].tasks[0].description = "Ours".into();
+
+        save_state(base_dir, &ours).unwrap();
+        fs::write(base_dir.join("base.toml"), toml::to_string(&base).unwrap()).unwrap();
+        fs::write(base_dir.join("theirs.toml"), toml::to_string(&theirs).unwrap()).unwrap();
+
+        let other = base_dir.join("theirs.toml");
+        merge_file(base_dir, &other, Some(&base_dir.join("base.toml")), Resolution::Theirs, &mut &b""[..]).unwrap();
+        let merged = storage::load_state(base_dir).unwrap();
+        assert_eq!(merged.projects[0].tasks.iter().map(|t| t.description.as_str()).collect::<Vec<_>>(), vec!["Theirs", "Two"]);
+
+        assert!(matches!(merge_file(base_dir, &base_dir.join("missing.toml"), None, Resolution::Ours, &mut &b""[..]), Err(TedoError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_merge_file_asks_about_each_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_dir = dir.path();
+        let base = state(vec![task(1, "One"), task(2, "Two")], vec![]);
+        let mut ours = base.clone();
+        ours.projects[0].tasks[0].description = "Ours 1".into();
+        ours.projects[0].tasks[1].description = "Ours 2".into();
+        let mut theirs = base.clone();
+        theirs.projects[0].tasks[0].description = "Theirs 1".into();
+        theirs.projects[0].tasks[1].description = "Theirs 2".into();
+
+        save_state(base_dir, &ours).unwrap();
+        fs::write(base_dir.join("base.toml"), toml::to_string(&base).unwrap()).unwrap();
+        fs::write(base_dir.join("theirs.toml"), toml::to_string(&theirs).unwrap()).unwrap();
+        let (other, base_file) = (base_dir.join("theirs.toml"), base_dir.join("base.toml"));
+
+        // Running out of answers aborts without saving
+        let result = merge_file(base_dir, &other, Some(&base_file), Resolution::Ask, &mut &b"t\n"[..]);
+        assert!(matches!(result, Err(TedoError::Aborted(_))));
+        assert_eq!(storage::load_state(base_dir).unwrap().projects[0].tasks[0].description, "Ours 1");
+
+        merge_file(base_dir, &other, Some(&base_file), Resolution::Ask, &mut &b"maybe\nt\no\n"[..]).unwrap();
+        let merged = storage::load_state(base_dir).unwrap();
+        assert_eq!(merged.projects[0].tasks.iter().map(|t| t.description.as_str()).collect::<Vec<_>>(), vec!["Theirs 1", "Ours 2"]);
+    }
+
+    #[test]
+    fn test_merge_file_reads_the_other_note_files() {
+        let (dir, other_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        for base_dir in [dir.path(), other_dir.path()] {
+            config::configure(base_dir, Some("note_storage"), Some("files")).unwrap();
+            crate::projects::create_project(base_dir, "growth", true).unwrap();
+            crate::notes::create_note(base_dir, None, "Plan", "shared").unwrap();
+        }
+        crate::notes::append_note(other_dir.path(), None, 1, "theirs").unwrap();
+
+        let other = storage::get_state_path(other_dir.path());
+        merge_file(dir.path(), &other, None, Resolution::Theirs, &mut &b""[..]).unwrap();
+        let merged = storage::load_state(dir.path()).unwrap();
+        assert_eq!(merged.projects[0].notes[0].content, storage::load_state(other_dir.path()).unwrap().projects[0].notes[0].content);
+        assert!(merged.projects[0].notes[0].content.contains("theirs"));
+    }
+}
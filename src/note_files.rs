@@ -0,0 +1,408 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::TedoError;
+use crate::front_matter;
+use crate::notes::slugify;
+use crate::storage::{Note, Project, Revision, TedoState};
+
+
+// With `note_storage = "files"` every note lives in
+// ~/.tedo/notes/<project>-<id>/<slug>.md, with its id, title and tags as
+// front-matter, and its revisions in .revisions/<slug>.toml next to it. The
+// state file only keeps the path. The files are the source of truth: edits
+// made outside tedo are picked up on the next load, renamed files are found by
+// their id and new files are imported as notes. A note whose file is gone is
+// written back from its last revision, notes are only deleted with tedo.
+
+#[derive(Serialize, Deserialize, Default)]
+struct RevisionFile {
+    #[serde(default)]
+    revisions: Vec<Revision>,
+}
+
+pub fn notes_dir(base_dir: &Path) -> PathBuf {
+    base_dir.join("notes")
+}
+
+
+fn note_text(note: &Note) -> String {
+    front_matter::with_front_matter(&[
+        ("id", note.id.to_string()),
+        ("title", note.description.clone()),
+        ("tags", front_matter::format_list(&note.tags)),
+    ], &note.content)
+}
+
+
+fn front_matter_id(text: &str) -> Option<u32> {
+    let (fields, _) = front_matter::split_front_matter(text);
+    front_matter::field(&fields, "id").and_then(|id| id.parse().ok())
+}
+
+
+fn apply_note_text(note: &mut Note, text: &str) {
+    let (fields, body) = front_matter::split_front_matter(text);
+    if let Some(title) = front_matter::field(&fields, "title").filter(|title| !title.is_empty()) {
+        note.description = title.to_string();
+    }
+    if let Some(tags) = front_matter::field(&fields, "tags") {
+        note.tags = front_matter::parse_list(tags);
+    }
+    note.content = body;
+}
+
+
+fn revisions_path(notes_dir: &Path, file: &str) -> PathBuf {
+    let path = notes_dir.join(file);
+    let name = format!("{}.toml", path.file_stem().unwrap_or_default().to_string_lossy());
+    path.with_file_name(".revisions").join(name)
+}
+
+
+// None when the note has no revision file yet, e.g. in state files written
+// before revisions were kept next to the notes
+fn read_revisions(path: &Path) -> Result<Option<Vec<Revision>>, TedoError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(toml::from_str::<RevisionFile>(&text)?.revisions)),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+
+// The id keeps apart projects whose names slugify the same, e.g. "Growth Team"
+// and "growth-team"
+fn project_dir_name(project: &Project) -> String {
+    let slug = slugify(&project.name);
+    if slug.is_empty() { format!("project-{}", project.id) } else { format!("{}-{}", slug, project.id) }
+}
+
+
+// Whether files in `dir` belong to the project, i.e. its notes are kept there
+fn owns_dir(project: &Project, dir: &str) -> bool {
+    project_dir_name(project) == dir
+        || project.notes.iter().any(|n| n.file.as_deref().is_some_and(|f| f.starts_with(&format!("{}/", dir))))
+}
+
+
+// All Markdown files below the notes directory, as paths relative to it
+fn markdown_files(notes_dir: &Path) -> Result<Vec<String>, TedoError> {
+    let mut files = Vec::new();
+    let project_dirs = match fs::read_dir(notes_dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(files),
+        Err(error) => return Err(error.into()),
+    };
+    for project_dir in project_dirs {
+        let project_dir = project_dir?;
+        if !project_dir.file_type()?.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(project_dir.path())? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "md") {
+                files.push(format!(
+                    "{}/{}",
+                    project_dir.file_name().to_string_lossy(),
+                    path.file_name().unwrap().to_string_lossy()
+                ));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+
+fn is_referenced(tedo_state: &TedoState, file: &str) -> bool {
+    tedo_state.projects.iter().flat_map(|p| &p.notes).any(|n| n.file.as_deref() == Some(file))
+}
+
+
+// Files created outside of tedo are only imported when `import` is set, i.e.
+// while notes are stored as files. Otherwise this just brings back the content
+// of notes that were stored as files before. Content changed outside of tedo
+// is recorded as a new revision.
+pub fn read_note_files(base_dir: &Path, tedo_state: &mut TedoState, import: bool, revision_limit: usize) -> Result<(), TedoError> {
+    let notes_dir = notes_dir(base_dir);
+    let now = chrono::Local::now().naive_local();
+
+    // Reconcile the notes we know about with their files, found by the id in
+    // their front-matter first and by their path otherwise
+    let files = markdown_files(&notes_dir)?;
+    for project in tedo_state.projects.iter_mut() {
+        let mut texts = Vec::new();
+        for file in files.iter().filter(|file| owns_dir(project, file.split_once('/').unwrap().0)) {
+            texts.push((file.clone(), fs::read_to_string(notes_dir.join(file))?));
+        }
+        let ids: Vec<u32> = project.notes.iter().map(|n| n.id).collect();
+
+        for note in project.notes.iter_mut() {
+            let recorded = match note.file.clone() {
+                Some(file) => file,
+                None => continue,
+            };
+            if let Some(revisions) = read_revisions(&revisions_path(&notes_dir, &recorded))? {
+                note.revisions = revisions;
+            }
+            let by_id = texts.iter()
+                .filter(|(_, text)| front_matter_id(text) == Some(note.id))
+                .min_by_key(|(file, _)| *file != recorded);
+            let by_path = texts.iter().find(|(file, text)| {
+                *file == recorded && front_matter_id(text).is_none_or(|id| id == note.id || !ids.contains(&id))
+            });
+            match by_id.or(by_path) {
+                Some((file, text)) => {
+                    note.file = Some(file.clone());
+                    apply_note_text(note, text);
+                    note.record_revision(now, revision_limit);
+                }
+                // Written back on the next save
+                None => note.content = note.revisions.last().map(|revision| revision.content.clone()).unwrap_or_default(),
+            }
+        }
+    }
+
+    if !import {
+        return Ok(());
+    }
+
+    // Import files created outside of tedo into the project owning the directory
+    for file in markdown_files(&notes_dir)? {
+        if is_referenced(tedo_state, &file) {
+            continue;
+        }
+        let (dir, file_name) = file.split_once('/').unwrap();
+        let project = tedo_state.projects.iter_mut().find(|p| owns_dir(p, dir));
+        let project = match project {
+            Some(project) => project,
+            None => continue,
+        };
+
+        let id = project.allocate_note_id();
+        let mut note = Note::new(id, file_name.trim_end_matches(".md"), "");
+        note.file = Some(file.clone());
+        note.revisions = read_revisions(&revisions_path(&notes_dir, &file))?.unwrap_or_default();
+        apply_note_text(&mut note, &fs::read_to_string(notes_dir.join(&file))?);
+        note.slug = project.unique_note_slug(&note.description, id);
+        note.record_revision(now, revision_limit);
+        project.notes.push(note);
+    }
+    Ok(())
+}
+
+
+// Writes changed notes and their revisions to their files, removes the files
+// of notes deleted since the `saved` state and strips both from the state that
+// is about to be saved. Other files in the notes directory are left alone.
+pub fn write_note_files(base_dir: &Path, saved: &TedoState, tedo_state: &mut TedoState) -> Result<(), TedoError> {
+    let notes_dir = notes_dir(base_dir);
+
+    for project_index in 0..tedo_state.projects.len() {
+        for note_index in 0..tedo_state.projects[project_index].notes.len() {
+            if tedo_state.projects[project_index].notes[note_index].file.is_none() {
+                let file = new_file_name(tedo_state, &notes_dir, project_index, note_index);
+                tedo_state.projects[project_index].notes[note_index].file = Some(file);
+            }
+
+            let note = &mut tedo_state.projects[project_index].notes[note_index];
+            let file = note.file.clone().unwrap();
+            let revisions = toml::to_string(&RevisionFile { revisions: std::mem::take(&mut note.revisions) })?;
+            for (path, text) in [(notes_dir.join(&file), note_text(note)), (revisions_path(&notes_dir, &file), revisions)] {
+                if fs::read_to_string(&path).ok().as_deref() != Some(text.as_str()) {
+                    fs::create_dir_all(path.parent().unwrap())?;
+                    fs::write(&path, text)?;
+                }
+            }
+            note.content = String::new();
+        }
+    }
+
+    let deleted = saved.projects.iter()
+        .flat_map(|p| &p.notes)
+        .filter_map(|n| n.file.as_deref())
+        .filter(|file| !is_referenced(tedo_state, file));
+    for file in deleted {
+        for path in [notes_dir.join(file), revisions_path(&notes_dir, file)] {
+            match fs::remove_file(path) {
+                Err(error) if error.kind() != ErrorKind::NotFound => return Err(error.into()),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+
+fn new_file_name(tedo_state: &TedoState, notes_dir: &Path, project_index: usize, note_index: usize) -> String {
+    let project = &tedo_state.projects[project_index];
+    let note = &project.notes[note_index];
+    let dir = project_dir_name(project);
+
+    let slug = if note.slug.is_empty() { slugify(&note.description) } else { note.slug.clone() };
+    let slug = if slug.is_empty() { format!("note-{}", note.id) } else { slug };
+    let file = format!("{}/{}.md", dir, slug);
+    if !is_referenced(tedo_state, &file) && !notes_dir.join(&file).exists() {
+        return file;
+    }
+    format!("{}/{}-{}.md", dir, slug, note.id)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::config;
+    use crate::notes::{create_note, delete_note};
+    use crate::projects::create_project;
+    use crate::storage::{load_state, save_state};
+
+    use super::*;
+
+    fn setup(base_dir: &Path) {
+        config::configure(base_dir, Some("note_storage"), Some("files")).unwrap();
+        create_project(base_dir, "Growth Team", true).unwrap();
+        create_note(base_dir, None, "Sprint review", "# Review\n").unwrap();
+        create_note(base_dir, None, "Sprint review", "again").unwrap();
+    }
+
+    #[test]
+    fn test_notes_are_written_to_files() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+
+        let first = fs::read_to_string(notes_dir(base_dir).join("growth-team-1/sprint-review.md")).unwrap();
+        assert_eq!(first, "---\nid: 1\ntitle: Sprint review\ntags: []\n---\n# Review\n");
+        assert!(notes_dir(base_dir).join("growth-team-1/sprint-review-2.md").exists());
+
+        // Neither the content nor the revisions are repeated in the state file
+        let state_file: TedoState = toml::from_str(&fs::read_to_string(base_dir.join("tedo_state.toml")).unwrap()).unwrap();
+        assert_eq!(state_file.projects[0].notes[0].content, "");
+        assert!(state_file.projects[0].notes[0].revisions.is_empty());
+        assert!(notes_dir(base_dir).join("growth-team-1/.revisions/sprint-review.toml").exists());
+
+        let state = load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].notes[0].content, "# Review\n");
+        assert_eq!(state.projects[0].notes[0].revisions[0].content, "# Review\n");
+        assert_eq!(state.projects[0].notes[1].content, "again");
+    }
+
+    #[test]
+    fn test_external_changes_are_reconciled() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+        let project_dir = notes_dir(base_dir).join("growth-team-1");
+
+        fs::remove_file(project_dir.join("sprint-review.md")).unwrap();
+        fs::write(project_dir.join("retro.md"), "---\nid: 1\ntitle: Retro\ntags: [team]\n---\nedited").unwrap();
+        fs::write(project_dir.join("ideas.md"), "- more tests").unwrap();
+
+        let state = load_state(base_dir).unwrap();
+        let notes = &state.projects[0].notes;
+        assert_eq!(notes.len(), 3);
+        // A renamed file stays with its note
+        assert_eq!(notes[0].file.as_deref(), Some("growth-team-1/retro.md"));
+        assert_eq!(notes[0].description, "Retro");
+        assert_eq!(notes[0].tags, vec!["team"]);
+        assert_eq!(notes[0].content, "edited");
+        // The content before the outside edit is kept as a revision
+        assert_eq!(notes[0].revisions.iter().map(|r| r.content.as_str()).collect::<Vec<_>>(), ["# Review\n", "edited"]);
+        assert_eq!(notes[2].id, 3);
+        assert_eq!(notes[2].description, "ideas");
+        assert_eq!(notes[2].content, "- more tests");
+    }
+
+    #[test]
+    fn test_missing_files_are_written_back() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+        let file = notes_dir(base_dir).join("growth-team-1/sprint-review-2.md");
+
+        fs::remove_file(&file).unwrap();
+        let state = load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].notes[1].content, "again");
+        save_state(base_dir, &state).unwrap();
+        assert!(fs::read_to_string(&file).unwrap().ends_with("again"));
+    }
+
+    #[test]
+    fn test_projects_with_the_same_slug_get_their_own_directory() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+
+        create_project(base_dir, "growth-team", true).unwrap();
+        crate::storage::set_current_project(base_dir, "growth-team").unwrap();
+        create_note(base_dir, None, "Sprint review", "other").unwrap();
+        assert!(notes_dir(base_dir).join("growth-team-2/sprint-review.md").exists());
+
+        let state = load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].notes.len(), 2);
+        assert_eq!(state.projects[1].notes[0].content, "other");
+    }
+
+    #[test]
+    fn test_deleting_a_note_removes_its_file() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+
+        delete_note(base_dir, None, 1).unwrap();
+        assert!(!notes_dir(base_dir).join("growth-team-1/sprint-review.md").exists());
+        assert!(!notes_dir(base_dir).join("growth-team-1/.revisions/sprint-review.toml").exists());
+        assert!(notes_dir(base_dir).join("growth-team-1/sprint-review-2.md").exists());
+    }
+
+    #[test]
+    fn test_files_outside_of_projects_are_kept() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+        fs::create_dir_all(notes_dir(base_dir).join("vault")).unwrap();
+        fs::write(notes_dir(base_dir).join("vault/keep.md"), "mine").unwrap();
+
+        create_note(base_dir, None, "Third", "").unwrap();
+        delete_note(base_dir, None, 3).unwrap();
+        assert_eq!(fs::read_to_string(notes_dir(base_dir).join("vault/keep.md")).unwrap(), "mine");
+        assert!(!notes_dir(base_dir).join("growth-team-1/third.md").exists());
+    }
+
+    #[test]
+    fn test_switching_to_files_keeps_inline_content() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "inline", true).unwrap();
+        create_note(base_dir, None, "Kept", "inline content").unwrap();
+        config::configure(base_dir, Some("note_storage"), Some("files")).unwrap();
+
+        let state = load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].notes[0].content, "inline content");
+
+        create_note(base_dir, None, "Second", "").unwrap();
+        let text = fs::read_to_string(notes_dir(base_dir).join("inline-1/kept.md")).unwrap();
+        assert!(text.ends_with("inline content"));
+    }
+
+    #[test]
+    fn test_switching_back_to_state_keeps_file_content() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        setup(base_dir);
+
+        config::configure(base_dir, Some("note_storage"), Some("state")).unwrap();
+        create_note(base_dir, None, "Third", "").unwrap();
+
+        let state_file = fs::read_to_string(base_dir.join("tedo_state.toml")).unwrap();
+        assert!(state_file.contains("# Review"));
+        assert!(!state_file.contains("sprint-review.md"));
+    }
+}
@@ -1,89 +1,274 @@
 use std::path::Path;
-use crate::{projects, storage};
+use chrono::NaiveDateTime;
+
+use crate::{config, editor, front_matter, projects, storage, tags};
+use crate::error::TedoError;
 use crate::storage::save_state;
-use crate::storage::Note;
-use std::fs::File;
-use std::io::Read;
-use std::io::Write;
+use crate::storage::{Note, Project};
+
 
-use std::process::Command;
+pub fn list_notes(base_dir: &Path, project: Option<&str>, mode: &str) -> Result<(), TedoError> {
 
+    let project = projects::scoped_project(base_dir, project)?;
 
-pub fn list_notes(base_dir: &Path, mode: &str) {
+    if mode == "table" {
+        println!("+ {:^10} + {:^40} +", "----------", "---------------------------------------");
+        println!("| {:^10} | {:^40} |", "ID", "Description");
+        println!("| {:^10} | {:^40} |", "----------", "---------------------------------------");
+        for note in &project.notes {
+            println!("| {:^10} | {:^40} |", note.id, format!("{} ({})", note.description, note.slug));
+        }
+        println!("+ {:^10} + {:^40} +", "----------", "---------------------------------------");
+        return Ok(());
+    }
+    for note in &project.notes {
+        println!("{} {} ({})", note.id, note.description, note.slug);
+    }
+    Ok(())
+}
 
-    let project = projects::current_project(&base_dir);
+// Opens the note in the editor with its title and tags as front-matter
+pub fn edit_note(base_dir: &Path, project: Option<&str>, id: u32) -> Result<(), TedoError> {
+    let keep = config::load_config(base_dir)?.revision_limit();
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+    let note = project.note(id)?;
 
-    if let Some(project) = project {
-        if mode == "table" {
-            println!("+ {:^10} + {:^40} +", "----------", "---------------------------------------");
-            println!("| {:^10} | {:^40} |", "ID", "Description");
-            println!("| {:^10} | {:^40} |", "----------", "---------------------------------------");
-            for note in &project.notes {
-                println!("| {:^10} | {:^40} |", note.id, note.description);
-            }
-            println!("+ {:^10} + {:^40} +", "----------", "---------------------------------------");
-            return;
+    let text = front_matter::with_front_matter(
+        &[("title", note.description.clone()), ("tags", front_matter::format_list(&note.tags))],
+        &note.content,
+    );
+    let edited = match editor::edit_text(base_dir, &text)? {
+        Some(edited) => edited,
+        None => {
+            println!("No changes.");
+            return Ok(());
         }
-        for note in &project.notes {
-            println!("{} {}", note.id, note.description);
+    };
+
+    let (fields, body) = front_matter::split_front_matter(&edited);
+    if let Some(title) = front_matter::field(&fields, "title").filter(|title| !title.is_empty() && *title != note.description) {
+        project.rename_note(id, title)?;
+    }
+    let note = project.note_mut(id)?;
+    if let Some(tags) = front_matter::field(&fields, "tags") {
+        note.tags.clear();
+        let tags = front_matter::parse_list(tags);
+        tags::update_tags(&mut note.tags, &tags.iter().map(String::as_str).collect::<Vec<&str>>(), false);
+    }
+    note.set_content(body, chrono::Local::now().naive_local(), keep);
+
+    save_state(base_dir, &tedo_state)
+}
+
+
+
+impl Note {
+    // The slug is left empty, see `Project::unique_note_slug`
+    pub fn new(id: u32, description: &str, content: &str) -> Note {
+        Note {
+            id,
+            slug: String::new(),
+            previous_slugs: Vec::new(),
+            description: description.into(),
+            content: content.into(),
+            tags: Vec::new(),
+            file: None,
+            revisions: Vec::new(),
+            created: None,
+            modified: None,
+            history: Vec::new(),
         }
-    } else {
-        println!("No selected project. Please switch to a project before listing notes.");
+    }
+
+    // Appends text on a line of its own
+    pub fn append(&mut self, text: &str, timestamp: NaiveDateTime, keep: usize) {
+        let mut content = self.content.clone();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(text);
+        if !text.ends_with('\n') {
+            content.push('\n');
+        }
+        self.set_content(content, timestamp, keep);
     }
 }
 
-pub fn edit_note(base_dir: &Path, id: u32) {
-    let mut tedo_state = storage::load_state(base_dir).unwrap_or_default();
 
+impl Project {
+    // Adds a note with a slug for its title and its content as the first
+    // revision. Returns the id of the new note.
+    pub fn add_note(&mut self, description: &str, content: &str, tags: &[&str], timestamp: NaiveDateTime, keep: usize) -> u32 {
+        let id = self.allocate_note_id();
+        let mut note = Note::new(id, description, content);
+        note.slug = self.unique_note_slug(description, id);
+        tags::update_tags(&mut note.tags, tags, false);
+        note.record_revision(timestamp, keep);
+        self.notes.push(note);
+        id
+    }
 
-    let current_project_name = tedo_state.current_project.clone().unwrap_or_default();
-    let project = tedo_state.projects.iter_mut().find(|p| p.name == current_project_name);
+    // A slug for the title that no other note in the project uses or used
+    pub fn unique_note_slug(&self, title: &str, note_id: u32) -> String {
+        let base = slugify(title);
+        let base = if base.is_empty() { format!("note-{}", note_id) } else { base };
+        let taken = |slug: &str| self.notes.iter()
+            .filter(|n| n.id != note_id)
+            .any(|n| n.slug == slug || n.previous_slugs.iter().any(|s| s == slug));
 
-    if let Some(project) = project {
-        if let Some(note) = project.notes.iter_mut().find(|n| n.id == id) {
-            // Create a temporary file and write the current content of the note to it
-            let mut temp_file = tempfile::NamedTempFile::new().expect("Failed to create temporary file");
-            write!(temp_file, "{}", note.content).expect("Failed to write to temporary file");
+        let mut slug = base.clone();
+        let mut counter = 2;
+        while taken(&slug) {
+            slug = format!("{}-{}", base, counter);
+            counter += 1;
+        }
+        slug
+    }
 
-            // Launch the VI editor to edit the file
-            Command::new("nvim")
-                .arg(temp_file.path())
-                .status()
-                .expect("Failed to launch editor");
+    // Notes created before slugs existed get one the first time they are loaded
+    pub fn ensure_note_slugs(&mut self) {
+        for index in 0..self.notes.len() {
+            if self.notes[index].slug.is_empty() {
+                let slug = self.unique_note_slug(&self.notes[index].description, self.notes[index].id);
+                self.notes[index].slug = slug;
+            }
+        }
+    }
 
-            // Read the edited content back from the temporary file
-            let mut new_content = String::new();
-            let mut file = File::open(temp_file.path()).expect("Failed to open temporary file");
-            file.read_to_string(&mut new_content).expect("Failed to read from temporary file");
+    // Gives the note a slug for its new title, keeping the old one as a redirect
+    pub fn rename_note(&mut self, id: u32, title: &str) -> Result<(), TedoError> {
+        let slug = self.unique_note_slug(title, id);
+        let note = self.note_mut(id)?;
+        note.description = title.to_string();
+        if note.slug != slug {
+            let old_slug = std::mem::replace(&mut note.slug, slug);
+            if !old_slug.is_empty() && !note.previous_slugs.contains(&old_slug) {
+                note.previous_slugs.push(old_slug);
+            }
+            note.previous_slugs.retain(|s| *s != note.slug);
+        }
+        Ok(())
+    }
+
+    // Resolves a note by id, slug, former slug, slug prefix, or fuzzily by
+    // the characters of the identifier appearing in order in the slug.
+    pub fn note_index(&self, identifier: &str) -> Result<usize, TedoError> {
+        if let Ok(id) = identifier.parse::<u32>() {
+            return self.notes.iter().position(|n| n.id == id)
+                .ok_or_else(|| TedoError::NotFound(format!("Note with id {} not found.", id)));
+        }
 
-            // Update the content of the note
-            note.content = new_content;
+        let query = identifier.to_lowercase();
+        let stages: [&dyn Fn(&Note) -> bool; 5] = [
+            &|n| n.slug == query,
+            &|n| n.previous_slugs.contains(&query),
+            &|n| n.slug.starts_with(&query),
+            &|n| n.slug.contains(&query) || n.description.to_lowercase().contains(&query),
+            &|n| is_subsequence(&query, &n.slug),
+        ];
 
-            // Save the state
-            save_state(base_dir, &tedo_state).expect("Failed to save note");
-        } else {
-            println!("Note with id {} not found.", id);
+        for matches_note in stages {
+            let matches: Vec<usize> = self.notes.iter().enumerate()
+                .filter(|(_, n)| matches_note(n))
+                .map(|(index, _)| index)
+                .collect();
+            match matches.len() {
+                0 => continue,
+                1 => return Ok(matches[0]),
+                _ => return Err(TedoError::Ambiguous {
+                    identifier: identifier.to_string(),
+                    candidates: matches.iter().map(|&index| self.notes[index].slug.clone()).collect(),
+                }),
+            }
         }
-    } else {
-        println!("No selected project. Please switch to a project before editing a note.");
+        Err(TedoError::NotFound(format!("Note {} not found.", identifier)))
     }
 }
 
 
+fn is_subsequence(query: &str, text: &str) -> bool {
+    let mut text = text.chars();
+    query.chars().filter(|c| *c != '-').all(|c| text.any(|t| t == c))
+}
+
 
-pub fn create_note(base_dir: &Path, description: &str, content: &str) {
-    let mut tedo_state = storage::load_state(base_dir).unwrap_or_default();
+pub fn resolve_note_id(base_dir: &Path, project: Option<&str>, identifier: &str) -> Result<u32, TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project = &tedo_state.projects[tedo_state.scoped_project_index(project)?];
+    Ok(project.notes[project.note_index(identifier)?].id)
+}
 
-    let current_project_name = tedo_state.current_project.clone().unwrap_or_default();
-    let project = tedo_state.projects.iter_mut().find(|p| p.name == current_project_name);
 
-    if let Some(project) = project {
-        let next_id = project.notes.len() as u32 + 1;
-        project.notes.push(Note { id: next_id, description: description.into(), content: content.into() });
-        save_state(base_dir, &tedo_state).expect("Failed to save note");
-    } else {
-        println!("No selected project. Please switch to a project before creating a note.");
+// Lowercase ASCII words joined by dashes, e.g. "Sprint review #3" becomes
+// "sprint-review-3"
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
     }
+    slug.trim_end_matches('-').to_string()
+}
+
+
+// Returns the id of the new note
+pub fn create_note(base_dir: &Path, project: Option<&str>, description: &str, content: &str) -> Result<u32, TedoError> {
+    let keep = config::load_config(base_dir)?.revision_limit();
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let id = tedo_state.scoped_project_mut(project)?.add_note(description, content, &[], chrono::Local::now().naive_local(), keep);
+    save_state(base_dir, &tedo_state)?;
+    Ok(id)
+}
+
+
+// Opens the new note in the editor with its title and tags as front-matter and
+// only creates it once the editor succeeds, so an aborted edit leaves nothing
+// behind. Returns the id of the new note.
+pub fn create_note_in_editor(base_dir: &Path, project: Option<&str>, description: &str, content: &str, tags: &[&str]) -> Result<u32, TedoError> {
+    let keep = config::load_config(base_dir)?.revision_limit();
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+    let text = front_matter::with_front_matter(
+        &[("title", description.to_string()), ("tags", front_matter::format_list(&tags))],
+        content,
+    );
+    let edited = editor::edit_text(base_dir, &text)?.unwrap_or(text);
+
+    let (fields, body) = front_matter::split_front_matter(&edited);
+    let description = front_matter::field(&fields, "title").filter(|title| !title.is_empty()).unwrap_or(description);
+    let tags = front_matter::field(&fields, "tags").map(front_matter::parse_list).unwrap_or(tags);
+    let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+    let id = project.add_note(description, &body, &tags, chrono::Local::now().naive_local(), keep);
+    save_state(base_dir, &tedo_state)?;
+    Ok(id)
+}
+
+
+// Appends text to the end of a note on a line of its own
+pub fn append_note(base_dir: &Path, project: Option<&str>, id: u32, text: &str) -> Result<(), TedoError> {
+    let keep = config::load_config(base_dir)?.revision_limit();
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let note = tedo_state.scoped_project_mut(project)?.note_mut(id)?;
+    note.append(text, chrono::Local::now().naive_local(), keep);
+    save_state(base_dir, &tedo_state)
+}
+
+
+pub fn delete_note(base_dir: &Path, project: Option<&str>, id: u32) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    project.note(id)?;
+    // The id stays taken
+    project.next_note_id = project.note_id_bound();
+    project.notes.retain(|n| n.id != id);
+    save_state(base_dir, &tedo_state)
 }
 
 
@@ -101,10 +286,10 @@ mod tests {
         let dir = tempdir().unwrap();
         let base_dir = dir.path();
 
-        create_project(base_dir, "test_project", false);
-        switch_project(base_dir, "test_project");
+        create_project(base_dir, "test_project", false).unwrap();
+        switch_project(base_dir, "test_project").unwrap();
 
-        create_note(base_dir, "test_note", "test_content");
+        create_note(base_dir, None, "test_note", "test_content").unwrap();
         let state = storage::load_state(base_dir).unwrap();
 
         assert_eq!(state.projects.len(), 1);
@@ -114,4 +299,139 @@ mod tests {
         assert_eq!(state.projects[0].notes[0].description, "test_note");
         assert_eq!(state.projects[0].notes[0].content, "test_content");
     }
+
+    #[test]
+    fn test_create_note_without_selected_project() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", false).unwrap();
+
+        assert!(matches!(create_note(base_dir, None, "test_note", ""), Err(TedoError::NoContext)));
+    }
+
+    #[test]
+    fn test_create_and_delete_note_in_scoped_project() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_project(base_dir, "other_project", false).unwrap();
+
+        create_note(base_dir, Some("other_project"), "test_note", "").unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].notes.len(), 0);
+        assert_eq!(state.projects[1].notes.len(), 1);
+
+        assert!(matches!(delete_note(base_dir, None, 1), Err(TedoError::NotFound(_))));
+        delete_note(base_dir, Some("other_project"), 1).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[1].notes.len(), 0);
+    }
+
+    #[test]
+    fn test_edit_note_with_front_matter() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_note(base_dir, None, "old title", "old body").unwrap();
+
+        let script = "sed -i -e s/old/new/g -e s/tags:.*/tags:\\x20[one,\\x20Two]/";
+        crate::config::configure(base_dir, Some("editor"), Some(script)).unwrap();
+        edit_note(base_dir, None, 1).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        let note = &state.projects[0].notes[0];
+        assert_eq!(note.description, "new title");
+        assert_eq!(note.content, "new body");
+        assert_eq!(note.tags, vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_create_note_in_editor() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+        create_project(base_dir, "test_project", true).unwrap();
+
+        // Nothing is created when the editor fails
+        crate::config::configure(base_dir, Some("editor"), Some("false")).unwrap();
+        assert!(matches!(create_note_in_editor(base_dir, None, "old title", "", &["draft"]), Err(TedoError::Aborted(_))));
+        assert!(storage::load_state(base_dir).unwrap().projects[0].notes.is_empty());
+
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i -e s/old/new/g -e s/draft/final/")).unwrap();
+        create_note_in_editor(base_dir, None, "old title", "old body", &["draft"]).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        let note = &state.projects[0].notes[0];
+        assert_eq!((note.id, note.description.as_str(), note.content.as_str()), (1, "new title", "new body"));
+        assert_eq!(note.tags, vec!["final"]);
+    }
+
+    #[test]
+    fn test_append_note() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_note(base_dir, None, "journal", "first line").unwrap();
+        create_note(base_dir, None, "empty", "").unwrap();
+
+        append_note(base_dir, None, 1, "second line").unwrap();
+        append_note(base_dir, None, 1, "third line\n").unwrap();
+        append_note(base_dir, None, 2, "only line").unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].notes[0].content, "first line\nsecond line\nthird line\n");
+        assert_eq!(state.projects[0].notes[1].content, "only line\n");
+        assert!(matches!(append_note(base_dir, None, 3, "x"), Err(TedoError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Sprint review #3"), "sprint-review-3");
+        assert_eq!(slugify("  Über--cool!  "), "ber-cool");
+        assert_eq!(slugify("???"), "");
+    }
+
+    #[test]
+    fn test_note_slugs_are_unique_and_survive_renames() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_note(base_dir, None, "Sprint review", "").unwrap();
+        create_note(base_dir, None, "Sprint Review!", "").unwrap();
+        create_note(base_dir, None, "???", "").unwrap();
+
+        let mut state = storage::load_state(base_dir).unwrap();
+        let project = &mut state.projects[0];
+        assert_eq!(project.notes[0].slug, "sprint-review");
+        assert_eq!(project.notes[1].slug, "sprint-review-2");
+        assert_eq!(project.notes[2].slug, "note-3");
+
+        project.rename_note(1, "Retrospective").unwrap();
+        assert_eq!(project.notes[0].slug, "retrospective");
+        assert_eq!(project.notes[0].previous_slugs, vec!["sprint-review"]);
+        assert_eq!(project.note_index("sprint-review").unwrap(), 0);
+        assert_eq!(project.unique_note_slug("Sprint review", 4), "sprint-review-3");
+    }
+
+    #[test]
+    fn test_resolve_note_identifiers() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_note(base_dir, None, "Sprint review", "").unwrap();
+        create_note(base_dir, None, "Sprint planning", "").unwrap();
+        create_note(base_dir, None, "Incident report", "").unwrap();
+
+        assert_eq!(resolve_note_id(base_dir, None, "2").unwrap(), 2);
+        assert_eq!(resolve_note_id(base_dir, None, "sprint-review").unwrap(), 1);
+        assert_eq!(resolve_note_id(base_dir, None, "inc").unwrap(), 3);
+        assert_eq!(resolve_note_id(base_dir, None, "planning").unwrap(), 2);
+        assert_eq!(resolve_note_id(base_dir, None, "srev").unwrap(), 1);
+        assert!(matches!(resolve_note_id(base_dir, None, "sprint"), Err(TedoError::Ambiguous { .. })));
+        assert!(matches!(resolve_note_id(base_dir, None, "zzz"), Err(TedoError::NotFound(_))));
+    }
 }
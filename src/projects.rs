@@ -1,90 +1,426 @@
 use std::path::Path;
 
-use crate::storage;
-use crate::storage::{Project, TedoState};
+use colored::{Color, ColoredString, Colorize};
+
+use crate::error::TedoError;
+use crate::{dates, editor, front_matter, storage};
+use crate::storage::{Project, ProjectStatus, TedoState};
 use crate::storage::save_state;
 
 impl Project {
-    pub fn find(base_dir: &Path, identifier: &str) -> Option<Project> {
-        let state = storage::load_state(base_dir).unwrap_or_default();
-        if identifier.parse::<u32>().is_ok() {
-            return Project::find_by_id(state, identifier.parse::<u32>().unwrap());
-        } else {
-            return Project::find_by_name(state, identifier.to_string());
+    pub fn new(id: u32, name: &str) -> Project {
+        Project {
+            id,
+            name: name.into(),
+            tasks: Vec::new(),
+            notes: Vec::new(),
+            description: String::new(),
+            status: ProjectStatus::Active,
+            start: None,
+            target: None,
+            color: None,
+            owner: None,
+            created: None,
+            modified: None,
+            history: Vec::new(),
+            next_task_id: 0,
+            next_note_id: 0,
         }
     }
 
+    // The lowest task id that was never handed out
+    pub fn task_id_bound(&self) -> u32 {
+        self.tasks.iter().map(|t| t.id + 1).max().unwrap_or(1).max(self.next_task_id)
+    }
+
+    pub fn note_id_bound(&self) -> u32 {
+        self.notes.iter().map(|n| n.id + 1).max().unwrap_or(1).max(self.next_note_id)
+    }
+
+    pub fn allocate_task_id(&mut self) -> u32 {
+        let id = self.task_id_bound();
+        self.next_task_id = id + 1;
+        id
+    }
+
+    pub fn allocate_note_id(&mut self) -> u32 {
+        let id = self.note_id_bound();
+        self.next_note_id = id + 1;
+        id
+    }
+
+    pub fn find(base_dir: &Path, identifier: &str) -> Result<Project, TedoError> {
+        let mut state = storage::load_state(base_dir)?;
+        let index = state.project_index(identifier)?;
+        Ok(state.projects.swap_remove(index))
+    }
+
+    // The last part of a nested name, `auth` for `work/platform/auth`
+    pub fn short_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+
+    pub fn parent_name(&self) -> Option<&str> {
+        self.name.rsplit_once('/').map(|(parent, _)| parent)
+    }
 
-    pub fn find_by_id(state: TedoState, id: u32) -> Option<Project> {
-        return state.projects.into_iter().find(|p| p.id == id);
+    pub fn depth(&self) -> usize {
+        self.name.matches('/').count()
     }
 
-    pub fn find_by_name(state: TedoState, name_start: String) -> Option<Project> {
-        return state.projects.into_iter().find(|p| p.name.starts_with(&name_start));
+    // Whether this is the project `name` or one nested below it
+    pub fn is_within(&self, name: &str) -> bool {
+        is_within(&self.name, name)
     }
 
+    pub fn is_archived(&self) -> bool {
+        self.status == ProjectStatus::Archived
+    }
+
+    // The name in the project's color, if it has one
+    pub fn colored_name(&self) -> ColoredString {
+        self.colored(&self.name)
+    }
+
+    pub fn colored(&self, text: &str) -> ColoredString {
+        match self.color.as_deref().and_then(|color| color.parse::<Color>().ok()) {
+            Some(color) => text.color(color),
+            None => text.normal(),
+        }
+    }
 }
 
 
-pub fn current_project(base_dir: &Path) -> Option<Project> {
-    let tedo_state = storage::load_state(base_dir).unwrap_or_default();
-    let current_project_name = tedo_state.current_project.unwrap_or_default();
-    return Project::find(base_dir, &current_project_name);
+impl ProjectStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProjectStatus::Active => "active",
+            ProjectStatus::Paused => "paused",
+            ProjectStatus::Archived => "archived",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<ProjectStatus, TedoError> {
+        match text.trim() {
+            "active" | "" => Ok(ProjectStatus::Active),
+            "paused" => Ok(ProjectStatus::Paused),
+            "archived" => Ok(ProjectStatus::Archived),
+            status => Err(TedoError::InvalidInput(format!("Unknown project status {}, use active, paused or archived", status))),
+        }
+    }
 }
 
 
-pub fn create_project(base_dir: &Path, name: &str, switch: bool) {
-    let mut tedo_state = storage::load_state(base_dir).unwrap_or_default();
+impl TedoState {
+    // Resolves a project by id, exact name, shorthand (as shown by `tedo list`)
+    // or unique name prefix, in that order.
+    pub fn project_index(&self, identifier: &str) -> Result<usize, TedoError> {
+        if let Ok(id) = identifier.parse::<u32>() {
+            return self.projects.iter().position(|p| p.id == id)
+                .ok_or_else(|| TedoError::NotFound(format!("Project with id {} does not exist", id)));
+        }
+
+        if let Some(index) = self.projects.iter().position(|p| p.name == identifier) {
+            return Ok(index);
+        }
+
+        // `work.platform.auth` for `work/platform/auth`
+        let path = identifier.replace('.', "/");
+        if let Some(index) = self.projects.iter().position(|p| p.name == path) {
+            return Ok(index);
+        }
+
+        if let Some(index) = shorthands(&self.projects).iter().position(|s| s == identifier) {
+            return Ok(index);
+        }
+
+        // A prefix of a parent also matches its sub-projects, the parent wins
+        let matches: Vec<usize> = self.projects.iter().enumerate()
+            .filter(|(_, p)| p.name.starts_with(identifier))
+            .map(|(index, _)| index)
+            .collect();
+        let matches: Vec<usize> = matches.iter().copied()
+            .filter(|&index| !matches.iter().any(|&other| other != index && self.projects[index].is_within(&self.projects[other].name)))
+            .collect();
+
+        match matches.len() {
+            0 => Err(TedoError::NotFound(format!("Project {} does not exist", identifier))),
+            1 => Ok(matches[0]),
+            _ => Err(TedoError::Ambiguous {
+                identifier: identifier.to_string(),
+                candidates: matches.iter().map(|&index| self.projects[index].name.clone()).collect(),
+            }),
+        }
+    }
+
+    pub fn current_project_index(&self) -> Result<usize, TedoError> {
+        let name = self.current_project.as_ref().ok_or(TedoError::NoContext)?;
+        self.projects.iter().position(|p| &p.name == name)
+            .ok_or_else(|| TedoError::NotFound(format!("Current project {} does not exist", name)))
+    }
+
+    // The project a command acts on: the explicitly requested one, or the
+    // current project when no scope was given.
+    pub fn scoped_project_index(&self, project: Option<&str>) -> Result<usize, TedoError> {
+        match project {
+            Some(identifier) => self.project_index(identifier),
+            None => self.current_project_index(),
+        }
+    }
+
+    pub fn scoped_project_mut(&mut self, project: Option<&str>) -> Result<&mut Project, TedoError> {
+        let index = self.scoped_project_index(project)?;
+        Ok(&mut self.projects[index])
+    }
+
+    // Projects below an archived one count as archived too
+    pub fn is_archived_within(&self, index: usize) -> bool {
+        self.projects.iter().any(|p| p.is_archived() && self.projects[index].is_within(&p.name))
+    }
+
+    // The project at `index` followed by its sub-projects, in tree order
+    pub fn subtree(&self, index: usize) -> Vec<usize> {
+        let name = &self.projects[index].name;
+        tree_order(&self.projects).into_iter()
+            .filter(|&other| self.projects[other].is_within(name))
+            .collect()
+    }
+
+    // Commands that cover all projects keep only the given one and its
+    // sub-projects. Without a project all are kept.
+    pub fn narrow_to_subtree(&mut self, project: Option<&str>) -> Result<(), TedoError> {
+        if project.is_none() {
+            return Ok(());
+        }
+        let index = self.scoped_project_index(project)?;
+        let name = self.projects[index].name.clone();
+        self.projects.retain(|p| p.is_within(&name));
+        Ok(())
+    }
+}
+
+
+// The shortest prefix of each project name that was not already claimed by a
+// project earlier in the list. Falls back to the full name.
+pub fn shorthands(projects: &[Project]) -> Vec<String> {
+    let mut shorthands: Vec<String> = Vec::new();
+    for project in projects {
+        let mut shorthand = String::new();
+        let mut found = false;
+        for c in project.name.chars() {
+            shorthand.push(c);
+            if !shorthands.contains(&shorthand) {
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            shorthand = project.name.clone();
+        }
+        shorthands.push(shorthand);
+    }
+    shorthands
+}
+
+
+// Parents come before their sub-projects, otherwise the order is kept
+pub fn tree_order(projects: &[Project]) -> Vec<usize> {
+    fn visit(projects: &[Project], parent: Option<&str>, order: &mut Vec<usize>) {
+        for (index, project) in projects.iter().enumerate() {
+            if project.parent_name() == parent {
+                order.push(index);
+                visit(projects, Some(&project.name), order);
+            }
+        }
+    }
+
+    let mut order = Vec::new();
+    visit(projects, None, &mut order);
+    // Sub-projects whose parent is missing are listed at the end
+    for index in 0..projects.len() {
+        if !order.contains(&index) {
+            order.push(index);
+        }
+    }
+    order
+}
+
+
+fn is_within(path: &str, ancestor: &str) -> bool {
+    path.strip_prefix(ancestor).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+
+// Trims each part of a nested name and rejects empty ones
+fn project_path(name: &str) -> Result<String, TedoError> {
+    let parts: Vec<&str> = name.split('/').map(str::trim).collect();
+    if parts.iter().any(|part| part.is_empty()) {
+        return Err(TedoError::InvalidInput(format!("Invalid project name {}", name)));
+    }
+    Ok(parts.join("/"))
+}
+
+
+pub fn scoped_project(base_dir: &Path, project: Option<&str>) -> Result<Project, TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let index = tedo_state.scoped_project_index(project)?;
+    Ok(tedo_state.projects.swap_remove(index))
+}
+
+
+// A nested name like `work/platform/auth` creates the parents that do not
+// exist yet.
+pub fn create_project(base_dir: &Path, name: &str, switch: bool) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let name = project_path(name)?;
     if tedo_state.projects.iter().any(|p| p.name == name) {
-        println!("Project with name {} already exists", name);
-        return;
+        return Err(TedoError::InvalidInput(format!("Project with name {} already exists", name)));
     }
-    let project_id = tedo_state.projects.len() as u32 + 1;
-    tedo_state.projects.push(Project { id: project_id, name: name.into(), tasks: Vec::new(), notes: Vec::new() });
-    save_state(base_dir, &tedo_state).expect("Failed to save projects");
+    let mut parents: Vec<&str> = name.match_indices('/').map(|(end, _)| &name[..end]).collect();
+    parents.retain(|parent| !tedo_state.projects.iter().any(|p| p.name == *parent));
+    for path in parents.into_iter().chain([name.as_str()]) {
+        let project_id = tedo_state.projects.len() as u32 + 1;
+        tedo_state.projects.push(Project::new(project_id, path));
+        if path != name {
+            println!("Created parent project {}", path);
+        }
+    }
+    save_state(base_dir, &tedo_state)?;
 
     if switch {
-        switch_project(base_dir, name);
+        switch_project(base_dir, &name)?;
     }
+    Ok(())
 }
 
 
-pub fn switch_project(base_dir: &Path, name: &str) {
-    let tedo_state = storage::load_state(base_dir).unwrap_or_default();
+// Opens the project's details in the editor as front-matter, with the
+// description as the body. Renaming the current project keeps it selected.
+pub fn edit_project(base_dir: &Path, project: Option<&str>) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let index = tedo_state.scoped_project_index(project)?;
+    let project = &tedo_state.projects[index];
+    let old_name = project.name.clone();
+
+    let date = |date: Option<chrono::NaiveDate>| date.map(|date| date.to_string()).unwrap_or_default();
+    let text = front_matter::with_front_matter(&[
+        ("name", old_name.clone()),
+        ("status", project.status.label().to_string()),
+        ("owner", project.owner.clone().unwrap_or_default()),
+        ("start", date(project.start)),
+        ("target", date(project.target)),
+        ("color", project.color.clone().unwrap_or_default()),
+    ], &project.description);
+    let edited = match editor::edit_text(base_dir, &text)? {
+        Some(edited) => edited,
+        None => {
+            println!("No changes.");
+            return Ok(());
+        }
+    };
+
+    let (fields, description) = front_matter::split_front_matter(&edited);
+    // Empty fields are cleared
+    let text = |key: &str| front_matter::field(&fields, key).filter(|value| !value.is_empty()).map(String::from);
+    let today = dates::today();
+    let start = text("start").map(|start| dates::parse_due_date(&start, today)).transpose()?;
+    let target = text("target").map(|target| dates::parse_due_date(&target, today)).transpose()?;
+    let status = ProjectStatus::parse(front_matter::field(&fields, "status").unwrap_or_default())?;
+    let color = text("color");
+    if let Some(color) = color.as_deref().filter(|color| color.parse::<Color>().is_err()) {
+        return Err(TedoError::InvalidInput(format!("Unknown color {}, use e.g. red, green, blue or bright magenta", color)));
+    }
+
+    let name = text("name").map(|name| project_path(&name)).transpose()?;
+    if let Some(name) = name.filter(|name| *name != old_name) {
+        rename_subtree(&mut tedo_state, &old_name, &name)?;
+    }
+    let project = &mut tedo_state.projects[index];
+    project.description = description.trim().to_string();
+    project.status = status;
+    project.owner = text("owner");
+    project.start = start;
+    project.target = target;
+    project.color = color;
+    save_state(base_dir, &tedo_state)
+}
+
 
+// Renames a project along with its sub-projects. The new parent has to
+// exist, so a project can be moved under another one by renaming it.
+fn rename_subtree(tedo_state: &mut TedoState, old_name: &str, name: &str) -> Result<(), TedoError> {
     if tedo_state.projects.iter().any(|p| p.name == name) {
-        println!("Switching to project {}", name);
-        storage::set_current_project(base_dir, name);
-    } else {
-        println!("Project with name {} does not exist", name);
+        return Err(TedoError::InvalidInput(format!("Project with name {} already exists", name)));
+    }
+    if let Some((parent, _)) = name.rsplit_once('/') {
+        if is_within(parent, old_name) {
+            return Err(TedoError::InvalidInput(format!("Project {} can not be moved below itself", old_name)));
+        }
+        if !tedo_state.projects.iter().any(|p| p.name == parent) {
+            return Err(TedoError::NotFound(format!("Parent project {} does not exist", parent)));
+        }
     }
+
+    let rename = |path: &str| format!("{}{}", name, &path[old_name.len()..]);
+    if let Some(current) = tedo_state.current_project.as_mut() {
+        if is_within(current, old_name) {
+            *current = rename(current);
+        }
+    }
+    for project in tedo_state.projects.iter_mut().filter(|p| p.is_within(old_name)) {
+        project.name = rename(&project.name);
+    }
+    Ok(())
+}
+
+
+pub fn set_project_status(base_dir: &Path, project: Option<&str>, status: ProjectStatus) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+    project.status = status;
+    println!("Project {} is now {}", project.name, status.label());
+    save_state(base_dir, &tedo_state)
+}
+
+
+pub fn switch_project(base_dir: &Path, identifier: &str) -> Result<(), TedoError> {
+    let project = Project::find(base_dir, identifier)?;
+    println!("Switching to project {}", project.name);
+    storage::set_current_project(base_dir, &project.name)
 }
 
 
-pub fn list_projects(base_dir: &Path, mode: &str) {
-    let projects = storage::load_state(base_dir).unwrap_or_default();
+// Archived projects are only listed with `archived`
+pub fn list_projects(base_dir: &Path, mode: &str, archived: bool) -> Result<(), TedoError> {
+    let projects = storage::load_state(base_dir)?;
 
-    let current_project = current_project(base_dir);
+    let current_project = projects.current_project.clone();
+    let listed: Vec<&Project> = tree_order(&projects.projects).into_iter()
+        .filter(|&index| archived || !projects.is_archived_within(index))
+        .map(|index| &projects.projects[index])
+        .collect();
 
     if mode == "table" {
         println!("+ {:^21} + {:^20}  + {:^20} +", "------------------", "----------", "----------");
         println!("| {:^21} + {:^20}  + {:^20} |", "Projects", "Tasks", "Notes");
         println!("+ {:^21} + {:^20}  + {:^20} +", "------------------", "----------", "----------");
-        for project in projects.projects {
-            if let Some(current_project) = &current_project {
-                if project.name == current_project.name {
-                    println!("| {:^21} + {:^20}  + {:^20} |", format!("{} (current)", project.name), project.tasks.len(), project.notes.len());
-                } else {
-                    println!("| {:^21} + {:^20}  + {:^20} |", project.name, project.tasks.len(), project.notes.len());
-                }
+        for project in listed {
+            if current_project.as_ref() == Some(&project.name) {
+                println!("| {:^21} + {:^20}  + {:^20} |", format!("{} (current)", project.name), project.tasks.len(), project.notes.len());
+            } else {
+                println!("| {:^21} + {:^20}  + {:^20} |", project.name, project.tasks.len(), project.notes.len());
             }
         }
         println!("+ {:^21} + {:^20}  + {:^20} +", "------------------", "----------", "----------");
-        return;
+        return Ok(());
     }
-    for project in projects.projects {
-        println!("({}) {}", project.id, project.name);
+    for project in listed {
+        match project.status {
+            ProjectStatus::Active => println!("({}) {}", project.id, project.colored_name()),
+            status => println!("({}) {} ({})", project.id, project.colored_name(), status.label()),
+        }
     }
+    Ok(())
 }
 
 
@@ -99,7 +435,7 @@ mod tests {
         let dir = tempdir().unwrap();
         let base_dir = dir.path();
 
-        create_project(base_dir, "test_project", false);
+        create_project(base_dir, "test_project", false).unwrap();
         let projects = storage::load_state(base_dir).unwrap();
         assert_eq!(projects.projects.len(), 1);
         assert_eq!(projects.projects[0].name, "test_project");
@@ -110,8 +446,8 @@ mod tests {
         let dir = tempdir().unwrap();
         let base_dir = dir.path();
 
-        create_project(base_dir, "test_project", false);
-        create_project(base_dir, "test_project", false);
+        create_project(base_dir, "test_project", false).unwrap();
+        assert!(create_project(base_dir, "test_project", false).is_err());
         let projects = storage::load_state(base_dir).unwrap();
         assert_eq!(projects.projects.len(), 1);
         assert_eq!(projects.projects[0].name, "test_project");
@@ -122,7 +458,7 @@ mod tests {
         let dir = tempdir().unwrap();
         let base_dir = dir.path();
 
-        create_project(base_dir, "test_project", true);
+        create_project(base_dir, "test_project", true).unwrap();
         let projects = storage::load_state(base_dir).unwrap();
         assert_eq!(projects.projects.len(), 1);
         assert_eq!(projects.projects[0].name, "test_project");
@@ -134,14 +470,179 @@ mod tests {
         let dir = tempdir().unwrap();
         let base_dir = dir.path();
 
-        create_project(base_dir, "test_project_1", false);
-        create_project(base_dir, "test_project_2", false);
-        create_project(base_dir, "test_project_3", false);
+        create_project(base_dir, "test_project_1", false).unwrap();
+        create_project(base_dir, "test_project_2", false).unwrap();
+        create_project(base_dir, "test_project_3", false).unwrap();
         let projects = storage::load_state(base_dir).unwrap();
         assert_eq!(projects.projects.len(), 3);
         assert_eq!(projects.projects[0].name, "test_project_1");
         assert_eq!(projects.projects[1].name, "test_project_2");
         assert_eq!(projects.projects[2].name, "test_project_3");
     }
-}
 
+    #[test]
+    fn test_find_project_by_id_name_and_shorthand() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "general", false).unwrap();
+        create_project(base_dir, "foo", false).unwrap();
+        create_project(base_dir, "growth", false).unwrap();
+
+        assert_eq!(Project::find(base_dir, "3").unwrap().name, "growth");
+        assert_eq!(Project::find(base_dir, "foo").unwrap().name, "foo");
+        assert_eq!(Project::find(base_dir, "g").unwrap().name, "general");
+        assert_eq!(Project::find(base_dir, "gr").unwrap().name, "growth");
+        assert_eq!(Project::find(base_dir, "gen").unwrap().name, "general");
+    }
+
+    #[test]
+    fn test_find_project_errors() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "alpha_one", false).unwrap();
+        create_project(base_dir, "alpha_two", false).unwrap();
+
+        assert!(matches!(Project::find(base_dir, "beta"), Err(TedoError::NotFound(_))));
+        assert!(matches!(Project::find(base_dir, "9"), Err(TedoError::NotFound(_))));
+        assert!(matches!(Project::find(base_dir, "alpha_"), Err(TedoError::Ambiguous { .. })));
+    }
+
+    #[test]
+    fn test_current_project_without_selection() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", false).unwrap();
+        assert!(matches!(scoped_project(base_dir, None), Err(TedoError::NoContext)));
+    }
+
+    #[test]
+    fn test_scoped_project_overrides_current_project() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "first", true).unwrap();
+        create_project(base_dir, "second", false).unwrap();
+
+        assert_eq!(scoped_project(base_dir, None).unwrap().name, "first");
+        assert_eq!(scoped_project(base_dir, Some("sec")).unwrap().name, "second");
+        assert_eq!(storage::load_state(base_dir).unwrap().current_project.unwrap(), "first");
+    }
+
+    #[test]
+    fn test_rename_current_project() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "old_name", true).unwrap();
+        create_project(base_dir, "taken", false).unwrap();
+
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i s/old_name/taken/")).unwrap();
+        assert!(matches!(edit_project(base_dir, None), Err(TedoError::InvalidInput(_))));
+
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i s/old_name/new_name/")).unwrap();
+        edit_project(base_dir, None).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].name, "new_name");
+        assert_eq!(state.current_project.unwrap(), "new_name");
+    }
+
+    #[test]
+    fn test_edit_project_details() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "growth", true).unwrap();
+        let script = dir.path().join("edit.sh");
+        std::fs::write(&script, r#"sed -i -e 's/^status: active/status: paused/' -e 's/^owner: /owner: kim/' -e 's/^target: /target: 2026-12-31/' -e 's/^color: /color: green/' "$1"
+echo 'Grow the user base' >> "$1"
+"#).unwrap();
+        crate::config::configure(base_dir, Some("editor"), Some(&format!("sh {}", script.display()))).unwrap();
+        edit_project(base_dir, None).unwrap();
+
+        let project = &storage::load_state(base_dir).unwrap().projects[0];
+        assert_eq!(project.status, ProjectStatus::Paused);
+        assert_eq!(project.owner.as_deref(), Some("kim"));
+        assert_eq!(project.target, chrono::NaiveDate::from_ymd_opt(2026, 12, 31));
+        assert_eq!(project.start, None);
+        assert_eq!(project.color.as_deref(), Some("green"));
+        assert_eq!(project.description, "Grow the user base");
+
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i s/^color:.*/color:plaid/")).unwrap();
+        assert!(matches!(edit_project(base_dir, None), Err(TedoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_archive_project() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "old", false).unwrap();
+        create_project(base_dir, "new", false).unwrap();
+        set_project_status(base_dir, Some("old"), ProjectStatus::Archived).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        assert!(state.projects[0].is_archived());
+        assert!(!state.projects[1].is_archived());
+        // Archived projects can still be found
+        assert_eq!(Project::find(base_dir, "old").unwrap().id, 1);
+
+        set_project_status(base_dir, Some("old"), ProjectStatus::Active).unwrap();
+        assert!(!storage::load_state(base_dir).unwrap().projects[0].is_archived());
+    }
+
+    #[test]
+    fn test_nested_projects() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "general", false).unwrap();
+        create_project(base_dir, " work / platform/auth", true).unwrap();
+        create_project(base_dir, "work/platform/api", false).unwrap();
+        assert!(matches!(create_project(base_dir, "work//docs", false), Err(TedoError::InvalidInput(_))));
+
+        let state = storage::load_state(base_dir).unwrap();
+        let names: Vec<&str> = state.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["general", "work", "work/platform", "work/platform/auth", "work/platform/api"]);
+        assert_eq!(state.current_project.as_deref(), Some("work/platform/auth"));
+        assert_eq!(state.projects[3].short_name(), "auth");
+        assert_eq!(state.projects[3].parent_name(), Some("work/platform"));
+        assert_eq!(state.subtree(2), vec![2, 3, 4]);
+
+        assert_eq!(Project::find(base_dir, "work/platform/api").unwrap().id, 5);
+        assert_eq!(Project::find(base_dir, "work.platform.auth").unwrap().id, 4);
+        // A prefix matching a parent and its sub-projects picks the parent
+        assert_eq!(Project::find(base_dir, "work/pl").unwrap().id, 3);
+
+        let mut state = storage::load_state(base_dir).unwrap();
+        state.narrow_to_subtree(Some("work.platform")).unwrap();
+        assert_eq!(state.projects.len(), 3);
+    }
+
+    #[test]
+    fn test_rename_nested_project() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "work/platform/auth", true).unwrap();
+        create_project(base_dir, "home", false).unwrap();
+
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i s|^name:.*|name:work/platform/auth/deep|")).unwrap();
+        assert!(matches!(edit_project(base_dir, Some("work/platform")), Err(TedoError::InvalidInput(_))));
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i s|^name:.*|name:garden/platform|")).unwrap();
+        assert!(matches!(edit_project(base_dir, Some("work/platform")), Err(TedoError::NotFound(_))));
+
+        // Moving a project takes its sub-projects along
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i s|^name:.*|name:home/platform|")).unwrap();
+        edit_project(base_dir, Some("work/platform")).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        let names: Vec<&str> = state.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["work", "home/platform", "home/platform/auth", "home"]);
+        assert_eq!(state.current_project.as_deref(), Some("home/platform/auth"));
+        assert_eq!(tree_order(&state.projects), vec![0, 3, 1, 2]);
+    }
+}
@@ -0,0 +1,539 @@
+use std::fmt;
+use std::path::Path;
+
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+use prettytable::row;
+
+use crate::error::TedoError;
+use crate::storage;
+use crate::storage::{save_state, Frequency, Project, Recurrence, Task};
+
+
+// A recurring task is a template holding the rule, description and tags, plus
+// one open instance at a time. The template's due date is the first
+// occurrence, which the rule's intervals count from. Closing an instance
+// generates the next one; deleting the template ends the recurrence.
+
+// Longer intervals would run past the dates chrono can represent
+const MAX_INTERVAL: u32 = 1000;
+
+const WEEKDAYS: [Weekday; 5] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+
+const RRULE_DAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon), ("TU", Weekday::Tue), ("WE", Weekday::Wed), ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri), ("SA", Weekday::Sat), ("SU", Weekday::Sun),
+];
+
+
+impl Recurrence {
+    fn new(frequency: Frequency, interval: u32) -> Recurrence {
+        Recurrence { frequency, interval, weekdays: Vec::new(), month_day: None }
+    }
+
+    // Accepts `daily`, `weekdays`, `weekly`, `monthly`, `yearly`, `every 2 weeks`,
+    // `every monday and thursday`, `weekly on fri`, `monthly on the 1st`, or an
+    // RRULE with FREQ, INTERVAL, BYDAY and BYMONTHDAY.
+    pub fn parse(text: &str) -> Result<Recurrence, TedoError> {
+        let text = text.trim().to_lowercase();
+        if let Some(rule) = text.strip_prefix("rrule:") {
+            return parse_rrule(rule);
+        }
+        if text.starts_with("freq=") {
+            return parse_rrule(&text);
+        }
+
+        let invalid = || TedoError::InvalidInput(format!(
+            "Invalid recurrence {}, use e.g. daily, weekdays, every 2 weeks, monthly on the 1st or an RRULE", text
+        ));
+        let (main, on) = match text.split_once(" on ") {
+            Some((main, on)) => (main, Some(on)),
+            None => (text.as_str(), None),
+        };
+        let words: Vec<&str> = main.split_whitespace().collect();
+        let words = words.strip_prefix(&["every"]).unwrap_or(&words);
+
+        let mut rule = match words {
+            ["daily"] | ["day"] => Recurrence::new(Frequency::Daily, 1),
+            ["weekly"] | ["week"] => Recurrence::new(Frequency::Weekly, 1),
+            ["monthly"] | ["month"] => Recurrence::new(Frequency::Monthly, 1),
+            ["yearly"] | ["annually"] | ["year"] => Recurrence::new(Frequency::Yearly, 1),
+            ["weekdays"] | ["weekday"] => Recurrence { weekdays: WEEKDAYS.to_vec(), ..Recurrence::new(Frequency::Weekly, 1) },
+            [interval, unit] => {
+                let interval = parse_interval(interval).ok_or_else(invalid)?;
+                let frequency = match unit.trim_end_matches('s') {
+                    "day" => Frequency::Daily,
+                    "week" => Frequency::Weekly,
+                    "month" => Frequency::Monthly,
+                    "year" => Frequency::Yearly,
+                    _ => return Err(invalid()),
+                };
+                Recurrence::new(frequency, interval)
+            }
+            _ => Recurrence { weekdays: parse_weekdays(main.trim_start_matches("every")).ok_or_else(invalid)?, ..Recurrence::new(Frequency::Weekly, 1) },
+        };
+
+        match (on, rule.frequency) {
+            (None, _) => {}
+            (Some(on), Frequency::Weekly) => rule.weekdays = parse_weekdays(on).ok_or_else(invalid)?,
+            (Some(on), Frequency::Monthly) => rule.month_day = Some(parse_month_day(on).ok_or_else(invalid)?),
+            (Some(_), _) => return Err(invalid()),
+        }
+        Ok(rule)
+    }
+
+    pub fn to_rrule(&self) -> String {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        let mut rule = format!("FREQ={}", frequency);
+        if self.interval > 1 {
+            rule.push_str(&format!(";INTERVAL={}", self.interval));
+        }
+        if !self.weekdays.is_empty() {
+            let days: Vec<&str> = self.weekdays.iter()
+                .filter_map(|day| RRULE_DAYS.iter().find(|(_, d)| d == day).map(|(code, _)| *code))
+                .collect();
+            rule.push_str(&format!(";BYDAY={}", days.join(",")));
+        }
+        if let Some(day) = self.month_day {
+            rule.push_str(&format!(";BYMONTHDAY={}", day));
+        }
+        rule
+    }
+
+    // Whether the rule falls on `date`, counting intervals from the first occurrence
+    fn matches(&self, date: NaiveDate, first: NaiveDate) -> bool {
+        if date < first {
+            return false;
+        }
+        let interval = self.interval.max(1) as i64;
+        match self.frequency {
+            Frequency::Daily => (date - first).num_days() % interval == 0,
+            Frequency::Weekly => {
+                let weeks = (week_start(date) - week_start(first)).num_days() / 7;
+                let on_day = if self.weekdays.is_empty() {
+                    date.weekday() == first.weekday()
+                } else {
+                    self.weekdays.contains(&date.weekday())
+                };
+                weeks % interval == 0 && on_day
+            }
+            Frequency::Monthly => {
+                let months = (date.year() as i64 * 12 + date.month0() as i64) - (first.year() as i64 * 12 + first.month0() as i64);
+                months % interval == 0 && date.day() == clamp_day(self.month_day.unwrap_or(first.day()), date)
+            }
+            Frequency::Yearly => {
+                (date.year() - first.year()) as i64 % interval == 0
+                    && date.month() == first.month()
+                    && date.day() == clamp_day(first.day(), date)
+            }
+        }
+    }
+
+    // Where a rule starting on `start` first falls. The interval only counts
+    // from there, so `every 2 weeks on mon` starts on the next Monday.
+    pub fn first_occurrence(&self, start: NaiveDate) -> Result<NaiveDate, TedoError> {
+        let rule = Recurrence { interval: 1, ..self.clone() };
+        rule.occurrence_from(start, start)
+    }
+
+    // The first occurrence on or after `date`
+    pub fn occurrence_from(&self, date: NaiveDate, first: NaiveDate) -> Result<NaiveDate, TedoError> {
+        let mut day = date.max(first);
+        while !self.matches(day, first) {
+            day = self.next_candidate(day, first).ok_or_else(|| TedoError::InvalidInput(format!(
+                "{} has no occurrence after {} within the supported dates", self, date
+            )))?;
+        }
+        Ok(day)
+    }
+
+    // The next day that can match after `day`. Periods the interval skips are
+    // jumped over whole instead of walked day by day.
+    fn next_candidate(&self, day: NaiveDate, first: NaiveDate) -> Option<NaiveDate> {
+        let interval = self.interval.max(1) as i64;
+        let skip = |elapsed: i64| (interval - elapsed % interval) % interval;
+        match self.frequency {
+            Frequency::Daily => day.checked_add_signed(Duration::days(skip((day - first).num_days()).max(1))),
+            Frequency::Weekly => match skip((week_start(day) - week_start(first)).num_days() / 7) {
+                0 => day.succ_opt(),
+                weeks => week_start(day).checked_add_signed(Duration::weeks(weeks)),
+            },
+            Frequency::Monthly => {
+                let months = (day.year() as i64 * 12 + day.month0() as i64) - (first.year() as i64 * 12 + first.month0() as i64);
+                match skip(months) {
+                    0 => day.succ_opt(),
+                    months => day.with_day(1)?.checked_add_months(Months::new(months as u32)),
+                }
+            }
+            Frequency::Yearly => match skip((day.year() - first.year()) as i64) {
+                0 => day.succ_opt(),
+                years => NaiveDate::from_ymd_opt(day.year().checked_add(years as i32)?, 1, 1),
+            },
+        }
+    }
+}
+
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = match self.frequency {
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Monthly => "month",
+            Frequency::Yearly => "year",
+        };
+        if self.frequency == Frequency::Weekly && self.interval == 1 && self.weekdays == WEEKDAYS {
+            return write!(f, "weekdays");
+        }
+        match self.interval {
+            1 => write!(f, "every {}", unit)?,
+            interval => write!(f, "every {} {}s", interval, unit)?,
+        }
+        if !self.weekdays.is_empty() {
+            let days: Vec<String> = self.weekdays.iter().map(|day| day.to_string()).collect();
+            write!(f, " on {}", days.join(", "))?;
+        }
+        if let Some(day) = self.month_day {
+            write!(f, " on the {}", ordinal(day))?;
+        }
+        Ok(())
+    }
+}
+
+
+impl TryFrom<String> for Recurrence {
+    type Error = TedoError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        parse_rrule(&text.to_lowercase())
+    }
+}
+
+
+impl From<Recurrence> for String {
+    fn from(rule: Recurrence) -> String {
+        rule.to_rrule()
+    }
+}
+
+
+fn parse_interval(text: &str) -> Option<u32> {
+    text.parse::<u32>().ok().filter(|n| (1..=MAX_INTERVAL).contains(n))
+}
+
+
+fn parse_rrule(rule: &str) -> Result<Recurrence, TedoError> {
+    let invalid = |part: &str| TedoError::InvalidInput(format!(
+        "Unsupported RRULE part {}, only FREQ, INTERVAL, BYDAY and BYMONTHDAY are supported", part.to_uppercase()
+    ));
+    let mut frequency = None;
+    let mut interval = 1;
+    let mut weekdays = Vec::new();
+    let mut month_day = None;
+
+    for part in rule.split(';').map(str::trim).filter(|part| !part.is_empty()) {
+        let (key, value) = part.split_once('=').ok_or_else(|| invalid(part))?;
+        match key {
+            "freq" => frequency = Some(match value {
+                "daily" => Frequency::Daily,
+                "weekly" => Frequency::Weekly,
+                "monthly" => Frequency::Monthly,
+                "yearly" => Frequency::Yearly,
+                _ => return Err(invalid(part)),
+            }),
+            "interval" => interval = parse_interval(value).ok_or_else(|| invalid(part))?,
+            "byday" => for code in value.split(',') {
+                let day = RRULE_DAYS.iter().find(|(c, _)| c.eq_ignore_ascii_case(code)).ok_or_else(|| invalid(part))?;
+                weekdays.push(day.1);
+            },
+            "bymonthday" => month_day = Some(value.parse::<u32>().ok().filter(|day| (1..=31).contains(day)).ok_or_else(|| invalid(part))?),
+            _ => return Err(invalid(part)),
+        }
+    }
+
+    let frequency = frequency.ok_or_else(|| TedoError::InvalidInput("RRULE is missing FREQ".into()))?;
+    if (!weekdays.is_empty() && frequency != Frequency::Weekly) || (month_day.is_some() && frequency != Frequency::Monthly) {
+        return Err(TedoError::InvalidInput("BYDAY only works with weekly and BYMONTHDAY with monthly rules".into()));
+    }
+    Ok(Recurrence { frequency, interval, weekdays, month_day })
+}
+
+
+// `monday and thursday`, `mon, thu`, ...
+fn parse_weekdays(text: &str) -> Option<Vec<Weekday>> {
+    let mut days: Vec<Weekday> = Vec::new();
+    for word in text.split([',', ' ']).filter(|word| !word.is_empty() && *word != "and") {
+        let day = word.parse::<Weekday>().ok()?;
+        if !days.contains(&day) {
+            days.push(day);
+        }
+    }
+    days.sort_by_key(|day| day.num_days_from_monday());
+    (!days.is_empty()).then_some(days)
+}
+
+
+// `the 1st`, `15th`, `3`
+fn parse_month_day(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let text = text.strip_prefix("the ").unwrap_or(text);
+    let digits = text.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    digits.parse::<u32>().ok().filter(|day| (1..=31).contains(day))
+}
+
+
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", day, suffix)
+}
+
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+
+// Rules for the 31st fall on the last day of shorter months
+fn clamp_day(day: u32, date: NaiveDate) -> u32 {
+    let next_month = if date.month() == 12 {
+        NaiveDate::from_ymd_opt(date.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1)
+    };
+    let last_day = next_month.map(|d| (d - Duration::days(1)).day()).unwrap_or(31);
+    day.min(last_day)
+}
+
+
+impl Task {
+    pub fn is_template(&self) -> bool {
+        self.recurrence.is_some()
+    }
+}
+
+
+impl Project {
+    fn add_instance(&mut self, template_id: u32, due: NaiveDate) -> Result<u32, TedoError> {
+        let template = self.task(template_id)?;
+        let (description, tags) = (template.description.clone(), template.tags.clone());
+        let next_id = self.allocate_task_id();
+        let mut instance = Task::new(next_id, &description);
+        instance.tags = tags;
+        instance.due = Some(due);
+        instance.template = Some(template_id);
+        self.tasks.push(instance);
+        Ok(next_id)
+    }
+
+    // Generates the next instance for every closed task that came from a
+    // template, unless the template already has another open instance. The
+    // next due date follows the closed one's, skipping occurrences that have
+    // already passed. Returns the ids of the new instances.
+    pub fn generate_next_instances(&mut self, closed: &[u32], today: NaiveDate) -> Vec<u32> {
+        let mut generated = Vec::new();
+        for id in closed {
+            let Some((template_id, due)) = self.task(*id).ok().and_then(|t| Some((t.template?, t.due))) else {
+                continue;
+            };
+            let Some((rule, first)) = self.task(template_id).ok().and_then(|t| Some((t.recurrence.clone()?, t.due))) else {
+                continue;
+            };
+            if self.tasks.iter().any(|t| t.template == Some(template_id) && !t.is_done()) {
+                continue;
+            }
+            let previous = due.unwrap_or(today);
+            let Some(next) = previous.succ_opt().and_then(|day| rule.occurrence_from(day.max(today), first.unwrap_or(previous)).ok()) else {
+                continue;
+            };
+            if let Ok(instance) = self.add_instance(template_id, next) {
+                generated.push(instance);
+            }
+        }
+        generated
+    }
+}
+
+
+// Creates the template and its first instance, due on the first occurrence
+// on or after `start`. Instances get the template's tags. Returns (template
+// id, instance id).
+pub fn create_recurring_task(base_dir: &Path, project: Option<&str>, description: &str, rule: Recurrence, start: NaiveDate, tags: &[&str]) -> Result<(u32, u32), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    let first = rule.first_occurrence(start)?;
+    let template_id = project.allocate_task_id();
+    let mut template = Task::new(template_id, description);
+    template.due = Some(first);
+    template.recurrence = Some(rule);
+    crate::tags::update_tags(&mut template.tags, tags, false);
+    project.tasks.push(template);
+    let instance_id = project.add_instance(template_id, first)?;
+
+    save_state(base_dir, &tedo_state)?;
+    Ok((template_id, instance_id))
+}
+
+
+pub fn list_recurring_tasks(base_dir: &Path, project: Option<&str>) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project = &tedo_state.projects[tedo_state.scoped_project_index(project)?];
+
+    let templates: Vec<&Task> = project.tasks.iter().filter(|t| t.is_template()).collect();
+    if templates.is_empty() {
+        println!("No recurring tasks in {}.", project.name);
+        return Ok(());
+    }
+    let mut table = prettytable::Table::new();
+    table.add_row(row!["ID", "Description", "Repeats", "Next"]);
+    for template in templates {
+        let next = project.tasks.iter()
+            .find(|t| t.template == Some(template.id) && !t.is_done())
+            .map(|t| format!("{} (task {})", t.due.map(|due| due.to_string()).unwrap_or_default(), t.id))
+            .unwrap_or_default();
+        let rule = template.recurrence.as_ref().map(Recurrence::to_string).unwrap_or_default();
+        table.add_row(row![template.id, template.description, rule, next]);
+    }
+    table.printstd();
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::projects::create_project;
+    use crate::tasks::{close_task, reopen_task};
+
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn rule(text: &str) -> Recurrence {
+        Recurrence::parse(text).unwrap()
+    }
+
+    // Occurrences following a rule that starts on `start`
+    fn occurrences(rule: &Recurrence, start: &str, count: usize) -> Vec<String> {
+        let first = rule.first_occurrence(date(start)).unwrap();
+        let mut dates = vec![first];
+        while dates.len() < count {
+            let previous = *dates.last().unwrap();
+            dates.push(rule.occurrence_from(previous + Duration::days(1), first).unwrap());
+        }
+        dates.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(rule("daily").to_rrule(), "FREQ=DAILY");
+        assert_eq!(rule("Every 2 weeks").to_rrule(), "FREQ=WEEKLY;INTERVAL=2");
+        assert_eq!(rule("weekdays").to_rrule(), "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR");
+        assert_eq!(rule("every thursday and monday").to_rrule(), "FREQ=WEEKLY;BYDAY=MO,TH");
+        assert_eq!(rule("weekly on fri").to_rrule(), "FREQ=WEEKLY;BYDAY=FR");
+        assert_eq!(rule("monthly on the 1st").to_rrule(), "FREQ=MONTHLY;BYMONTHDAY=1");
+        assert_eq!(rule("every 3 months").to_rrule(), "FREQ=MONTHLY;INTERVAL=3");
+        assert_eq!(rule("RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TU").to_rrule(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU");
+
+        assert!(Recurrence::parse("sometimes").is_err());
+        assert!(Recurrence::parse("every 0 days").is_err());
+        assert!(Recurrence::parse("every 3000000000 days").is_err());
+        assert!(Recurrence::parse("FREQ=YEARLY;INTERVAL=5000").is_err());
+        assert!(Recurrence::parse("daily on the 1st").is_err());
+        assert!(Recurrence::parse("FREQ=DAILY;COUNT=3").is_err());
+        assert!(Recurrence::parse("FREQ=DAILY;BYDAY=MO").is_err());
+    }
+
+    #[test]
+    fn test_describe_rules() {
+        assert_eq!(rule("weekdays").to_string(), "weekdays");
+        assert_eq!(rule("every 2 weeks on mon").to_string(), "every 2 weeks on Mon");
+        assert_eq!(rule("monthly on the 22nd").to_string(), "every month on the 22nd");
+        // Descriptions parse back to the same rule
+        for text in ["every 2 weeks on mon, thu", "every month on the 3rd", "every 4 days", "weekdays"] {
+            assert_eq!(Recurrence::parse(&rule(text).to_string()).unwrap(), rule(text));
+        }
+    }
+
+    #[test]
+    fn test_occurrences() {
+        // 2026-10-21 is a Wednesday
+        assert_eq!(occurrences(&rule("daily"), "2026-10-21", 3), ["2026-10-21", "2026-10-22", "2026-10-23"]);
+        assert_eq!(occurrences(&rule("weekdays"), "2026-10-23", 3), ["2026-10-23", "2026-10-26", "2026-10-27"]);
+        assert_eq!(occurrences(&rule("every 2 weeks"), "2026-10-21", 3), ["2026-10-21", "2026-11-04", "2026-11-18"]);
+        assert_eq!(occurrences(&rule("every 2 weeks on mon, fri"), "2026-10-21", 4), ["2026-10-23", "2026-11-02", "2026-11-06", "2026-11-16"]);
+        assert_eq!(occurrences(&rule("monthly on the 1st"), "2026-10-21", 3), ["2026-11-01", "2026-12-01", "2027-01-01"]);
+        assert_eq!(occurrences(&rule("monthly on the 31st"), "2027-01-31", 3), ["2027-01-31", "2027-02-28", "2027-03-31"]);
+        assert_eq!(occurrences(&rule("yearly"), "2028-02-29", 3), ["2028-02-29", "2029-02-28", "2030-02-28"]);
+        assert_eq!(occurrences(&rule("every 1000 years"), "2026-10-21", 2), ["2026-10-21", "3026-10-21"]);
+        assert_eq!(occurrences(&rule("every 1000 months on the 31st"), "2026-10-21", 2), ["2026-10-31", "2110-02-28"]);
+
+        // Past the last date chrono supports there is no next occurrence
+        let last = NaiveDate::MAX - Duration::days(10);
+        assert!(matches!(rule("every 1000 days").occurrence_from(last, last - Duration::days(1)), Err(TedoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_closing_an_instance_generates_the_next_one() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "ops", true).unwrap();
+        let (template, instance) = create_recurring_task(base_dir, None, "Rotate keys", rule("every 2 weeks on mon"), date("2099-10-21"), &["security"]).unwrap();
+
+        close_task(base_dir, None, instance, false).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        let project = &state.projects[0];
+        assert_eq!(project.task(template).unwrap().due, Some(date("2099-10-26")));
+        assert_eq!(project.task(instance).unwrap().due, Some(date("2099-10-26")));
+        let next = project.task(3).unwrap();
+        assert_eq!(next.template, Some(template));
+        assert_eq!(next.due, Some(date("2099-11-09")));
+        assert_eq!(next.tags, vec!["security"]);
+        assert!(next.recurrence.is_none());
+
+        // Closing again after a reopen doesn't pile up instances
+        reopen_task(base_dir, None, instance).unwrap();
+        close_task(base_dir, None, instance, false).unwrap();
+        assert_eq!(storage::load_state(base_dir).unwrap().projects[0].tasks.len(), 3);
+    }
+
+    #[test]
+    fn test_overdue_instances_skip_missed_occurrences() {
+        let mut project = Project::new(1, "ops");
+        project.tasks.push(Task { due: Some(date("2026-09-01")), recurrence: Some(rule("weekly")), ..Task::new(1, "Backups") });
+        let instance = project.add_instance(1, date("2026-09-01")).unwrap();
+        project.task_mut(instance).unwrap().status = storage::TaskStatus::Done;
+
+        // 2026-10-21 is a Wednesday, the rule falls on Tuesdays
+        let generated = project.generate_next_instances(&[instance], date("2026-10-21"));
+        assert_eq!(project.task(generated[0]).unwrap().due, Some(date("2026-10-27")));
+    }
+
+    #[test]
+    fn test_templates_cannot_be_closed() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "ops", true).unwrap();
+        let (template, _) = create_recurring_task(base_dir, None, "Rotate keys", rule("weekly"), date("2099-10-21"), &[]).unwrap();
+        assert!(matches!(close_task(base_dir, None, template, false), Err(TedoError::InvalidInput(_))));
+
+        let state = storage::load_state(base_dir).unwrap();
+        let listed: Vec<u32> = state.projects[0].task_tree().iter().map(|(_, t)| t.id).collect();
+        assert_eq!(listed, vec![2]);
+    }
+}
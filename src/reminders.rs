@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use colored::Colorize;
+
+use crate::error::TedoError;
+use crate::storage::{save_state, TedoState};
+use crate::{focus, git, storage};
+
+
+pub fn set_reminder(base_dir: &Path, project: Option<&str>, id: u32, at: Option<NaiveDateTime>) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let task = tedo_state.scoped_project_mut(project)?.task_mut(id)?;
+    task.reminder = at;
+    task.reminded = false;
+    save_state(base_dir, &tedo_state)
+}
+
+
+// (project index, task index) of open tasks whose reminder time has come
+// and that haven't been reminded of yet
+pub fn due_reminders(tedo_state: &TedoState, now: NaiveDateTime) -> Vec<(usize, usize)> {
+    let mut due = Vec::new();
+    for (project_index, project) in tedo_state.projects.iter().enumerate() {
+        for (task_index, task) in project.tasks.iter().enumerate() {
+            if !task.is_done() && !task.reminded && task.reminder.is_some_and(|at| at <= now) {
+                due.push((project_index, task_index));
+            }
+        }
+    }
+    due
+}
+
+
+// Prints the reminders that are due, or also sends them as desktop
+// notifications with `notify`. Each reminder goes off once. Returns how many
+// went off.
+pub fn check_reminders(base_dir: &Path, now: NaiveDateTime, notify: bool) -> Result<usize, TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let due = due_reminders(&tedo_state, now);
+    for (project_index, task_index) in &due {
+        let project = &mut tedo_state.projects[*project_index];
+        let project_name = project.name.clone();
+        let task = &mut project.tasks[*task_index];
+        let mut message = format!("Reminder: {}:{} {}", project_name, task.id, task.description);
+        if let Some(due) = task.due {
+            message.push_str(&format!(" (due {})", due));
+        }
+        if notify {
+            focus::notify(&message);
+        } else {
+            println!("{}", message);
+        }
+        task.reminded = true;
+    }
+    if !due.is_empty() {
+        save_state(base_dir, &tedo_state)?;
+    }
+    Ok(due.len())
+}
+
+
+// Checks every `interval` until killed. Meant to run in the background, so
+// the changes are committed as they happen. Errors are reported and the next
+// check tries again, e.g. when another tedo was saving at the same time.
+pub fn watch_reminders(base_dir: &Path, interval: std::time::Duration, notify: bool) -> Result<(), TedoError> {
+    loop {
+        let result = check_reminders(base_dir, chrono::Local::now().naive_local(), notify)
+            .and_then(|due| if due > 0 { git::commit_changes(base_dir) } else { Ok(()) });
+        if let Err(error) = result {
+            eprintln!("{} {}", "error:".red().bold(), error);
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::projects::create_project;
+    use crate::tasks::{close_task, create_task};
+
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_reminders_go_off_once() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "ops", true).unwrap();
+        create_task(base_dir, None, "Renew certificate", None).unwrap();
+        create_task(base_dir, None, "Call vendor", None).unwrap();
+        create_task(base_dir, None, "Archive logs", None).unwrap();
+        set_reminder(base_dir, None, 1, Some(at("2026-10-21 09:00"))).unwrap();
+        set_reminder(base_dir, None, 2, Some(at("2026-10-21 14:00"))).unwrap();
+        set_reminder(base_dir, None, 3, Some(at("2026-10-21 08:00"))).unwrap();
+        close_task(base_dir, None, 3, false).unwrap();
+
+        assert_eq!(check_reminders(base_dir, at("2026-10-21 10:00"), false).unwrap(), 1);
+        assert_eq!(check_reminders(base_dir, at("2026-10-21 10:05"), false).unwrap(), 0);
+        assert_eq!(check_reminders(base_dir, at("2026-10-21 14:00"), false).unwrap(), 1);
+
+        // A new reminder time goes off again
+        set_reminder(base_dir, None, 1, Some(at("2026-10-22 09:00"))).unwrap();
+        assert_eq!(check_reminders(base_dir, at("2026-10-21 18:00"), false).unwrap(), 0);
+        assert_eq!(check_reminders(base_dir, at("2026-10-22 09:00"), false).unwrap(), 1);
+    }
+}
@@ -0,0 +1,255 @@
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use colored::Colorize;
+use prettytable::row;
+use similar::TextDiff;
+
+use crate::error::TedoError;
+use crate::storage::{save_state, Note, Revision};
+use crate::{config, storage};
+
+
+// Every change to a note's content is kept as a numbered revision. The last
+// revision always matches the current content. How many revisions are kept is
+// set with `tedo config note_revisions <n>`.
+
+impl Note {
+    // Records the current content unless it matches the last revision, then
+    // drops the oldest revisions beyond `keep` (0 keeps all of them)
+    pub fn record_revision(&mut self, timestamp: NaiveDateTime, keep: usize) {
+        if self.revisions.last().is_none_or(|revision| revision.content != self.content) {
+            let number = self.revisions.last().map_or(0, |revision| revision.number) + 1;
+            self.revisions.push(Revision { number, timestamp: Some(timestamp), content: self.content.clone() });
+        }
+        if keep > 0 && self.revisions.len() > keep {
+            let excess = self.revisions.len() - keep;
+            self.revisions.drain(..excess);
+        }
+    }
+
+    // Notes created before revisions existed keep their old content as an
+    // undated first revision
+    pub fn set_content(&mut self, content: String, timestamp: NaiveDateTime, keep: usize) {
+        if self.revisions.is_empty() && content != self.content {
+            self.revisions.push(Revision { number: 1, timestamp: None, content: self.content.clone() });
+        }
+        self.content = content;
+        self.record_revision(timestamp, keep);
+    }
+
+    pub fn revision(&self, number: u32) -> Result<&Revision, TedoError> {
+        self.revisions.iter().find(|revision| revision.number == number)
+            .ok_or_else(|| TedoError::NotFound(format!("Revision {} of note {} not found.", number, self.id)))
+    }
+}
+
+
+fn timestamp_label(revision: &Revision) -> String {
+    match revision.timestamp {
+        Some(timestamp) => timestamp.format("%Y-%m-%d %H:%M").to_string(),
+        None => "-".to_string(),
+    }
+}
+
+
+fn line_changes(old: &str, new: &str) -> (usize, usize) {
+    let diff = TextDiff::from_lines(old, new);
+    let mut added = 0;
+    let mut removed = 0;
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            similar::ChangeTag::Insert => added += 1,
+            similar::ChangeTag::Delete => removed += 1,
+            similar::ChangeTag::Equal => {}
+        }
+    }
+    (added, removed)
+}
+
+
+// A unified diff with added lines in green and removed lines in red
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let text = diff.unified_diff().context_radius(3).header(old_label, new_label).to_string();
+
+    let mut output = String::new();
+    for line in text.lines() {
+        let line = if line.starts_with("---") || line.starts_with("+++") {
+            line.bold()
+        } else if line.starts_with("@@") {
+            line.cyan()
+        } else if line.starts_with('+') {
+            line.green()
+        } else if line.starts_with('-') {
+            line.red()
+        } else {
+            line.normal()
+        };
+        output.push_str(&format!("{}\n", line));
+    }
+    output
+}
+
+
+pub fn show_history(base_dir: &Path, project: Option<&str>, id: u32) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let note = tedo_state.projects[tedo_state.scoped_project_index(project)?].note(id)?;
+
+    if note.revisions.is_empty() {
+        println!("Note {} has no revisions yet.", id);
+        return Ok(());
+    }
+
+    let mut table = prettytable::Table::new();
+    table.add_row(row!["Rev", "Date", "Changes"]);
+    let current = note.revisions.last().filter(|revision| revision.content == note.content).map(|revision| revision.number);
+    let mut previous = "";
+    for revision in &note.revisions {
+        let (added, removed) = line_changes(previous, &revision.content);
+        let changes = format!("+{} -{}", added, removed);
+        let number = if current == Some(revision.number) {
+            format!("{} (current)", revision.number)
+        } else {
+            revision.number.to_string()
+        };
+        table.add_row(row![number, timestamp_label(revision), changes]);
+        previous = &revision.content;
+    }
+    table.printstd();
+    Ok(())
+}
+
+
+// Without revision numbers this shows the last change, i.e. the previous
+// revision against the current content. With one revision number it compares
+// that revision against the current content, with two the revisions.
+pub fn show_diff(base_dir: &Path, project: Option<&str>, id: u32, from: Option<u32>, to: Option<u32>) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let note = tedo_state.projects[tedo_state.scoped_project_index(project)?].note(id)?;
+
+    let from = match from {
+        Some(number) => note.revision(number)?,
+        None if note.revisions.len() >= 2 => &note.revisions[note.revisions.len() - 2],
+        None => {
+            println!("Note {} has no earlier revision to compare with.", id);
+            return Ok(());
+        }
+    };
+    let (to_label, to_content) = match to {
+        Some(number) => (format!("{}@{}", note.slug, number), note.revision(number)?.content.as_str()),
+        None => (format!("{}@current", note.slug), note.content.as_str()),
+    };
+
+    if from.content == to_content {
+        println!("No changes.");
+        return Ok(());
+    }
+    print!("{}", unified_diff(&from.content, to_content, &format!("{}@{}", note.slug, from.number), &to_label));
+    Ok(())
+}
+
+
+// Restores the content of an earlier revision, which becomes a new revision
+pub fn revert_note(base_dir: &Path, project: Option<&str>, id: u32, number: u32) -> Result<(), TedoError> {
+    let keep = config::load_config(base_dir)?.revision_limit();
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let note = tedo_state.scoped_project_mut(project)?.note_mut(id)?;
+
+    let content = note.revision(number)?.content.clone();
+    if content == note.content {
+        println!("Note {} already matches revision {}.", id, number);
+        return Ok(());
+    }
+    note.set_content(content, chrono::Local::now().naive_local(), keep);
+    save_state(base_dir, &tedo_state)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::notes::{append_note, create_note, edit_note};
+    use crate::projects::create_project;
+
+    use super::*;
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("2026-10-19 {}", time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_record_revision_skips_unchanged_content_and_trims() {
+        let mut note = Note::new(1, "note", "one");
+        note.record_revision(at("09:00"), 2);
+        note.record_revision(at("09:01"), 2);
+        assert_eq!(note.revisions.len(), 1);
+
+        note.set_content("two".into(), at("09:02"), 2);
+        note.set_content("three".into(), at("09:03"), 2);
+        let numbers: Vec<u32> = note.revisions.iter().map(|r| r.number).collect();
+        assert_eq!(numbers, vec![2, 3]);
+        assert_eq!(note.revisions[1].content, "three");
+        assert!(note.revision(1).is_err());
+    }
+
+    #[test]
+    fn test_legacy_notes_keep_their_content_as_first_revision() {
+        let mut note = Note::new(1, "note", "old");
+        note.set_content("new".into(), at("10:00"), 0);
+        assert_eq!(note.revisions.len(), 2);
+        assert_eq!(note.revisions[0].timestamp, None);
+        assert_eq!(note.revisions[0].content, "old");
+        assert_eq!(note.revisions[1].number, 2);
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        colored::control::set_override(false);
+        let diff = unified_diff("a\nb\nc\n", "a\nB\nc\n", "note@1", "note@2");
+        assert_eq!(diff, "--- note@1\n+++ note@2\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+        assert_eq!(line_changes("a\nb\n", "a\nB\nc\n"), (2, 1));
+    }
+
+    #[test]
+    fn test_edits_are_recorded_and_can_be_reverted() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_note(base_dir, None, "note", "first\n").unwrap();
+        append_note(base_dir, None, 1, "second").unwrap();
+        config::configure(base_dir, Some("editor"), Some("sed -i s/first/1st/")).unwrap();
+        edit_note(base_dir, None, 1).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        let note = &state.projects[0].notes[0];
+        let contents: Vec<&str> = note.revisions.iter().map(|r| r.content.as_str()).collect();
+        assert_eq!(contents, vec!["first\n", "first\nsecond\n", "1st\nsecond\n"]);
+
+        revert_note(base_dir, None, 1, 1).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        let note = &state.projects[0].notes[0];
+        assert_eq!(note.content, "first\n");
+        assert_eq!(note.revisions.last().unwrap().number, 4);
+        assert!(matches!(revert_note(base_dir, None, 1, 9), Err(TedoError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_retention_is_configurable() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        config::configure(base_dir, Some("note_revisions"), Some("3")).unwrap();
+        create_note(base_dir, None, "note", "").unwrap();
+        for line in ["a", "b", "c", "d"] {
+            append_note(base_dir, None, 1, line).unwrap();
+        }
+
+        let state = storage::load_state(base_dir).unwrap();
+        let numbers: Vec<u32> = state.projects[0].notes[0].revisions.iter().map(|r| r.number).collect();
+        assert_eq!(numbers, vec![3, 4, 5]);
+    }
+}
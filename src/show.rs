@@ -0,0 +1,240 @@
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use colored::Colorize;
+
+use crate::error::TedoError;
+use crate::history::describe_change;
+use crate::links::{parse_references, Reference};
+use crate::markdown;
+use crate::storage;
+use crate::storage::{Change, Project, TaskRef, TedoState};
+use crate::time_tracking::format_duration;
+
+
+const RECENT_CHANGES: usize = 5;
+
+
+fn task_label(project: &Project, id: u32) -> String {
+    match project.task(id) {
+        Ok(task) => format!("{} {}", task.id, task.description),
+        Err(_) => format!("{} {}", id, "(broken link)".red()),
+    }
+}
+
+
+fn task_ref_label(tedo_state: &TedoState, task_ref: TaskRef) -> String {
+    let project = tedo_state.projects.iter().find(|p| p.id == task_ref.project);
+    match (project, tedo_state.find_task(task_ref)) {
+        (Some(project), Some(task)) => {
+            let status = if task.is_done() { "done" } else { "open" };
+            format!("{}:{} {} ({})", project.name, task.id, task.description, status)
+        }
+        _ => format!("{}:{} {}", task_ref.project, task_ref.task, "(broken link)".red()),
+    }
+}
+
+
+fn note_label(project: &Project, id: u32) -> String {
+    match project.note(id) {
+        Ok(note) => format!("{} {}", note.id, note.description),
+        Err(_) => format!("{} {}", id, "(broken link)".red()),
+    }
+}
+
+
+fn print_field(name: &str, value: &str) {
+    println!("{:<12} {}", format!("{}:", name).dimmed(), value);
+}
+
+
+// Entities saved before the history was kept have no timestamps
+fn print_timestamps(created: Option<NaiveDateTime>, modified: Option<NaiveDateTime>) {
+    if let Some(created) = created {
+        print_field("Created", &created.format("%Y-%m-%d %H:%M").to_string());
+    }
+    if let Some(modified) = modified {
+        print_field("Modified", &modified.format("%Y-%m-%d %H:%M").to_string());
+    }
+}
+
+
+// The latest changes, `tedo history` lists all of them
+fn print_recent_changes(history: &[Change]) {
+    let changes: Vec<String> = history[history.len().saturating_sub(RECENT_CHANGES)..].iter()
+        .map(|change| format!("{}  {}", change.at.format("%Y-%m-%d %H:%M").to_string().dimmed(), describe_change(change)))
+        .collect();
+    print_section("Recent changes", &changes);
+}
+
+
+fn print_section(title: &str, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}", title.bold());
+    for line in lines {
+        println!("  {}", line);
+    }
+}
+
+
+pub fn show_task(base_dir: &Path, project: Option<&str>, id: u32) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project_index = tedo_state.scoped_project_index(project)?;
+    let project = &tedo_state.projects[project_index];
+    let task = project.task(id)?;
+    let task_ref = TaskRef { project: project.id, task: id };
+
+    println!("{} {}", format!("Task {}:", task.id).bold(), task.description);
+    print_field("Project", &format!("({}) {}", project.id, project.name));
+    let status = if task.is_done() {
+        "done".green().to_string()
+    } else if tedo_state.is_blocked(task_ref) {
+        "blocked".red().to_string()
+    } else {
+        "open".to_string()
+    };
+    print_field("Status", &status);
+    if let Some(parent) = task.parent {
+        print_field("Parent", &task_label(project, parent));
+    }
+    if let Some((done, total)) = project.progress(id) {
+        print_field("Subtasks", &format!("{}/{} done", done, total));
+    }
+    if !task.tags.is_empty() {
+        print_field("Tags", &task.tags.join(", "));
+    }
+    if let Some(due) = task.due {
+        print_field(if task.is_template() { "Starts" } else { "Due" }, &due.to_string());
+    }
+    if let Some(scheduled) = task.scheduled {
+        print_field("Scheduled", &scheduled.to_string());
+    }
+    if let Some(at) = task.reminder {
+        let sent = if task.reminded { " (sent)" } else { "" };
+        print_field("Reminder", &format!("{}{}", at.format("%Y-%m-%d %H:%M"), sent));
+    }
+    if let Some(rule) = &task.recurrence {
+        print_field("Repeats", &rule.to_string());
+    }
+    if let Some(template) = task.template.and_then(|id| project.task(id).ok()) {
+        print_field("Template", &format!("{} {}", template.id, template.description));
+    }
+    if !task.time_entries.is_empty() {
+        let tracked = format_duration(task.tracked_time(chrono::Local::now().naive_local()));
+        print_field("Time", &if task.is_tracking() { format!("{} (running)", tracked) } else { tracked });
+    }
+    print_timestamps(task.created, task.modified);
+
+    let subtasks: Vec<String> = project.children(id).iter().map(|t| task_label(project, t.id)).collect();
+    print_section("Subtasks", &subtasks);
+
+    let prerequisites: Vec<String> = task.depends_on.iter().map(|t| task_ref_label(&tedo_state, *t)).collect();
+    print_section("Depends on", &prerequisites);
+
+    let dependents: Vec<String> = tedo_state.projects.iter()
+        .flat_map(|p| p.tasks.iter().map(move |t| (p.id, t)))
+        .filter(|(_, t)| t.depends_on.contains(&task_ref))
+        .map(|(project_id, t)| task_ref_label(&tedo_state, TaskRef { project: project_id, task: t.id }))
+        .collect();
+    print_section("Blocks", &dependents);
+
+    let notes: Vec<String> = project.task_notes(id).iter().map(|note_id| note_label(project, *note_id)).collect();
+    print_section("Notes", &notes);
+    print_recent_changes(&task.history);
+    Ok(())
+}
+
+
+// With `raw` only the unrendered note body is printed, so it can be piped
+pub fn show_note(base_dir: &Path, project: Option<&str>, id: u32, raw: bool) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project = &tedo_state.projects[tedo_state.scoped_project_index(project)?];
+    let note = project.note(id)?;
+
+    if raw {
+        print!("{}", note.content);
+        return Ok(());
+    }
+
+    println!("{} {}", format!("Note {}:", note.id).bold(), note.description);
+    print_field("Project", &format!("({}) {}", project.id, project.name));
+    if !note.tags.is_empty() {
+        print_field("Tags", &note.tags.join(", "));
+    }
+    print_timestamps(note.created, note.modified);
+
+    if !note.content.trim().is_empty() {
+        println!();
+        println!("{}", markdown::render(&note.content));
+    }
+
+    let references: Vec<String> = parse_references(&note.content).into_iter()
+        .map(|reference| match reference {
+            Reference::Note(id) => format!("note {}", note_label(project, id)),
+            Reference::Task(id) => format!("task {}", task_label(project, id)),
+        })
+        .collect();
+    print_section("References", &references);
+
+    let backlinks = project.note_backlinks(id);
+    let tasks: Vec<String> = backlinks.tasks.iter().map(|task_id| task_label(project, *task_id)).collect();
+    print_section("Linked from tasks", &tasks);
+    let notes: Vec<String> = backlinks.notes.iter().map(|note_id| note_label(project, *note_id)).collect();
+    print_section("Linked from notes", &notes);
+    print_recent_changes(&note.history);
+    Ok(())
+}
+
+
+pub fn show_project(base_dir: &Path, project: Option<&str>) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project_index = tedo_state.scoped_project_index(project)?;
+    let project = &tedo_state.projects[project_index];
+
+    println!("{} {}", format!("Project {}:", project.id).bold(), project.name);
+    if tedo_state.current_project.as_ref() == Some(&project.name) {
+        print_field("Current", "yes");
+    }
+    print_field("Status", project.status.label());
+    if let Some(owner) = &project.owner {
+        print_field("Owner", owner);
+    }
+    if let Some(start) = project.start {
+        print_field("Start", &start.to_string());
+    }
+    if let Some(target) = project.target {
+        print_field("Target", &target.to_string());
+    }
+    if let Some(color) = &project.color {
+        print_field("Color", &project.colored(color).to_string());
+    }
+    let done = project.tasks.iter().filter(|t| t.is_done()).count();
+    print_field("Tasks", &format!("{} open, {} done", project.tasks.len() - done, done));
+    print_field("Notes", &project.notes.len().to_string());
+    print_timestamps(project.created, project.modified);
+    if !project.description.is_empty() {
+        println!();
+        println!("{}", markdown::render(&project.description));
+    }
+
+    let blocked = tedo_state.blocked_tasks(project_index);
+    let tasks: Vec<String> = project.task_tree().iter()
+        .map(|(depth, task)| {
+            let marker = if task.is_done() { "[x]" } else { "[ ]" };
+            let blocked = if !task.is_done() && blocked.contains(&task.id) { " (blocked)" } else { "" };
+            format!("{}{} {} {}{}", "  ".repeat(*depth), marker, task.id, task.description, blocked)
+        })
+        .collect();
+    print_section("Tasks", &tasks);
+
+    let notes: Vec<String> = project.notes.iter().map(|n| format!("{} {}", n.id, n.description)).collect();
+    print_section("Notes", &notes);
+
+    let broken = project.broken_links();
+    print_section("Broken links", &broken);
+    print_recent_changes(&project.history);
+    Ok(())
+}
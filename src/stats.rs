@@ -0,0 +1,299 @@
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use colored::Colorize;
+use prettytable::row;
+
+use crate::error::TedoError;
+use crate::projects::tree_order;
+use crate::storage;
+use crate::storage::{Project, Task, TedoState};
+use crate::time_tracking::format_duration;
+
+
+// Progress metrics from task statuses and the change history. Tasks created
+// or closed before the history was kept only count towards the totals.
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Ten years, which keeps the date arithmetic in range
+const MAX_WEEKS: usize = 520;
+
+
+pub struct Stats {
+    pub open: usize,
+    pub done: usize,
+    pub overdue: usize,
+    // Tasks closed in each of the last weeks, oldest first. A week is the
+    // seven days up to and including a day.
+    pub throughput: Vec<usize>,
+    // Average time from creation to done of the tasks closed in those weeks
+    pub cycle_time: Option<Duration>,
+    // Open tasks at the end of each day of those weeks, oldest first
+    pub burndown: Vec<(NaiveDate, usize)>,
+}
+
+
+impl Task {
+    // When the task was last closed, if it is done
+    pub fn completed_at(&self) -> Option<NaiveDateTime> {
+        if !self.is_done() {
+            return None;
+        }
+        self.history.iter().rev()
+            .find(|change| change.field == "status" && change.value.as_deref() == Some("done"))
+            .map(|change| change.at)
+    }
+
+    fn is_open_on(&self, day: NaiveDate) -> bool {
+        let created = self.created.is_none_or(|created| created.date() <= day);
+        let closed = match self.completed_at() {
+            Some(completed) => completed.date() <= day,
+            None => self.is_done(),
+        };
+        created && !closed
+    }
+}
+
+
+pub fn compute(projects: &[&Project], weeks: usize, today: NaiveDate) -> Stats {
+    let tasks: Vec<&Task> = projects.iter()
+        .flat_map(|p| p.tasks.iter())
+        .filter(|t| !t.is_template())
+        .collect();
+    let first_day = today - Duration::days(7 * weeks as i64 - 1);
+
+    let throughput = (0..weeks)
+        .map(|week| {
+            let end = today - Duration::days(7 * (weeks - 1 - week) as i64);
+            let start = end - Duration::days(6);
+            tasks.iter()
+                .filter_map(|t| t.completed_at())
+                .filter(|completed| (start..=end).contains(&completed.date()))
+                .count()
+        })
+        .collect();
+
+    let cycle_times: Vec<Duration> = tasks.iter()
+        .filter_map(|t| Some((t.created?, t.completed_at()?)))
+        .filter(|(_, completed)| completed.date() >= first_day)
+        .map(|(created, completed)| completed - created)
+        .collect();
+    let cycle_time = match cycle_times.len() {
+        0 => None,
+        count => Some(cycle_times.iter().fold(Duration::zero(), |total, time| total + *time) / count as i32),
+    };
+
+    let burndown = first_day.iter_days()
+        .take_while(|day| *day <= today)
+        .map(|day| (day, tasks.iter().filter(|t| t.is_open_on(day)).count()))
+        .collect();
+
+    Stats {
+        open: tasks.iter().filter(|t| !t.is_done()).count(),
+        done: tasks.iter().filter(|t| t.is_done()).count(),
+        overdue: tasks.iter().filter(|t| !t.is_done() && t.due.is_some_and(|due| due < today)).count(),
+        throughput,
+        cycle_time,
+        burndown,
+    }
+}
+
+
+pub fn sparkline(values: &[usize]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+    values.iter()
+        .map(|&value| match max {
+            0 => BARS[0],
+            _ => BARS[(value * (BARS.len() - 1) + max / 2) / max],
+        })
+        .collect()
+}
+
+
+// A bar chart with one column per value, `height` rows high and the axis
+// labelled with the highest value and zero
+pub fn chart(values: &[usize], height: usize) -> Vec<String> {
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    let width = max.to_string().len();
+    (1..=height).rev()
+        .map(|row| {
+            let bars: String = values.iter()
+                .map(|&value| {
+                    // The value in half rows
+                    let halves = (value * height * 2 + max / 2) / max;
+                    match halves {
+                        _ if halves >= row * 2 => '█',
+                        _ if halves == row * 2 - 1 => '▄',
+                        _ => ' ',
+                    }
+                })
+                .collect();
+            let label = match row {
+                _ if row == height => max.to_string(),
+                1 => "0".to_string(),
+                _ => String::new(),
+            };
+            format!("{:>width$} ┤{}", label, bars, width = width)
+        })
+        .collect()
+}
+
+
+fn format_cycle_time(cycle_time: Option<Duration>) -> String {
+    match cycle_time {
+        Some(time) if time.num_days() > 0 => format!("{}d {}h", time.num_days(), time.num_hours() % 24),
+        Some(time) => format_duration(time),
+        None => "-".to_string(),
+    }
+}
+
+
+fn show_overview(tedo_state: &TedoState, weeks: usize, today: NaiveDate) {
+    let mut table = prettytable::Table::new();
+    table.add_row(row!["Project", "Open", "Done", "Overdue", "Cycle time", format!("Done per week ({})", weeks)]);
+    for index in tree_order(&tedo_state.projects).into_iter().filter(|&index| !tedo_state.is_archived_within(index)) {
+        let project = &tedo_state.projects[index];
+        // Like `tedo list`, a parent includes its sub-projects
+        let subtree: Vec<&Project> = tedo_state.subtree(index).into_iter()
+            .filter(|&index| !tedo_state.is_archived_within(index))
+            .map(|index| &tedo_state.projects[index])
+            .collect();
+        let stats = compute(&subtree, weeks, today);
+        let overdue = match stats.overdue {
+            0 => "0".normal(),
+            overdue => overdue.to_string().red(),
+        };
+        table.add_row(row![
+            format!("{}{}", "  ".repeat(project.depth()), project.colored(project.short_name())),
+            stats.open,
+            stats.done,
+            overdue,
+            format_cycle_time(stats.cycle_time),
+            format!("{} {}", sparkline(&stats.throughput), stats.throughput.last().unwrap_or(&0)),
+        ]);
+    }
+    table.printstd();
+}
+
+
+fn show_project(tedo_state: &TedoState, index: usize, weeks: usize, today: NaiveDate) {
+    let project = &tedo_state.projects[index];
+    let subtree: Vec<&Project> = tedo_state.subtree(index).into_iter().map(|index| &tedo_state.projects[index]).collect();
+    let stats = compute(&subtree, weeks, today);
+
+    let title = match subtree.len() {
+        1 => format!("Project {}", project.name),
+        _ => format!("Project {} and {} sub-project(s)", project.name, subtree.len() - 1),
+    };
+    println!("{}", title.bold());
+    println!("{:<15} {}", "Open:".dimmed(), stats.open);
+    println!("{:<15} {}", "Done:".dimmed(), stats.done);
+    let overdue = match stats.overdue {
+        0 => "0".normal(),
+        overdue => overdue.to_string().red(),
+    };
+    println!("{:<15} {}", "Overdue:".dimmed(), overdue);
+    println!("{:<15} {}", "Cycle time:".dimmed(), format_cycle_time(stats.cycle_time));
+    let counts: Vec<String> = stats.throughput.iter().map(|count| count.to_string()).collect();
+    println!("{:<15} {}  {}", "Done per week:".dimmed(), sparkline(&stats.throughput), counts.join(" "));
+
+    println!();
+    println!("{}", format!("Open tasks over the last {} weeks", weeks).bold());
+    let values: Vec<usize> = stats.burndown.iter().map(|(_, open)| *open).collect();
+    let lines = chart(&values, 8);
+    for line in &lines {
+        println!("{}", line);
+    }
+    if let (Some((first, _)), Some((last, _))) = (stats.burndown.first(), stats.burndown.last()) {
+        let indent = lines[0].chars().position(|c| c == '┤').unwrap_or(0) + 1;
+        let first = first.format("%m-%d").to_string();
+        let last = last.format("%m-%d").to_string();
+        let gap = values.len().saturating_sub(first.len() + last.len()).max(1);
+        println!("{}{}{}{}", " ".repeat(indent), first, " ".repeat(gap), last);
+    }
+}
+
+
+// Without a project, a table of all projects that are not archived. With one,
+// the details of that project and its sub-projects along with a burndown.
+pub fn show_stats(base_dir: &Path, project: Option<&str>, weeks: usize, today: NaiveDate) -> Result<(), TedoError> {
+    if !(1..=MAX_WEEKS).contains(&weeks) {
+        return Err(TedoError::InvalidInput(format!("--weeks must be between 1 and {}", MAX_WEEKS)));
+    }
+    let tedo_state = storage::load_state(base_dir)?;
+    match project {
+        Some(identifier) => show_project(&tedo_state, tedo_state.project_index(identifier)?, weeks, today),
+        None => show_overview(&tedo_state, weeks, today),
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::{Change, TaskStatus};
+
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn task(id: u32, created: &str, done: Option<&str>) -> Task {
+        let mut task = Task::new(id, "Task");
+        task.created = Some(at(created));
+        if let Some(done) = done {
+            task.status = TaskStatus::Done;
+            task.history.push(Change { at: at(done), field: "status".into(), value: Some("done".into()) });
+        }
+        task
+    }
+
+    #[test]
+    fn test_compute() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let mut project = Project::new(1, "growth");
+        project.tasks = vec![
+            task(1, "2026-10-01 09:00", Some("2026-10-03 09:00")),
+            task(2, "2026-10-10 09:00", Some("2026-10-14 09:00")),
+            task(3, "2026-10-10 09:00", None),
+            Task { due: NaiveDate::from_ymd_opt(2026, 10, 18), ..task(4, "2026-10-15 09:00", None) },
+            // Closed before the history was kept
+            Task { status: TaskStatus::Done, ..Task::new(5, "Old") },
+        ];
+
+        let stats = compute(&[&project], 2, today);
+        assert_eq!((stats.open, stats.done, stats.overdue), (2, 3, 1));
+        // Weeks are Oct 6-12 and Oct 13-19
+        assert_eq!(stats.throughput, vec![0, 1]);
+        assert_eq!(stats.cycle_time, Some(Duration::days(4)));
+        assert_eq!(stats.burndown.len(), 14);
+        assert_eq!(stats.burndown[0], (NaiveDate::from_ymd_opt(2026, 10, 6).unwrap(), 0));
+        assert_eq!(stats.burndown[4].1, 2);
+        assert_eq!(stats.burndown[8].1, 1);
+        assert_eq!(stats.burndown[13].1, 2);
+    }
+
+    #[test]
+    fn test_sparkline_and_chart() {
+        assert_eq!(sparkline(&[0, 1, 2, 4, 8]), "▁▂▃▅█");
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+
+        assert_eq!(chart(&[4, 3, 1, 0], 2), vec![
+            "4 ┤█▄  ",
+            "0 ┤██▄ ",
+        ]);
+    }
+
+    #[test]
+    fn test_weeks_out_of_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+
+        for weeks in [0, 521, 9999999999999999] {
+            assert!(matches!(show_stats(dir.path(), None, weeks, today), Err(TedoError::InvalidInput(_))));
+        }
+        assert!(show_stats(dir.path(), None, 520, today).is_ok());
+    }
+}
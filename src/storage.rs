@@ -1,10 +1,20 @@
+use std::fs;
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
-use toml;
+use chrono::{NaiveDate, NaiveDateTime, Weekday};
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+use crate::config;
+use crate::config::NoteStorage;
+use crate::error::TedoError;
+use crate::history;
+use crate::note_files;
+
+pub(crate) static STATE_FILE: &str = "tedo_state.toml";
+
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct TedoState {
     pub(crate) current_project: Option<String>,
     pub(crate) projects: Vec<Project>,
@@ -12,21 +22,87 @@ pub struct TedoState {
 
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Project {
     pub id: u32,
     pub name: String,
     pub tasks: Vec<Task>,
     pub notes: Vec<Note>,  // List of notes for each project
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub status: ProjectStatus,
+    #[serde(default)]
+    pub start: Option<NaiveDate>,
+    #[serde(default)]
+    pub target: Option<NaiveDate>,
+    // A color name such as blue, used for the project name in listings
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub created: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub modified: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub history: Vec<Change>,
+    // The ids the next task and note get, so that the ids of deleted ones
+    // are never handed out again. Zero in states saved before they existed.
+    #[serde(default)]
+    pub next_task_id: u32,
+    #[serde(default)]
+    pub next_note_id: u32,
 }
 
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Note {
     pub id: u32,
+    // Unique within the project, derived from the description
+    #[serde(default)]
+    pub slug: String,
+    // Former slugs, so old identifiers keep working after a rename
+    #[serde(default)]
+    pub previous_slugs: Vec<String>,
     pub description: String,
     pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Path of the Markdown file holding the content, relative to the notes
+    // directory, when notes are stored as files
+    #[serde(default)]
+    pub file: Option<String>,
+    // Earlier versions of the content, oldest first
+    #[serde(default)]
+    pub revisions: Vec<Revision>,
+    #[serde(default)]
+    pub created: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub modified: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub history: Vec<Change>,
+}
+
+
+// A field of a project, task or note that changed, along with its new value
+// for short values. Creation is recorded as a change of the field `created`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Change {
+    pub at: NaiveDateTime,
+    pub field: String,
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Revision {
+    pub number: u32,
+    // None for the content a note had before revisions were recorded
+    pub timestamp: Option<NaiveDateTime>,
+    pub content: String,
 }
 
 
@@ -38,11 +114,132 @@ impl PartialEq for Project {
 }
 
 
-#[derive(Serialize, Deserialize,  Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Task {
     pub id: u32,
     pub description: String,
-    // Add other task properties here, such as status, due date, etc.
+    #[serde(default)]
+    pub status: TaskStatus,
+    // Id of the parent task within the same project
+    #[serde(default)]
+    pub parent: Option<u32>,
+    // Tasks that have to be done before this one, possibly in other projects
+    #[serde(default)]
+    pub depends_on: Vec<TaskRef>,
+    // Ids of the notes attached to this task
+    #[serde(default)]
+    pub notes: Vec<u32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Time tracked on the task, at most one entry in the whole state is open
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
+    #[serde(default)]
+    pub focus_sessions: Vec<FocusSession>,
+    #[serde(default)]
+    pub due: Option<NaiveDate>,
+    // Day the task is planned to be worked on
+    #[serde(default)]
+    pub scheduled: Option<NaiveDate>,
+    #[serde(default)]
+    pub reminder: Option<NaiveDateTime>,
+    // Set once the reminder went off, so it only goes off once
+    #[serde(default)]
+    pub reminded: bool,
+    // Only set on recurring task templates, which are never worked on themselves
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    // Id of the template a recurring task instance was generated from
+    #[serde(default)]
+    pub template: Option<u32>,
+    // iCalendar UID of a task imported from a calendar
+    #[serde(default)]
+    pub uid: Option<String>,
+    #[serde(default)]
+    pub created: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub modified: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub history: Vec<Change>,
+    // Add other task properties here
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TimeEntry {
+    pub start: NaiveDateTime,
+    // None while the timer is running
+    pub end: Option<NaiveDateTime>,
+}
+
+
+// A pomodoro on a task. Its time is also recorded as a time entry.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FocusSession {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub planned_minutes: u32,
+    // False when the session was stopped early
+    pub completed: bool,
+    #[serde(default)]
+    pub interruptions: Vec<Interruption>,
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Interruption {
+    pub at: NaiveDateTime,
+    pub note: String,
+}
+
+
+// Stored as an RRULE string, e.g. `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    // Days of the week for weekly rules, the first occurrence's day when empty
+    pub weekdays: Vec<Weekday>,
+    // Day of the month for monthly rules, the first occurrence's day when unset
+    pub month_day: Option<u32>,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+
+// Identifies a task across projects by project id and task id
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TaskRef {
+    pub project: u32,
+    pub task: u32,
+}
+
+
+// Archived projects are hidden from listings by default
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectStatus {
+    #[default]
+    Active,
+    Paused,
+    Archived,
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    #[default]
+    Open,
+    Done,
 }
 
 impl PartialEq for Task {
@@ -53,39 +250,90 @@ impl PartialEq for Task {
 }
 
 
-pub fn load_state(base_dir: &Path) -> Result<TedoState, Box<dyn std::error::Error>> {
+// A missing state file is a fresh install, not an error. Anything that fails to
+// parse is reported as corrupt so that we never overwrite it with an empty state.
+pub fn load_state(base_dir: &Path) -> Result<TedoState, TedoError> {
+    let mut tedo_state = read_state_file(base_dir)?;
+    let config = config::load_config(base_dir)?;
+    read_notes(base_dir, &mut tedo_state, config.note_storage == NoteStorage::Files, config.revision_limit())?;
+    Ok(tedo_state)
+}
+
+
+// Fills in the notes of a state file read from `dir` that are kept as files
+// there. New note files are only imported when `import` is set.
+pub(crate) fn read_notes(dir: &Path, tedo_state: &mut TedoState, import: bool, revision_limit: usize) -> Result<(), TedoError> {
+    for project in tedo_state.projects.iter_mut() {
+        project.ensure_note_slugs();
+    }
+    if import || tedo_state.projects.iter().flat_map(|p| &p.notes).any(|n| n.file.is_some()) {
+        note_files::read_note_files(dir, tedo_state, import, revision_limit)?;
+    }
+    Ok(())
+}
+
+
+// The state as saved, without the notes kept in files
+fn read_state_file(base_dir: &Path) -> Result<TedoState, TedoError> {
     let path = get_state_path(base_dir);
-    let mut file = File::open(&path)?;
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(TedoState::default()),
+        Err(error) => return Err(error.into()),
+    };
     let mut data = String::new();
     file.read_to_string(&mut data)?;
-    let tedo_state: TedoState = toml::from_str(&data)?;
-    Ok(tedo_state)
+    Ok(toml::from_str(&data)?)
 }
 
 
-pub fn save_state(base_dir: &Path, tedo_state: &TedoState) -> Result<(), Box<dyn std::error::Error>> {
+// Stamps what changed since the last save before writing, see `history`
+pub fn save_state(base_dir: &Path, tedo_state: &TedoState) -> Result<(), TedoError> {
+    let mut tedo_state = tedo_state.clone();
+    let previous = load_state(base_dir).unwrap_or_default();
+    history::record_changes(&previous, &mut tedo_state, chrono::Local::now().naive_local());
+    let tedo_state = &tedo_state;
+
+    let toml = if config::load_config(base_dir)?.note_storage == NoteStorage::Files {
+        let mut tedo_state = tedo_state.clone();
+        note_files::write_note_files(base_dir, &read_state_file(base_dir).unwrap_or_default(), &mut tedo_state)?;
+        toml::to_string(&tedo_state)?
+    } else if tedo_state.projects.iter().flat_map(|p| &p.notes).any(|n| n.file.is_some()) {
+        // Notes were moved back into the state, forget about their files
+        let mut tedo_state = tedo_state.clone();
+        tedo_state.projects.iter_mut().flat_map(|p| &mut p.notes).for_each(|n| n.file = None);
+        toml::to_string(&tedo_state)?
+    } else {
+        toml::to_string(tedo_state)?
+    };
+
+    // Written next to the state file and renamed over it, so that neither a
+    // failed save nor a concurrent reader ever sees a partial file
     let path = get_state_path(base_dir);
+    let temp_path = base_dir.join(format!(".{}.tmp", STATE_FILE));
     let mut file = OpenOptions::new()
         .write(true)
         .create(true)
-        .open(&path)?;
-    let toml = toml::to_string(tedo_state)?;
+        .truncate(true)
+        .open(&temp_path)?;
     file.write_all(toml.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp_path, &path)?;
     Ok(())
 }
 
-pub fn set_current_project(base_dir: &Path, name: &str) {
-    let mut tedo_state = load_state(base_dir).unwrap_or_default();
+pub fn set_current_project(base_dir: &Path, name: &str) -> Result<(), TedoError> {
+    let mut tedo_state = load_state(base_dir)?;
     if tedo_state.projects.iter().any(|p| p.name == name) {
         tedo_state.current_project = Some(name.into());
-        save_state(base_dir, &tedo_state).expect("Failed to save projects");
+        save_state(base_dir, &tedo_state)
     } else {
-        println!("Project with name {} does not exist", name);
+        Err(TedoError::NotFound(format!("Project with name {} does not exist", name)))
     }
 }
 
-fn get_state_path(base_dir: &Path) -> PathBuf {
-    base_dir.join("tedo_state.toml")
+pub(crate) fn get_state_path(base_dir: &Path) -> PathBuf {
+    base_dir.join(STATE_FILE)
 }
 
 
@@ -104,7 +352,7 @@ mod tests {
         let tedo_state = TedoState {
             current_project: Some("test_project".into()),
 
-            projects: vec![Project { id: 1, name: "test".into(), tasks: Vec::new(), notes: Vec::new() }],
+            projects: vec![Project::new(1, "test")],
         };
         save_state(base_dir, &tedo_state)?;
 
@@ -118,4 +366,59 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_load_state_without_state_file() {
+        let dir = tempdir().unwrap();
+
+        let state = load_state(dir.path()).unwrap();
+        assert!(state.current_project.is_none());
+        assert!(state.projects.is_empty());
+    }
+
+    #[test]
+    fn test_load_corrupt_state() {
+        let dir = tempdir().unwrap();
+        std::fs::write(get_state_path(dir.path()), "projects = [[[").unwrap();
+
+        let result = load_state(dir.path());
+        assert!(matches!(result, Err(TedoError::StorageCorrupt(_))));
+    }
+
+    #[test]
+    fn test_save_shorter_state_truncates_file() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        let long_state = TedoState {
+            current_project: Some("a_project_with_a_rather_long_name".into()),
+            projects: vec![Project::new(1, "a_project_with_a_rather_long_name")],
+        };
+        save_state(base_dir, &long_state).unwrap();
+        save_state(base_dir, &TedoState::default()).unwrap();
+
+        let loaded_state = load_state(base_dir).unwrap();
+        assert!(loaded_state.projects.is_empty());
+    }
+
+    #[test]
+    fn test_failed_save_keeps_previous_state() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        config::configure(base_dir, Some("note_storage"), Some("files")).unwrap();
+        crate::projects::create_project(base_dir, "beta", true).unwrap();
+        // The notes directory of the project can not be created
+        fs::create_dir_all(note_files::notes_dir(base_dir)).unwrap();
+        fs::write(note_files::notes_dir(base_dir).join("beta-1"), "").unwrap();
+
+        let mut state = load_state(base_dir).unwrap();
+        state.projects[0].notes.push(Note::new(1, "x", ""));
+        assert!(matches!(save_state(base_dir, &state), Err(TedoError::Io(_))));
+
+        let state = load_state(base_dir).unwrap();
+        assert_eq!(state.projects.len(), 1);
+        assert!(state.projects[0].notes.is_empty());
+        assert!(!base_dir.join(format!(".{}.tmp", STATE_FILE)).exists());
+    }
 }
@@ -0,0 +1,215 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use crate::error::TedoError;
+use crate::storage;
+use crate::storage::{Change, Note, Project, Task, TedoState};
+use crate::time_tracking::format_duration;
+
+
+// A Markdown report of what happened in each project since a day, built from
+// the change history and time entries. Meant for standups and weekly reports.
+
+pub struct ProjectSummary<'a> {
+    pub project: &'a Project,
+    pub completed: Vec<&'a Task>,
+    pub created: Vec<&'a Task>,
+    pub in_progress: Vec<&'a Task>,
+    // Notes written or edited, along with whether they are new
+    pub notes: Vec<(&'a Note, bool)>,
+    pub time: Duration,
+}
+
+impl ProjectSummary<'_> {
+    fn is_empty(&self) -> bool {
+        self.completed.is_empty() && self.created.is_empty() && self.in_progress.is_empty()
+            && self.notes.is_empty() && self.time.is_zero()
+    }
+}
+
+
+fn changed_since(history: &[Change], since: NaiveDate, matches: impl Fn(&Change) -> bool) -> bool {
+    history.iter().any(|change| change.at.date() >= since && matches(change))
+}
+
+
+// In progress are open tasks that had time tracked or other changes than
+// their creation since the day. Projects without any activity are left out.
+pub fn summarize(tedo_state: &TedoState, since: NaiveDate, now: NaiveDateTime) -> Vec<ProjectSummary<'_>> {
+    let mut summaries = Vec::new();
+    for project in &tedo_state.projects {
+        let tasks: Vec<&Task> = project.tasks.iter().filter(|t| !t.is_template()).collect();
+        let summary = ProjectSummary {
+            project,
+            completed: tasks.iter().copied()
+                .filter(|t| t.is_done() && changed_since(&t.history, since, |c| c.field == "status" && c.value.as_deref() == Some("done")))
+                .collect(),
+            created: tasks.iter().copied()
+                .filter(|t| changed_since(&t.history, since, |c| c.field == "created"))
+                .collect(),
+            in_progress: tasks.iter().copied()
+                .filter(|t| !t.is_done())
+                .filter(|t| t.is_tracking() || !t.tracked_since(since, now).is_zero() || changed_since(&t.history, since, |c| c.field != "created"))
+                .collect(),
+            notes: project.notes.iter()
+                .filter(|n| changed_since(&n.history, since, |c| c.field == "created" || c.field == "content"))
+                .map(|n| (n, changed_since(&n.history, since, |c| c.field == "created")))
+                .collect(),
+            time: tasks.iter().map(|t| t.tracked_since(since, now)).fold(Duration::zero(), |total, time| total + time),
+        };
+        if !summary.is_empty() {
+            summaries.push(summary);
+        }
+    }
+    summaries
+}
+
+
+fn task_line(task: &Task, since: NaiveDate, now: NaiveDateTime) -> String {
+    let time = task.tracked_since(since, now);
+    if time.is_zero() {
+        format!("- {} (#{})\n", task.description, task.id)
+    } else {
+        format!("- {} (#{}, {})\n", task.description, task.id, format_duration(time))
+    }
+}
+
+
+pub fn render_markdown(summaries: &[ProjectSummary], since: NaiveDate, now: NaiveDateTime) -> String {
+    let mut markdown = format!("# Summary since {}\n", since.format("%a %Y-%m-%d"));
+    if summaries.is_empty() {
+        markdown.push_str("\nNo activity.\n");
+        return markdown;
+    }
+
+    for summary in summaries {
+        markdown.push_str(&format!("\n## {}\n", summary.project.name));
+        if !summary.time.is_zero() {
+            markdown.push_str(&format!("\nTime tracked: {}\n", format_duration(summary.time)));
+        }
+        for (title, tasks) in [("Completed", &summary.completed), ("Created", &summary.created), ("In progress", &summary.in_progress)] {
+            if tasks.is_empty() {
+                continue;
+            }
+            markdown.push_str(&format!("\n### {}\n\n", title));
+            for task in tasks {
+                markdown.push_str(&task_line(task, since, now));
+            }
+        }
+        if !summary.notes.is_empty() {
+            markdown.push_str("\n### Notes\n\n");
+            for (note, new) in &summary.notes {
+                let label = if *new { "new" } else { "edited" };
+                markdown.push_str(&format!("- {} ({})\n", note.description, label));
+            }
+        }
+    }
+    markdown
+}
+
+
+// Covers all projects unless one is given, then it and its sub-projects.
+// With `output` the report is written to that file instead of printed.
+pub fn summary(base_dir: &Path, project: Option<&str>, since: NaiveDate, output: Option<&Path>, now: NaiveDateTime) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    tedo_state.narrow_to_subtree(project)?;
+
+    let markdown = render_markdown(&summarize(&tedo_state, since, now), since, now);
+    match output {
+        Some(path) => {
+            fs::write(path, markdown)?;
+            println!("Summary written to {}", path.display());
+        }
+        None => print!("{}", markdown),
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::{TaskStatus, TimeEntry};
+
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn change(when: &str, field: &str, value: Option<&str>) -> Change {
+        Change { at: at(when), field: field.into(), value: value.map(String::from) }
+    }
+
+    fn state() -> TedoState {
+        let mut project = Project::new(1, "growth");
+        let mut docs = Task::new(1, "Write docs");
+        docs.status = TaskStatus::Done;
+        docs.history = vec![change("2026-10-12 09:00", "created", None), change("2026-10-20 16:00", "status", Some("done"))];
+        docs.time_entries.push(TimeEntry { start: at("2026-10-20 09:00"), end: Some(at("2026-10-20 10:30")) });
+        let mut review = Task::new(2, "Review");
+        review.history = vec![change("2026-10-20 11:00", "created", None)];
+        let mut release = Task::new(3, "Release");
+        release.history = vec![change("2026-10-10 11:00", "created", None)];
+        release.time_entries.push(TimeEntry { start: at("2026-10-21 08:00"), end: None });
+        let stale = Task { history: vec![change("2026-10-01 11:00", "created", None)], ..Task::new(4, "Stale") };
+        project.tasks = vec![docs, review, release, stale];
+
+        let mut ideas = crate::storage::Note::new(1, "Ideas", "");
+        ideas.history = vec![change("2026-10-01 11:00", "created", None), change("2026-10-20 12:00", "content", None)];
+        project.notes.push(ideas);
+
+        let mut state = TedoState::default();
+        state.projects.push(project);
+        state.projects.push(Project::new(2, "quiet"));
+        state
+    }
+
+    #[test]
+    fn test_summarize() {
+        let state = state();
+        let summaries = summarize(&state, NaiveDate::from_ymd_opt(2026, 10, 20).unwrap(), at("2026-10-21 08:30"));
+
+        assert_eq!(summaries.len(), 1);
+        let ids = |tasks: &[&Task]| tasks.iter().map(|t| t.id).collect::<Vec<u32>>();
+        assert_eq!(ids(&summaries[0].completed), vec![1]);
+        assert_eq!(ids(&summaries[0].created), vec![2]);
+        assert_eq!(ids(&summaries[0].in_progress), vec![3]);
+        assert_eq!(summaries[0].notes.len(), 1);
+        assert!(!summaries[0].notes[0].1);
+        assert_eq!(summaries[0].time, Duration::minutes(120));
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let state = state();
+        let since = NaiveDate::from_ymd_opt(2026, 10, 20).unwrap();
+        let now = at("2026-10-21 08:30");
+
+        assert_eq!(render_markdown(&summarize(&state, since, now), since, now), "\
+# Summary since Tue 2026-10-20
+
+## growth
+
+Time tracked: 2h 00m
+
+### Completed
+
+- Write docs (#1, 1h 30m)
+
+### Created
+
+- Review (#2)
+
+### In progress
+
+- Release (#3, 30m)
+
+### Notes
+
+- Ideas (edited)
+");
+        assert_eq!(render_markdown(&[], since, now), "# Summary since Tue 2026-10-20\n\nNo activity.\n");
+    }
+}
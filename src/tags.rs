@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use crate::error::TedoError;
+use crate::storage;
+use crate::storage::save_state;
+
+
+// Tags are stored lowercase, without a leading '#' and without duplicates
+pub fn update_tags(tags: &mut Vec<String>, changes: &[&str], remove: bool) {
+    for tag in changes {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if tag.is_empty() {
+            continue;
+        }
+        if remove {
+            tags.retain(|t| *t != tag);
+        } else if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+}
+
+
+pub fn tag_task(base_dir: &Path, project: Option<&str>, id: u32, tags: &[&str], remove: bool) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let task = tedo_state.scoped_project_mut(project)?.task_mut(id)?;
+
+    update_tags(&mut task.tags, tags, remove);
+    save_state(base_dir, &tedo_state)
+}
+
+
+pub fn tag_note(base_dir: &Path, project: Option<&str>, id: u32, tags: &[&str], remove: bool) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let note = tedo_state.scoped_project_mut(project)?.note_mut(id)?;
+
+    update_tags(&mut note.tags, tags, remove);
+    save_state(base_dir, &tedo_state)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_tags() {
+        let mut tags = vec!["ops".to_string()];
+
+        update_tags(&mut tags, &["#Billing", "ops", " ", "urgent"], false);
+        assert_eq!(tags, vec!["ops", "billing", "urgent"]);
+
+        update_tags(&mut tags, &["OPS", "missing"], true);
+        assert_eq!(tags, vec!["billing", "urgent"]);
+    }
+}
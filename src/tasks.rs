@@ -1,83 +1,403 @@
 use std::path::Path;
 
-use crate::projects::current_project;
-use crate::storage;
+use crate::error::TedoError;
+use crate::{dates, editor, front_matter, storage, tags};
 use crate::storage::{Project, save_state};
-use crate::storage::Task;
+use crate::storage::{Task, TaskRef, TaskStatus};
 
 use prettytable::row;
 
 
-pub fn create_task(base_dir: &Path, description: &str) {
-    let mut tedo_state = storage::load_state(base_dir).unwrap_or_default();
-    let current_project_name = tedo_state.current_project.clone().unwrap_or_default();
+impl Task {
+    pub fn new(id: u32, description: &str) -> Task {
+        Task {
+            id,
+            description: description.into(),
+            status: TaskStatus::Open,
+            parent: None,
+            depends_on: Vec::new(),
+            notes: Vec::new(),
+            tags: Vec::new(),
+            time_entries: Vec::new(),
+            focus_sessions: Vec::new(),
+            due: None,
+            scheduled: None,
+            reminder: None,
+            reminded: false,
+            recurrence: None,
+            template: None,
+            uid: None,
+            created: None,
+            modified: None,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.status == TaskStatus::Done
+    }
+}
+
+
+// Returns the id of the new task
+// Shorthand for tests, the CLI fills in the task before adding it
+#[cfg(test)]
+pub fn create_task(base_dir: &Path, project: Option<&str>, description: &str, parent: Option<u32>) -> Result<u32, TedoError> {
+    let mut task = Task::new(0, description);
+    task.parent = parent;
+    add_task(base_dir, project, task)
+}
+
+
+// Adds a task with its fields already filled in, e.g. the due date and tags
+// given to `tedo create task`, in a single save. Returns the id it gets.
+pub fn add_task(base_dir: &Path, project: Option<&str>, mut task: Task) -> Result<u32, TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    if let Some(parent) = task.parent {
+        project.task(parent)?;
+    }
+
+    task.id = project.allocate_task_id();
+    let id = task.id;
+    project.tasks.push(task);
+    save_state(base_dir, &tedo_state)?;
+    Ok(id)
+}
+
+
+pub fn delete_task(base_dir: &Path, project: Option<&str>, id: u32) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    let parent = project.task(id)?.parent;
+    // The id stays taken
+    project.next_task_id = project.task_id_bound();
+    project.tasks.retain(|t| t.id != id);
+
+    // Subtasks of a deleted task move up one level
+    for task in project.tasks.iter_mut().filter(|t| t.parent == Some(id)) {
+        task.parent = parent;
+    }
+
+    // and tasks in any project no longer wait for it
+    let deleted = TaskRef { project: project.id, task: id };
+    for task in tedo_state.projects.iter_mut().flat_map(|p| p.tasks.iter_mut()) {
+        task.depends_on.retain(|t| *t != deleted);
+    }
+    save_state(base_dir, &tedo_state)
+}
 
-    let project = tedo_state.projects.iter_mut().find(|p| p.name == current_project_name);
 
-    if let Some(project) = project {
-        let next_id = project.tasks.len() as u32 + 1;
-        project.tasks.push(Task { id: next_id, description: description.into() });
-        save_state(base_dir, &tedo_state).expect("Failed to save task");
-    } else {
-        println!("No selected project. Please switch to a project before creating a task.");
+// Opens the task's description, status and tags in the editor as front-matter
+pub fn edit_task(base_dir: &Path, project: Option<&str>, id: u32) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+    let task = project.task(id)?;
+
+    let text = front_matter::with_front_matter(&[
+        ("title", task.description.clone()),
+        ("status", status_label(task, &[]).to_string()),
+        ("tags", front_matter::format_list(&task.tags)),
+        ("due", task.due.map(|due| due.to_string()).unwrap_or_default()),
+        ("scheduled", task.scheduled.map(|scheduled| scheduled.to_string()).unwrap_or_default()),
+        ("reminder", task.reminder.map(|at| at.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default()),
+    ], "");
+    let edited = match editor::edit_text(base_dir, &text)? {
+        Some(edited) => edited,
+        None => {
+            println!("No changes.");
+            return Ok(());
+        }
+    };
+
+    let (fields, _) = front_matter::split_front_matter(&edited);
+    let status = match front_matter::field(&fields, "status") {
+        Some("open") | None => TaskStatus::Open,
+        Some("done") => TaskStatus::Done,
+        Some(status) => return Err(TedoError::InvalidInput(format!("Unknown task status {}, use open or done", status))),
+    };
+    if status == TaskStatus::Done && project.subtasks(id).iter().any(|t| !t.is_done()) {
+        return Err(TedoError::InvalidInput(format!("Task {} has open subtasks and cannot be closed.", id)));
+    }
+    // Dates are left empty to remove them
+    let now = chrono::Local::now().naive_local();
+    let date = |key: &str| front_matter::field(&fields, key).filter(|value| !value.is_empty())
+        .map(|value| dates::parse_due_date(value, now.date())).transpose();
+    let (due, scheduled) = (date("due")?, date("scheduled")?);
+    let reminder = front_matter::field(&fields, "reminder").filter(|value| !value.is_empty())
+        .map(|value| dates::parse_datetime(value, now)).transpose()?;
+
+    let task = project.task_mut(id)?;
+    if let Some(title) = front_matter::field(&fields, "title").filter(|title| !title.is_empty()) {
+        task.description = title.to_string();
+    }
+    if let Some(tags) = front_matter::field(&fields, "tags") {
+        task.tags.clear();
+        let tags = front_matter::parse_list(tags);
+        tags::update_tags(&mut task.tags, &tags.iter().map(String::as_str).collect::<Vec<&str>>(), false);
+    }
+    task.due = due;
+    task.scheduled = scheduled;
+    if task.reminder != reminder {
+        task.reminder = reminder;
+        task.reminded = false;
     }
+    let closed = task.status == TaskStatus::Open && status == TaskStatus::Done;
+    let reopened = task.status == TaskStatus::Done && status == TaskStatus::Open;
+    task.status = status;
+    if closed {
+        project.generate_next_instances(&[id], dates::today());
+    }
+    if reopened {
+        project.reopen(id)?;
+    }
+    save_state(base_dir, &tedo_state)
+}
+
+
+// A parent task can only be closed once all of its subtasks are done. Forcing
+// the close closes the open subtasks along with it.
+pub fn close_task(base_dir: &Path, project: Option<&str>, id: u32, force: bool) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    if project.task(id)?.is_template() {
+        return Err(TedoError::InvalidInput(format!(
+            "Task {} is a recurring task template, delete it to stop the recurrence.", id
+        )));
+    }
+    let open_subtasks: Vec<u32> = project.subtasks(id).iter()
+        .filter(|t| !t.is_done())
+        .map(|t| t.id)
+        .collect();
+
+    if !open_subtasks.is_empty() && !force {
+        return Err(TedoError::InvalidInput(format!(
+            "Task {} has {} open subtask(s). Close them first or use --force.", id, open_subtasks.len()
+        )));
+    }
+
+    let mut closed = Vec::new();
+    for task in project.tasks.iter_mut().filter(|t| t.id == id || open_subtasks.contains(&t.id)) {
+        if !task.is_done() {
+            closed.push(task.id);
+        }
+        task.status = TaskStatus::Done;
+    }
+    project.generate_next_instances(&closed, dates::today());
+    save_state(base_dir, &tedo_state)
+}
+
+
+pub fn reopen_task(base_dir: &Path, project: Option<&str>, id: u32) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    tedo_state.scoped_project_mut(project)?.reopen(id)?;
+    save_state(base_dir, &tedo_state)
+}
+
+
+// Moves a task (with its subtasks) under a new parent, or to the top level
+// when no parent is given.
+pub fn move_task(base_dir: &Path, project: Option<&str>, id: u32, parent: Option<u32>) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let project = tedo_state.scoped_project_mut(project)?;
+
+    project.task(id)?;
+    if let Some(parent) = parent {
+        project.task(parent)?;
+        if parent == id || project.subtasks(id).iter().any(|t| t.id == parent) {
+            return Err(TedoError::InvalidInput(format!(
+                "Task {} cannot be moved under its own subtask {}", id, parent
+            )));
+        }
+    }
+
+    project.task_mut(id)?.parent = parent;
+    save_state(base_dir, &tedo_state)
 }
 
 
 impl Project {
-    pub fn list_tasks(&self, mode: &str) {
-        if mode == "table" {
-            // println!("+ {:^10} + {:^40} +", "----------", "---------------------------------------");
-            // println!("| {:^10} | {:^40} |", "ID", "Description");
-            // println!("| {:^10} | {:^40} |", "----------", "---------------------------------------");
-            // for task in &self.tasks {
-            //     println!("| {:^10} | {:^40} |", task.id, task.description);
-            // }
-            // println!("+ {:^10} + {:^40} +", "----------", "---------------------------------------");
+    pub fn task(&self, id: u32) -> Result<&Task, TedoError> {
+        self.tasks.iter().find(|t| t.id == id)
+            .ok_or_else(|| TedoError::NotFound(format!("Task with id {} not found.", id)))
+    }
 
-            // Use prettytable crate
+    pub fn task_mut(&mut self, id: u32) -> Result<&mut Task, TedoError> {
+        self.tasks.iter_mut().find(|t| t.id == id)
+            .ok_or_else(|| TedoError::NotFound(format!("Task with id {} not found.", id)))
+    }
+
+    pub fn children(&self, id: u32) -> Vec<&Task> {
+        self.tasks.iter().filter(|t| t.parent == Some(id)).collect()
+    }
 
+    // Closed parents are reopened too, as a parent can't be done while one of
+    // its subtasks is open
+    pub fn reopen(&mut self, id: u32) -> Result<(), TedoError> {
+        let mut parent = self.task(id)?.parent;
+        self.task_mut(id)?.status = TaskStatus::Open;
+        let mut visited = vec![id];
+        // Guard against cycles in hand-edited or merged state files
+        while let Some(parent_id) = parent.filter(|parent_id| !visited.contains(parent_id)) {
+            visited.push(parent_id);
+            let task = match self.tasks.iter_mut().find(|t| t.id == parent_id) {
+                Some(task) => task,
+                None => break,
+            };
+            if task.is_done() {
+                task.status = TaskStatus::Open;
+                println!("Reopened parent task {} '{}'", task.id, task.description);
+            }
+            parent = task.parent;
+        }
+        Ok(())
+    }
+
+    // All tasks below the given task, at any depth
+    pub fn subtasks(&self, id: u32) -> Vec<&Task> {
+        let mut subtasks = Vec::new();
+        let mut pending = vec![id];
+        while let Some(current) = pending.pop() {
+            for child in self.children(current) {
+                // Guard against cycles in hand-edited state files
+                if child.id != id && !subtasks.iter().any(|t: &&Task| t.id == child.id) {
+                    pending.push(child.id);
+                    subtasks.push(child);
+                }
+            }
+        }
+        subtasks
+    }
+
+    // (done, total) over all subtasks, or None for a task without subtasks
+    pub fn progress(&self, id: u32) -> Option<(usize, usize)> {
+        let subtasks = self.subtasks(id);
+        if subtasks.is_empty() {
+            return None;
+        }
+        Some((subtasks.iter().filter(|t| t.is_done()).count(), subtasks.len()))
+    }
 
+    // Tasks in depth-first order along with their depth in the hierarchy.
+    // Tasks whose parent no longer exists are shown at the top level, as are
+    // tasks caught in a parent cycle. Recurring task templates are left out.
+    pub fn task_tree(&self) -> Vec<(usize, &Task)> {
+        let mut tree = Vec::new();
+        let roots = self.tasks.iter()
+            .filter(|t| t.parent.is_none_or(|parent| self.task(parent).is_err()));
+        for root in roots.chain(&self.tasks).filter(|t| !t.is_template()) {
+            self.push_subtree(root, 0, &mut tree);
+        }
+        tree
+    }
+
+    fn push_subtree<'a>(&'a self, task: &'a Task, depth: usize, tree: &mut Vec<(usize, &'a Task)>) {
+        if tree.iter().any(|(_, t)| t.id == task.id) {
+            return;
+        }
+        tree.push((depth, task));
+        for child in self.children(task.id) {
+            self.push_subtree(child, depth + 1, tree);
+        }
+    }
+
+    // Blocked tasks are marked as such, or left out entirely with `hide_blocked`
+    pub fn list_tasks(&self, mode: &str, blocked: &[u32], hide_blocked: bool) {
+        let tree: Vec<(usize, &Task)> = self.task_tree().into_iter()
+            .filter(|(_, t)| !(hide_blocked && blocked.contains(&t.id)))
+            .collect();
+
+        if mode == "table" {
             let mut table = prettytable::Table::new();
-            table.add_row(row!["ID", "Description"]);
-            for task in &self.tasks {
-                table.add_row(row![task.id, task.description]);
+            table.add_row(row!["ID", "Description", "Status", "Progress", "Due"]);
+            for (depth, task) in tree {
+                table.add_row(row![
+                    task.id,
+                    format!("{}{}", "  ".repeat(depth), task.description),
+                    status_label(task, blocked),
+                    progress_label(self.progress(task.id)),
+                    task.due.map(|due| due.to_string()).unwrap_or_default(),
+                ]);
             }
             table.printstd();
 
 
             return;
         }
-        for task in &self.tasks {
-            println!("{} {}", task.id, task.description);
+        for (depth, task) in tree {
+            let marker = if task.is_done() { "[x]" } else { "[ ]" };
+            let progress = match self.progress(task.id) {
+                Some(progress) => format!(" ({})", progress_label(Some(progress))),
+                None => String::new(),
+            };
+            let blocked = if !task.is_done() && blocked.contains(&task.id) { " (blocked)" } else { "" };
+            let due = match task.due {
+                Some(due) if !task.is_done() => format!(" (due {})", due),
+                _ => String::new(),
+            };
+            println!("{}{} {} {}{}{}{}", "  ".repeat(depth), marker, task.id, task.description, progress, due, blocked);
         }
     }
 }
 
 
-pub fn list_tasks(base_dir: &Path, mode: &str) {
+pub fn list_project_tasks(base_dir: &Path, project: Option<&str>, mode: &str, hide_blocked: bool) -> Result<(), TedoError> {
+    let tedo_state = storage::load_state(base_dir)?;
+    let project_index = tedo_state.scoped_project_index(project)?;
+    let blocked = tedo_state.blocked_tasks(project_index);
+
+    tedo_state.projects[project_index].list_tasks(mode, &blocked, hide_blocked);
+    Ok(())
+}
+
+
+fn status_label(task: &Task, blocked: &[u32]) -> &'static str {
+    match task.status {
+        TaskStatus::Open if blocked.contains(&task.id) => "blocked",
+        TaskStatus::Open => "open",
+        TaskStatus::Done => "done",
+    }
+}
+
+
+fn progress_label(progress: Option<(usize, usize)>) -> String {
+    match progress {
+        Some((done, total)) => format!("{}/{}", done, total),
+        None => String::new(),
+    }
+}
+
+
+// The tasks of all projects, or of one project and its sub-projects
+pub fn list_tasks(base_dir: &Path, project: Option<&str>, mode: &str) -> Result<(), TedoError> {
 
-    let tedo_state = storage::load_state(base_dir).unwrap_or_default();
-    let all_tasks: Vec<&Task> = tedo_state.projects.iter().flat_map(|p| &p.tasks).collect();
+    let mut tedo_state = storage::load_state(base_dir)?;
+    tedo_state.narrow_to_subtree(project)?;
+    let all_tasks: Vec<(&Project, &Task)> = tedo_state.projects.iter()
+        .flat_map(|p| p.tasks.iter().map(move |t| (p, t)))
+        .filter(|(_, t)| !t.is_template())
+        .collect();
     println!("{} tasks found", all_tasks.len());
-    println!("");
+    println!();
     // Display table or list of tasks including project identifier, name and shorthand
 
     if mode == "table" {
         let mut table = prettytable::Table::new();
         table.add_row(row!["ID", "Description", "Project"]);
-        for task in all_tasks {
-            let project = tedo_state.projects.iter().find(|p| p.tasks.contains(task)).unwrap();
+        for (project, task) in all_tasks {
             table.add_row(row![task.id, task.description, project.name]);
         }
         table.printstd();
-        return;
+        return Ok(());
     }
 
-    for task in all_tasks {
-        let project = tedo_state.projects.iter().find(|p| p.tasks.contains(task)).unwrap();
+    for (project, task) in all_tasks {
         println!("{}\t{}\t{}", project.name, task.id, task.description);
     }
+    Ok(())
 }
 
 
@@ -95,10 +415,10 @@ mod tests {
         let dir = tempdir().unwrap();
         let base_dir = dir.path();
 
-        create_project(base_dir, "test_project", false);
-        switch_project(base_dir, "test_project");
+        create_project(base_dir, "test_project", false).unwrap();
+        switch_project(base_dir, "test_project").unwrap();
 
-        create_task(base_dir, "test_task");
+        create_task(base_dir, None, "test_task", None).unwrap();
         let state = storage::load_state(base_dir).unwrap();
 
         assert_eq!(state.projects.len(), 1);
@@ -113,9 +433,9 @@ mod tests {
         let dir = tempdir().unwrap();
         let base_dir = dir.path();
 
-        create_project(base_dir, "test_project", false);
+        create_project(base_dir, "test_project", false).unwrap();
 
-        create_task(base_dir, "test_task");
+        assert!(matches!(create_task(base_dir, None, "test_task", None), Err(TedoError::NoContext)));
         let state = storage::load_state(base_dir).unwrap();
 
         assert_eq!(state.projects.len(), 1);
@@ -127,11 +447,11 @@ mod tests {
         let dir = tempdir().unwrap();
         let base_dir = dir.path();
 
-        create_project(base_dir, "test_project", false);
-        switch_project(base_dir, "test_project");
+        create_project(base_dir, "test_project", false).unwrap();
+        switch_project(base_dir, "test_project").unwrap();
 
-        create_task(base_dir, "test_task_1");
-        create_task(base_dir, "test_task_2");
+        create_task(base_dir, None, "test_task_1", None).unwrap();
+        create_task(base_dir, None, "test_task_2", None).unwrap();
         let state = storage::load_state(base_dir).unwrap();
         assert_eq!(state.projects[0].tasks.len(), 2);
         assert_eq!(state.projects[0].tasks[0].description, "test_task_1");
@@ -144,12 +464,12 @@ mod tests {
         let dir = tempdir().unwrap();
         let base_dir = dir.path();
 
-        create_project(base_dir, "test_project", false);
-        switch_project(base_dir, "test_project");
+        create_project(base_dir, "test_project", false).unwrap();
+        switch_project(base_dir, "test_project").unwrap();
 
-        create_task(base_dir, "test_task_1");
-        create_task(base_dir, "test_task_2");
-        create_task(base_dir, "test_task_3");
+        create_task(base_dir, None, "test_task_1", None).unwrap();
+        create_task(base_dir, None, "test_task_2", None).unwrap();
+        create_task(base_dir, None, "test_task_3", None).unwrap();
         let state = storage::load_state(base_dir).unwrap();
 
         assert_eq!(state.projects[0].tasks.len(), 3);
@@ -157,4 +477,193 @@ mod tests {
         assert_eq!(state.projects[0].tasks[1].description, "test_task_2");
         assert_eq!(state.projects[0].tasks[2].description, "test_task_3");
     }
+
+    #[test]
+    fn test_create_task_in_scoped_project() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_project(base_dir, "other_project", false).unwrap();
+
+        create_task(base_dir, Some("other_project"), "test_task", None).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+
+        assert_eq!(state.current_project.unwrap(), "test_project");
+        assert_eq!(state.projects[0].tasks.len(), 0);
+        assert_eq!(state.projects[1].tasks.len(), 1);
+    }
+
+    #[test]
+    fn test_delete_task_does_not_reuse_ids() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_task(base_dir, None, "test_task_1", None).unwrap();
+        create_task(base_dir, None, "test_task_2", None).unwrap();
+
+        delete_task(base_dir, None, 1).unwrap();
+        create_task(base_dir, None, "test_task_3", None).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+
+        let ids: Vec<u32> = state.projects[0].tasks.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        assert!(matches!(delete_task(base_dir, None, 1), Err(TedoError::NotFound(_))));
+
+        // Not even the highest id
+        delete_task(base_dir, None, 3).unwrap();
+        create_task(base_dir, None, "test_task_4", None).unwrap();
+        crate::notes::create_note(base_dir, None, "note_1", "").unwrap();
+        crate::notes::delete_note(base_dir, None, 1).unwrap();
+        crate::notes::create_note(base_dir, None, "note_2", "").unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].tasks.iter().map(|t| t.id).collect::<Vec<u32>>(), vec![2, 4]);
+        assert_eq!(state.projects[0].notes[0].id, 2);
+    }
+
+    #[test]
+    fn test_subtask_tree_and_progress() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_task(base_dir, None, "parent", None).unwrap();
+        create_task(base_dir, None, "other", None).unwrap();
+        create_task(base_dir, None, "child_1", Some(1)).unwrap();
+        create_task(base_dir, None, "child_2", Some(1)).unwrap();
+        create_task(base_dir, None, "grandchild", Some(4)).unwrap();
+        close_task(base_dir, None, 5, false).unwrap();
+
+        let project = storage::load_state(base_dir).unwrap().projects.remove(0);
+        let tree: Vec<(usize, u32)> = project.task_tree().iter().map(|(depth, t)| (*depth, t.id)).collect();
+        assert_eq!(tree, vec![(0, 1), (1, 3), (1, 4), (2, 5), (0, 2)]);
+        assert_eq!(project.progress(1), Some((1, 3)));
+        assert_eq!(project.progress(4), Some((1, 1)));
+        assert_eq!(project.progress(2), None);
+    }
+
+    #[test]
+    fn test_create_task_with_missing_parent() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        assert!(matches!(create_task(base_dir, None, "child", Some(7)), Err(TedoError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_close_parent_with_open_subtasks() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_task(base_dir, None, "parent", None).unwrap();
+        create_task(base_dir, None, "child", Some(1)).unwrap();
+
+        assert!(matches!(close_task(base_dir, None, 1, false), Err(TedoError::InvalidInput(_))));
+        let state = storage::load_state(base_dir).unwrap();
+        assert!(!state.projects[0].tasks[0].is_done());
+
+        close_task(base_dir, None, 1, true).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert!(state.projects[0].tasks.iter().all(|t| t.is_done()));
+
+        reopen_task(base_dir, None, 1).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert!(!state.projects[0].tasks[0].is_done());
+    }
+
+    #[test]
+    fn test_reopen_subtask_reopens_parents() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_task(base_dir, None, "parent", None).unwrap();
+        create_task(base_dir, None, "child", Some(1)).unwrap();
+        create_task(base_dir, None, "grandchild", Some(2)).unwrap();
+        create_task(base_dir, None, "sibling", Some(1)).unwrap();
+        close_task(base_dir, None, 1, true).unwrap();
+
+        reopen_task(base_dir, None, 3).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        let open: Vec<u32> = state.projects[0].tasks.iter().filter(|t| !t.is_done()).map(|t| t.id).collect();
+        assert_eq!(open, vec![1, 2, 3]);
+        // and stays open until its subtasks are closed
+        assert!(close_task(base_dir, None, 1, false).is_err());
+
+        // Parents that lead back to the task, e.g. after a merge
+        let mut state = storage::load_state(base_dir).unwrap();
+        state.projects[0].tasks[0].parent = Some(3);
+        state.projects[0].tasks.iter_mut().for_each(|t| t.status = TaskStatus::Done);
+        save_state(base_dir, &state).unwrap();
+        reopen_task(base_dir, None, 3).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].tasks.iter().filter(|t| !t.is_done()).count(), 3);
+    }
+
+    #[test]
+    fn test_move_task() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_task(base_dir, None, "parent", None).unwrap();
+        create_task(base_dir, None, "child", Some(1)).unwrap();
+        create_task(base_dir, None, "grandchild", Some(2)).unwrap();
+
+        assert!(move_task(base_dir, None, 1, Some(3)).is_err());
+        assert!(move_task(base_dir, None, 1, Some(1)).is_err());
+
+        move_task(base_dir, None, 3, Some(1)).unwrap();
+        move_task(base_dir, None, 2, None).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].tasks[1].parent, None);
+        assert_eq!(state.projects[0].tasks[2].parent, Some(1));
+    }
+
+    #[test]
+    fn test_delete_task_moves_subtasks_up() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_task(base_dir, None, "parent", None).unwrap();
+        create_task(base_dir, None, "child", Some(1)).unwrap();
+        create_task(base_dir, None, "grandchild", Some(2)).unwrap();
+
+        delete_task(base_dir, None, 2).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].tasks[1].parent, Some(1));
+    }
+
+    #[test]
+    fn test_edit_task_front_matter() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "test_project", true).unwrap();
+        create_task(base_dir, None, "old_task", None).unwrap();
+        create_task(base_dir, None, "child", Some(1)).unwrap();
+
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i s/open/done/")).unwrap();
+        assert!(matches!(edit_task(base_dir, None, 1), Err(TedoError::InvalidInput(_))));
+        edit_task(base_dir, None, 2).unwrap();
+
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i s/old_task/new_task/")).unwrap();
+        edit_task(base_dir, None, 1).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].tasks[0].description, "new_task");
+        assert!(!state.projects[0].tasks[0].is_done());
+        assert!(state.projects[0].tasks[1].is_done());
+
+        // Reopening the subtask reopens its parent, as `tedo reopen` does
+        close_task(base_dir, None, 1, false).unwrap();
+        crate::config::configure(base_dir, Some("editor"), Some("sed -i s/done/open/")).unwrap();
+        edit_task(base_dir, None, 2).unwrap();
+        let state = storage::load_state(base_dir).unwrap();
+        assert!(state.projects[0].tasks.iter().all(|t| !t.is_done()));
+    }
 }
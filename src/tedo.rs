@@ -1,61 +1,88 @@
 use std::path::Path;
-use crate::projects::current_project;
+use crate::error::TedoError;
+use crate::projects::{shorthands, tree_order};
 use crate::storage;
+use crate::storage::{Project, ProjectStatus};
+use crate::{dates, focus};
+use crate::time_tracking::format_duration;
 
 use prettytable::row;
 use colored::Colorize;
 
 
-pub fn list(base_dir: &Path) {
-    let project = current_project(base_dir).unwrap();
-    let projects = storage::load_state(base_dir).unwrap();
+// Archived projects are left out unless `archived` is set
+pub fn list(base_dir: &Path, archived: bool) -> Result<(), TedoError> {
+    let projects = storage::load_state(base_dir)?;
+    let current_project = projects.current_project_index().ok().map(|index| &projects.projects[index]);
+    let shown = |index: usize| archived || !projects.is_archived_within(index);
+    let listed: Vec<&Project> = (0..projects.projects.len()).filter(|&index| shown(index)).map(|index| &projects.projects[index]).collect();
 
-    let total_tasks = projects.projects.iter().map(|p| p.tasks.len()).sum::<usize>();
-    let total_notes = projects.projects.iter().map(|p| p.notes.len()).sum::<usize>();
+    let total_tasks = listed.iter().map(|p| p.tasks.len()).sum::<usize>();
+    let total_notes = listed.iter().map(|p| p.notes.len()).sum::<usize>();
     // We output in one single line:
     // - Current project
     // - # of projects
     // - total # of tasks
     // - total # of notes
     // Use bold text for the current project
+    let current_project_label = match current_project {
+        Some(project) => format!("({}) {}", project.id, project.name.blue().bold()),
+        None => "none".to_string(),
+    };
     println!("{}", format!("Current project: {} \t # projects: {}  \t # tasks {} \t # notes {} ",
-                format!("({}) {}", project.id , project.name.blue().bold()),
-                projects.projects.len().to_string().blue().bold(),
+                current_project_label,
+                listed.len().to_string().blue().bold(),
                 total_tasks.to_string().blue().bold(),
                 total_notes.to_string().blue().bold()).white().bold()
              );
 
-    // List all projects in a table
+    // Today's focus sessions and the running timer, if any
+    let today = dates::today();
+    if let Some(day) = focus::daily_summary(&projects, today, today).get(&today) {
+        println!("Focus today: {} sessions ({} completed), {}, {} interruptions",
+                 day.sessions, day.completed, format_duration(day.time).bold(), day.interruptions);
+    }
+    if let Some((project_index, task_index)) = projects.running_timer() {
+        let task = &projects.projects[project_index].tasks[task_index];
+        println!("Timer running: task {} '{}' in {}", task.id, task.description, projects.projects[project_index].name.blue());
+    }
+
+    // List all projects in a table, sub-projects indented below their
+    // parent. The counts of a parent include those of its sub-projects.
 
     let mut table = prettytable::Table::new();
 
-    // define a list 'shorthands' that contains the shortest possible unique
-    // letter combination to identify each project in the list
-
-    let mut shorthands: Vec<String> = Vec::new();
-    for project in projects.projects.iter() {
-        let mut shorthand = String::new();
-        for c in project.name.chars() {
-            shorthand.push(c);
-            if !shorthands.contains(&shorthand) {
-                shorthands.push(shorthand.clone());
-                break;
-            }
-        }
-    }
+    // 'shorthands' contains the shortest possible unique letter combination
+    // to identify each project in the list. They are worked out over all
+    // projects, so they stay the same when archived ones are hidden.
+    let shorthands = shorthands(&projects.projects);
 
-    table.add_row(row!["ID", "Project Name", "Tasks", "Notes"]);
-    for project in projects.projects {
-        // table.add_row(row![project.id, project.name, project.tasks.len(), project.notes.len()]);
+    table.add_row(row!["ID", "Project Name", "Status", "Target", "Tasks", "Notes"]);
+    for index in tree_order(&projects.projects).into_iter().filter(|&index| shown(index)) {
+        let project = &projects.projects[index];
         // include the shorthand
-        let mut project_name = format!("({}) {}", shorthands.remove(0), project.name).white();
-        // make bold and white if current project
-
-        if project.id == current_project(base_dir).unwrap().id {
-            project_name = project_name.white().bold()
+        let mut project_name = format!("{}({}) {}", "  ".repeat(project.depth()), shorthands[index], project.colored(project.short_name()));
+        // make bold if current project
+        if current_project.map(|p| p.id) == Some(project.id) {
+            project_name = project_name.bold().to_string()
         }
+        let status = match project.status {
+            ProjectStatus::Active => project.status.label().normal(),
+            ProjectStatus::Paused => project.status.label().yellow(),
+            ProjectStatus::Archived => project.status.label().dimmed(),
+        };
+        let target = project.target.map(|target| target.to_string()).unwrap_or_default();
+        let subtree: Vec<&Project> = projects.subtree(index).into_iter().filter(|&index| shown(index)).map(|index| &projects.projects[index]).collect();
+        let tasks = subtree.iter().map(|p| p.tasks.len()).sum::<usize>();
+        let notes = subtree.iter().map(|p| p.notes.len()).sum::<usize>();
 
-        table.add_row(row![project.id, project_name, project.tasks.len(), project.notes.len()]);
+        table.add_row(row![project.id, project_name, status, target, tasks, notes]);
     }
     table.printstd();
-}
\ No newline at end of file
+
+    let hidden = projects.projects.len() - listed.len();
+    if hidden > 0 {
+        println!("{}", format!("{} archived project(s) hidden, use `tedo list --archived` to show them.", hidden).dimmed());
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::editor;
+use crate::error::TedoError;
+
+
+// Templates are Markdown files in ~/.tedo/templates, e.g. `meeting.md`. They
+// may contain the placeholders {{title}}, {{project}}, {{date}} and {{time}}.
+
+pub fn templates_dir(base_dir: &Path) -> PathBuf {
+    base_dir.join("templates")
+}
+
+
+fn template_path(base_dir: &Path, name: &str) -> Result<PathBuf, TedoError> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(TedoError::InvalidInput(format!("Invalid template name {}", name)));
+    }
+    Ok(templates_dir(base_dir).join(format!("{}.md", name)))
+}
+
+
+pub fn template_names(base_dir: &Path) -> Result<Vec<String>, TedoError> {
+    let entries = match fs::read_dir(templates_dir(base_dir)) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "md") {
+            names.push(path.file_stem().unwrap().to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+
+pub fn load_template(base_dir: &Path, name: &str) -> Result<String, TedoError> {
+    match fs::read_to_string(template_path(base_dir, name)?) {
+        Ok(template) => Ok(template),
+        Err(error) if error.kind() == ErrorKind::NotFound => Err(TedoError::NotFound(format!(
+            "Template {} not found. Create it with `tedo template edit {}`", name, name
+        ))),
+        Err(error) => Err(error.into()),
+    }
+}
+
+
+// Replaces `{{name}}` placeholders. Unknown placeholders are left alone.
+pub fn render(template: &str, variables: &[(&str, String)]) -> String {
+    let mut output = template.to_string();
+    for (name, value) in variables {
+        output = output.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    output
+}
+
+
+pub fn note_variables(title: &str, project: &str) -> Vec<(&'static str, String)> {
+    let now = chrono::Local::now();
+    vec![
+        ("title", title.to_string()),
+        ("project", project.to_string()),
+        ("date", now.format("%Y-%m-%d").to_string()),
+        ("time", now.format("%H:%M").to_string()),
+    ]
+}
+
+
+pub fn list_templates(base_dir: &Path) -> Result<(), TedoError> {
+    let names = template_names(base_dir)?;
+    if names.is_empty() {
+        println!("No templates yet. Create one with `tedo template edit <name>`.");
+    }
+    for name in names {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+
+// Opens a template in the editor, creating it when it doesn't exist yet
+pub fn edit_template(base_dir: &Path, name: &str) -> Result<(), TedoError> {
+    let path = template_path(base_dir, name)?;
+    let template = match fs::read_to_string(&path) {
+        Ok(template) => template,
+        Err(error) if error.kind() == ErrorKind::NotFound => "# {{title}}\n\n{{date}}\n".to_string(),
+        Err(error) => return Err(error.into()),
+    };
+
+    let edited = match editor::edit_text(base_dir, &template)? {
+        Some(edited) => edited,
+        None if path.exists() => {
+            println!("No changes.");
+            return Ok(());
+        }
+        None => template,
+    };
+    fs::create_dir_all(templates_dir(base_dir))?;
+    fs::write(path, edited)?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::config;
+
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let template = "# {{title}}\nProject: {{project}}\n{{unknown}} {{ title }}";
+        let rendered = render(template, &[("title", "Sprint review".into()), ("project", "growth".into())]);
+        assert_eq!(rendered, "# Sprint review\nProject: growth\n{{unknown}} {{ title }}");
+    }
+
+    #[test]
+    fn test_note_variables_include_date() {
+        let variables = note_variables("Title", "growth");
+        let date = &variables.iter().find(|(name, _)| *name == "date").unwrap().1;
+        assert!(chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+    }
+
+    #[test]
+    fn test_edit_and_list_templates() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        assert!(template_names(base_dir).unwrap().is_empty());
+        assert!(matches!(load_template(base_dir, "meeting"), Err(TedoError::NotFound(_))));
+        assert!(edit_template(base_dir, "../escape").is_err());
+
+        config::configure(base_dir, Some("editor"), Some("sed -i s/date/project/")).unwrap();
+        edit_template(base_dir, "meeting").unwrap();
+        config::configure(base_dir, Some("editor"), Some("true")).unwrap();
+        edit_template(base_dir, "incident").unwrap();
+
+        assert_eq!(template_names(base_dir).unwrap(), vec!["incident", "meeting"]);
+        assert_eq!(load_template(base_dir, "meeting").unwrap(), "# {{title}}\n\n{{project}}\n");
+        assert_eq!(load_template(base_dir, "incident").unwrap(), "# {{title}}\n\n{{date}}\n");
+    }
+}
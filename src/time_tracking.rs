@@ -0,0 +1,341 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use prettytable::row;
+
+use crate::error::TedoError;
+use crate::storage;
+use crate::storage::{save_state, Task, TedoState, TimeEntry};
+
+
+// Time is tracked as intervals on tasks, either with a timer (`tedo start` /
+// `tedo stop`) or logged by hand. Only one timer runs at a time.
+
+impl TimeEntry {
+    // A running timer counts up to now
+    pub fn duration(&self, now: NaiveDateTime) -> Duration {
+        self.end.unwrap_or(now) - self.start
+    }
+}
+
+
+impl Task {
+    pub fn tracked_time(&self, now: NaiveDateTime) -> Duration {
+        self.time_entries.iter().map(|entry| entry.duration(now)).fold(Duration::zero(), |total, duration| total + duration)
+    }
+
+    // Time of the entries started on or after the given day, as in reports
+    pub fn tracked_since(&self, since: NaiveDate, now: NaiveDateTime) -> Duration {
+        self.time_entries.iter()
+            .filter(|entry| entry.start.date() >= since)
+            .map(|entry| entry.duration(now))
+            .fold(Duration::zero(), |total, duration| total + duration)
+    }
+
+    pub fn is_tracking(&self) -> bool {
+        self.time_entries.iter().any(|entry| entry.end.is_none())
+    }
+}
+
+
+impl TedoState {
+    // Project and task index of the task with the running timer
+    pub fn running_timer(&self) -> Option<(usize, usize)> {
+        self.projects.iter().enumerate().find_map(|(project_index, project)| {
+            project.tasks.iter().position(|t| t.is_tracking()).map(|task_index| (project_index, task_index))
+        })
+    }
+}
+
+
+pub fn start_timer(base_dir: &Path, project: Option<&str>, id: u32, now: NaiveDateTime) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    if let Some((project_index, task_index)) = tedo_state.running_timer() {
+        let project = &tedo_state.projects[project_index];
+        return Err(TedoError::InvalidInput(format!(
+            "A timer is already running on task {} '{}' in {}, stop it first with `tedo stop`",
+            project.tasks[task_index].id, project.tasks[task_index].description, project.name
+        )));
+    }
+
+    let task = tedo_state.scoped_project_mut(project)?.task_mut(id)?;
+    if task.is_done() {
+        return Err(TedoError::InvalidInput(format!("Task {} is closed, reopen it to track time", id)));
+    }
+    task.time_entries.push(TimeEntry { start: now, end: None });
+    println!("Started timer on task {} '{}'", task.id, task.description);
+    save_state(base_dir, &tedo_state)
+}
+
+
+pub fn stop_timer(base_dir: &Path, now: NaiveDateTime) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let (project_index, task_index) = tedo_state.running_timer()
+        .ok_or_else(|| TedoError::InvalidInput("No timer is running".into()))?;
+
+    let task = &mut tedo_state.projects[project_index].tasks[task_index];
+    let entry = task.time_entries.iter_mut().find(|entry| entry.end.is_none()).unwrap();
+    entry.end = Some(now.max(entry.start));
+    println!("Stopped timer on task {} '{}' after {}", task.id, task.description, format_duration(entry.duration(now)));
+    save_state(base_dir, &tedo_state)
+}
+
+
+// The entry ends at the current time of day on the given date, without
+// reaching back into the previous day
+pub fn log_time(base_dir: &Path, project: Option<&str>, id: u32, duration: Duration, date: NaiveDate, now: NaiveDateTime) -> Result<(), TedoError> {
+    if duration <= Duration::zero() {
+        return Err(TedoError::InvalidInput("The duration has to be positive".into()));
+    }
+    let mut tedo_state = storage::load_state(base_dir)?;
+    let task = tedo_state.scoped_project_mut(project)?.task_mut(id)?;
+
+    let day_start = date.and_time(NaiveTime::MIN);
+    let start = date.and_time(now.time()).checked_sub_signed(duration).map_or(day_start, |start| start.max(day_start));
+    let end = start.checked_add_signed(duration)
+        .ok_or_else(|| TedoError::InvalidInput(format!("The duration {} is too long", format_duration(duration))))?;
+    task.time_entries.push(TimeEntry { start, end: Some(end) });
+    save_state(base_dir, &tedo_state)
+}
+
+
+// Accepts e.g. `1h30m`, `2h`, `45m`, `1.5h` or plain minutes
+pub fn parse_duration(text: &str) -> Result<Duration, TedoError> {
+    let invalid = || TedoError::InvalidInput(format!("Invalid duration {}, use e.g. 1h30m, 45m or 1.5h", text));
+    let text = text.trim().to_lowercase();
+    if let Ok(minutes) = text.parse::<i64>() {
+        return Duration::try_minutes(minutes).ok_or_else(invalid);
+    }
+
+    let mut minutes = 0.0;
+    let mut number = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' if !number.is_empty() => {
+                let value: f64 = number.parse().map_err(|_| invalid())?;
+                minutes += if c == 'h' { value * 60.0 } else { value };
+                number.clear();
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() || text.is_empty() {
+        return Err(invalid());
+    }
+    let seconds = (minutes * 60.0).round();
+    if !seconds.is_finite() || seconds.abs() >= i64::MAX as f64 {
+        return Err(invalid());
+    }
+    Duration::try_seconds(seconds as i64).ok_or_else(invalid)
+}
+
+
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grouping {
+    Project,
+    Tag,
+    Day,
+}
+
+
+// Time per project, tag or day of the entries that started on or after
+// `since`, plus the total. A task with several tags counts towards each.
+pub fn report_rows(tedo_state: &TedoState, grouping: Grouping, since: Option<NaiveDate>, now: NaiveDateTime) -> (Vec<(String, Duration)>, Duration) {
+    let mut rows: BTreeMap<String, Duration> = BTreeMap::new();
+    let mut total = Duration::zero();
+
+    for project in &tedo_state.projects {
+        for task in &project.tasks {
+            for entry in task.time_entries.iter().filter(|entry| since.is_none_or(|since| entry.start.date() >= since)) {
+                let groups = match grouping {
+                    Grouping::Project => vec![project.name.clone()],
+                    Grouping::Tag if task.tags.is_empty() => vec!["(untagged)".to_string()],
+                    Grouping::Tag => task.tags.clone(),
+                    Grouping::Day => vec![entry.start.date().format("%Y-%m-%d").to_string()],
+                };
+                for group in groups {
+                    *rows.entry(group).or_insert_with(Duration::zero) += entry.duration(now);
+                }
+                total += entry.duration(now);
+            }
+        }
+    }
+    (rows.into_iter().collect(), total)
+}
+
+
+fn hours(duration: Duration) -> String {
+    format!("{:.2}", duration.num_minutes() as f64 / 60.0)
+}
+
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+
+// Reports on all projects unless one is given, then on it and its sub-projects
+pub fn time_report(base_dir: &Path, project: Option<&str>, grouping: Grouping, since: Option<NaiveDate>, csv: bool, now: NaiveDateTime) -> Result<(), TedoError> {
+    let mut tedo_state = storage::load_state(base_dir)?;
+    tedo_state.narrow_to_subtree(project)?;
+
+    let (rows, total) = report_rows(&tedo_state, grouping, since, now);
+    let group = match grouping {
+        Grouping::Project => "project",
+        Grouping::Tag => "tag",
+        Grouping::Day => "day",
+    };
+
+    if csv {
+        println!("{},minutes,hours", group);
+        for (name, duration) in rows {
+            println!("{},{},{}", csv_field(&name), duration.num_minutes(), hours(duration));
+        }
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!("No time tracked{}.", since.map(|since| format!(" since {}", since)).unwrap_or_default());
+        return Ok(());
+    }
+    let mut table = prettytable::Table::new();
+    let mut header = group.to_string();
+    header[..1].make_ascii_uppercase();
+    table.add_row(row![header, "Time", "Hours"]);
+    for (name, duration) in rows {
+        table.add_row(row![name, format_duration(duration), r->hours(duration)]);
+    }
+    table.add_row(row![b->"Total", b->format_duration(total), br->hours(total)]);
+    table.printstd();
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::projects::create_project;
+    use crate::tags::tag_task;
+    use crate::tasks::{close_task, create_task};
+
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_format_durations() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("1.5h").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("45M").unwrap(), Duration::minutes(45));
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(matches!(parse_duration("9999999999999999"), Err(TedoError::InvalidInput(_))));
+        assert!(matches!(parse_duration("99999999999999999999h"), Err(TedoError::InvalidInput(_))));
+        assert!(matches!(parse_duration(&format!("{}h", "9".repeat(400))), Err(TedoError::InvalidInput(_))));
+
+        assert_eq!(format_duration(Duration::minutes(45)), "45m");
+        assert_eq!(format_duration(Duration::minutes(125)), "2h 05m");
+    }
+
+    #[test]
+    fn test_only_one_timer_runs_at_a_time() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "growth", true).unwrap();
+        create_task(base_dir, None, "Write docs", None).unwrap();
+        create_task(base_dir, None, "Review", None).unwrap();
+        create_task(base_dir, None, "Done already", None).unwrap();
+        close_task(base_dir, None, 3, false).unwrap();
+
+        assert!(matches!(stop_timer(base_dir, at("2026-10-19 09:00")), Err(TedoError::InvalidInput(_))));
+        assert!(matches!(start_timer(base_dir, None, 3, at("2026-10-19 09:00")), Err(TedoError::InvalidInput(_))));
+        start_timer(base_dir, None, 1, at("2026-10-19 09:00")).unwrap();
+        assert!(matches!(start_timer(base_dir, None, 2, at("2026-10-19 09:10")), Err(TedoError::InvalidInput(_))));
+
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.running_timer(), Some((0, 0)));
+        assert_eq!(state.projects[0].tasks[0].tracked_time(at("2026-10-19 09:20")), Duration::minutes(20));
+
+        stop_timer(base_dir, at("2026-10-19 10:30")).unwrap();
+        start_timer(base_dir, None, 2, at("2026-10-19 10:30")).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        assert_eq!(state.projects[0].tasks[0].time_entries, vec![TimeEntry { start: at("2026-10-19 09:00"), end: Some(at("2026-10-19 10:30")) }]);
+        assert_eq!(state.running_timer(), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_log_time_by_hand() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "growth", true).unwrap();
+        create_task(base_dir, None, "Write docs", None).unwrap();
+        let now = at("2026-10-19 01:00");
+        log_time(base_dir, None, 1, Duration::minutes(30), now.date(), now).unwrap();
+        log_time(base_dir, None, 1, Duration::minutes(90), NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(), now).unwrap();
+        assert!(log_time(base_dir, None, 1, Duration::zero(), now.date(), now).is_err());
+
+        let entries = &storage::load_state(base_dir).unwrap().projects[0].tasks[0].time_entries;
+        assert_eq!(entries[0], TimeEntry { start: at("2026-10-19 00:30"), end: Some(at("2026-10-19 01:00")) });
+        assert_eq!(entries[1], TimeEntry { start: at("2026-10-16 00:00"), end: Some(at("2026-10-16 01:30")) });
+    }
+
+    #[test]
+    fn test_report_rows() {
+        let dir = tempdir().unwrap();
+        let base_dir = dir.path();
+
+        create_project(base_dir, "growth", true).unwrap();
+        create_project(base_dir, "infra", false).unwrap();
+        create_task(base_dir, None, "Write docs", None).unwrap();
+        create_task(base_dir, None, "Review", None).unwrap();
+        create_task(base_dir, Some("infra"), "Upgrade", None).unwrap();
+        tag_task(base_dir, None, 1, &["docs", "billable"], false).unwrap();
+
+        let now = at("2026-10-21 12:00");
+        log_time(base_dir, None, 1, Duration::minutes(60), NaiveDate::from_ymd_opt(2026, 10, 19).unwrap(), now).unwrap();
+        log_time(base_dir, None, 2, Duration::minutes(30), NaiveDate::from_ymd_opt(2026, 10, 20).unwrap(), now).unwrap();
+        log_time(base_dir, Some("infra"), 1, Duration::minutes(45), NaiveDate::from_ymd_opt(2026, 10, 12).unwrap(), now).unwrap();
+        start_timer(base_dir, Some("infra"), 1, at("2026-10-21 11:00")).unwrap();
+
+        let state = storage::load_state(base_dir).unwrap();
+        let since = NaiveDate::from_ymd_opt(2026, 10, 19);
+        let (rows, total) = report_rows(&state, Grouping::Project, since, now);
+        assert_eq!(rows, vec![("growth".to_string(), Duration::minutes(90)), ("infra".to_string(), Duration::minutes(60))]);
+        assert_eq!(total, Duration::minutes(150));
+
+        let (rows, total) = report_rows(&state, Grouping::Tag, None, now);
+        let names: Vec<&str> = rows.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["(untagged)", "billable", "docs"]);
+        assert_eq!(rows[0].1, Duration::minutes(135));
+        assert_eq!(total, Duration::minutes(195));
+
+        let (rows, _) = report_rows(&state, Grouping::Day, since, now);
+        assert_eq!(rows[0], ("2026-10-19".to_string(), Duration::minutes(60)));
+        assert_eq!(rows.len(), 3);
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+    }
+}